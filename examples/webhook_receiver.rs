@@ -0,0 +1,100 @@
+//! Local stand-in for a merchant's webhook endpoint.
+//!
+//! Register `http://127.0.0.1:4100/` as an endpoint, then run with the secret it was
+//! issued:
+//!
+//!     WEBHOOK_SECRET=whsec_... cargo run --example webhook_receiver
+//!
+//! Every delivery is printed with the outcome of its signature check. Set
+//! `WEBHOOK_RESPOND_WITH=500` to exercise retries and dead-lettering.
+
+use std::{convert::Infallible, net::SocketAddr};
+
+use hmac::{Hmac, Mac};
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server, StatusCode,
+};
+use sha2::Sha256;
+
+const TOLERANCE_SECONDS: i64 = 5 * 60;
+
+// Mirrors bank::models::webhooks::verify_signature, as a receiver would implement it
+fn verify(secret: &str, header: &str, body: &[u8]) -> bool {
+    let mut timestamp = None;
+    let mut signature = None;
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signature = hex::decode(value).ok(),
+            _ => {}
+        }
+    }
+    let (timestamp, signature) = match (timestamp, signature) {
+        (Some(timestamp), Some(signature)) => (timestamp, signature),
+        _ => return false,
+    };
+    if (chrono::Utc::now().timestamp() - timestamp).abs() > TOLERANCE_SECONDS {
+        return false;
+    }
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(format!("{}.", timestamp).as_bytes());
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+async fn handle(request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let secret = std::env::var("WEBHOOK_SECRET").unwrap_or_default();
+    let respond_with = std::env::var("WEBHOOK_RESPOND_WITH")
+        .ok()
+        .and_then(|status| status.parse::<u16>().ok())
+        .and_then(|status| StatusCode::from_u16(status).ok())
+        .unwrap_or(StatusCode::NO_CONTENT);
+
+    let signature = request
+        .headers()
+        .get("x-signature")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let event_type = request
+        .headers()
+        .get("x-event-type")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let body = hyper::body::to_bytes(request.into_body())
+        .await
+        .unwrap_or_default();
+
+    let valid = verify(&secret, &signature, &body);
+    println!(
+        "{} signature={} {}",
+        event_type,
+        if valid { "valid" } else { "INVALID" },
+        String::from_utf8_lossy(&body)
+    );
+
+    let status = if valid {
+        respond_with
+    } else {
+        StatusCode::UNAUTHORIZED
+    };
+    Ok(Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .unwrap())
+}
+
+#[tokio::main]
+async fn main() {
+    let addr = SocketAddr::from(([127, 0, 0, 1], 4100));
+    let make_service = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+
+    println!("webhook receiver listening on http://{}", addr);
+    Server::bind(&addr)
+        .serve(make_service)
+        .await
+        .expect("failed to serve");
+}
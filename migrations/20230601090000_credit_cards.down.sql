@@ -0,0 +1,6 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS credit_statements;
+
+DROP TABLE IF EXISTS credit_accounts;
+
+DROP TYPE IF EXISTS statementstatus;
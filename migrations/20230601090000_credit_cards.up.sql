@@ -0,0 +1,46 @@
+-- Add up migration script here
+CREATE TYPE statementstatus AS ENUM ('open', 'paid', 'minimum_paid', 'overdue');
+
+ALTER TYPE transactiontype ADD VALUE IF NOT EXISTS 'credit_card_charge';
+ALTER TYPE transactiontype ADD VALUE IF NOT EXISTS 'credit_card_payment';
+ALTER TYPE transactiontype ADD VALUE IF NOT EXISTS 'interest_charge';
+ALTER TYPE transactiontype ADD VALUE IF NOT EXISTS 'late_fee';
+
+-- Revolving credit line attached to a credit account and its card
+CREATE TABLE IF NOT EXISTS credit_accounts (
+    id UUID PRIMARY KEY,
+    account_id UUID NOT NULL UNIQUE REFERENCES accounts(id) ON DELETE CASCADE,
+    card_number VARCHAR(255) NOT NULL UNIQUE REFERENCES cards(card_number) ON DELETE CASCADE,
+    credit_limit INTEGER NOT NULL,
+    current_balance INTEGER DEFAULT 0 NOT NULL,
+    -- Day of month (1-28) on which the statement cycle closes
+    statement_day INTEGER NOT NULL CHECK (statement_day BETWEEN 1 AND 28),
+    grace_period_days INTEGER NOT NULL,
+    -- Annual percentage rate in basis points (1950 = 19.50%)
+    apr_bps INTEGER NOT NULL,
+    min_payment_bps INTEGER NOT NULL,
+    min_payment_floor INTEGER NOT NULL,
+    late_fee INTEGER NOT NULL,
+    bank_id UUID NOT NULL REFERENCES banks(id),
+    branch_id UUID NOT NULL REFERENCES branches(id),
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+
+-- One row per closed statement cycle
+CREATE TABLE IF NOT EXISTS credit_statements (
+    id UUID PRIMARY KEY,
+    credit_account_id UUID NOT NULL REFERENCES credit_accounts(id) ON DELETE CASCADE,
+    period_start DATE NOT NULL,
+    period_end DATE NOT NULL,
+    statement_balance INTEGER NOT NULL,
+    minimum_payment_due INTEGER NOT NULL,
+    due_date DATE NOT NULL,
+    amount_paid INTEGER DEFAULT 0 NOT NULL,
+    interest_charged INTEGER DEFAULT 0 NOT NULL,
+    late_fee_charged INTEGER DEFAULT 0 NOT NULL,
+    status statementstatus NOT NULL,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    UNIQUE (credit_account_id, period_end)
+);
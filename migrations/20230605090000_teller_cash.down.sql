@@ -0,0 +1,12 @@
+-- Add down migration script here
+-- Card-less postings are ledger history; refuse to roll back rather than delete them
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM transactions WHERE card_number IS NULL) THEN
+        RAISE EXCEPTION 'transactions without a card number exist; cannot restore NOT NULL on card_number';
+    END IF;
+END
+$$;
+
+ALTER TABLE transactions
+ALTER COLUMN card_number SET NOT NULL;
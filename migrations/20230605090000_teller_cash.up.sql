@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- Teller cash movements are not made with a card
+ALTER TABLE transactions
+ALTER COLUMN card_number DROP NOT NULL;
@@ -0,0 +1,6 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS branch_cash_positions;
+
+DROP TABLE IF EXISTS branch_cash_transfers;
+
+ALTER TABLE banks DROP COLUMN reserve_ratio_bps;
@@ -0,0 +1,27 @@
+-- Add up migration script here
+-- Minimum share of customer deposits a branch must hold in its vault (1000 = 10%)
+ALTER TABLE banks
+ADD COLUMN reserve_ratio_bps integer DEFAULT 1000 NOT NULL;
+
+CREATE TABLE IF NOT EXISTS branch_cash_transfers (
+    id UUID PRIMARY KEY,
+    bank_id UUID NOT NULL REFERENCES banks(id),
+    from_branch_id UUID NOT NULL REFERENCES branches(id),
+    to_branch_id UUID NOT NULL REFERENCES branches(id),
+    amount INTEGER NOT NULL CHECK (amount > 0),
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    CHECK (from_branch_id <> to_branch_id)
+);
+
+-- End-of-day cash position per branch
+CREATE TABLE IF NOT EXISTS branch_cash_positions (
+    id UUID PRIMARY KEY,
+    bank_id UUID NOT NULL REFERENCES banks(id),
+    branch_id UUID NOT NULL REFERENCES branches(id),
+    business_date DATE NOT NULL,
+    total_money INTEGER NOT NULL,
+    customer_deposits BIGINT NOT NULL,
+    required_reserve BIGINT NOT NULL,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    UNIQUE (branch_id, business_date)
+);
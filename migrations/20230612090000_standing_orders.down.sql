@@ -0,0 +1,16 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS standing_order_executions;
+
+DROP TABLE IF EXISTS standing_orders;
+
+DROP TYPE IF EXISTS executionstatus;
+
+DROP TYPE IF EXISTS standingorderstatus;
+
+DROP TYPE IF EXISTS standingorderfrequency;
+
+DELETE FROM transfers WHERE sender_card_number IS NULL;
+
+ALTER TABLE transfers
+DROP COLUMN sender_account_number,
+ALTER COLUMN sender_card_number SET NOT NULL;
@@ -0,0 +1,52 @@
+-- Add up migration script here
+-- Transfers can be made from an account without a card (standing orders, batches)
+ALTER TABLE transfers
+ADD COLUMN sender_account_number VARCHAR(255) REFERENCES accounts(account_number) ON DELETE CASCADE,
+ALTER COLUMN sender_card_number DROP NOT NULL;
+
+UPDATE transfers AS t
+SET sender_account_number = c.account_number
+FROM cards AS c
+WHERE c.card_number = t.sender_card_number;
+
+ALTER TABLE transfers
+ALTER COLUMN sender_account_number SET NOT NULL;
+
+CREATE TYPE standingorderfrequency AS ENUM ('daily', 'weekly', 'monthly', 'cron');
+
+CREATE TYPE standingorderstatus AS ENUM ('active', 'paused', 'completed', 'cancelled');
+
+CREATE TYPE executionstatus AS ENUM ('succeeded', 'failed');
+
+CREATE TABLE IF NOT EXISTS standing_orders (
+    id UUID PRIMARY KEY,
+    source_account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    beneficiary_id UUID NOT NULL REFERENCES beneficiaries(id) ON DELETE CASCADE,
+    amount INTEGER NOT NULL CHECK (amount > 0),
+    frequency standingorderfrequency NOT NULL,
+    -- "day-of-month month day-of-week", only used with the cron frequency
+    cron_expression VARCHAR(255),
+    start_date DATE NOT NULL,
+    end_date DATE,
+    next_run_date DATE NOT NULL,
+    retry_count INTEGER DEFAULT 0 NOT NULL,
+    next_retry_at TIMESTAMP WITHOUT TIME ZONE,
+    status standingorderstatus NOT NULL,
+    bank_id UUID NOT NULL REFERENCES banks(id),
+    branch_id UUID NOT NULL REFERENCES branches(id),
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+
+CREATE INDEX standing_orders_due_idx ON standing_orders (next_run_date) WHERE status = 'active';
+
+CREATE TABLE IF NOT EXISTS standing_order_executions (
+    id UUID PRIMARY KEY,
+    standing_order_id UUID NOT NULL REFERENCES standing_orders(id) ON DELETE CASCADE,
+    run_date DATE NOT NULL,
+    attempt INTEGER NOT NULL,
+    status executionstatus NOT NULL,
+    transfer_id UUID REFERENCES transfers(id) ON DELETE SET NULL,
+    failure_reason VARCHAR(255),
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
@@ -0,0 +1,6 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS direct_debit_collections;
+
+DROP TABLE IF EXISTS mandates;
+
+DROP TYPE IF EXISTS mandatestatus;
@@ -0,0 +1,35 @@
+-- Add up migration script here
+ALTER TYPE transactiontype ADD VALUE IF NOT EXISTS 'direct_debit';
+ALTER TYPE transactiontype ADD VALUE IF NOT EXISTS 'direct_debit_refund';
+
+CREATE TYPE mandatestatus AS ENUM ('active', 'revoked');
+
+-- A debtor's authorization for a creditor to pull funds from their account
+CREATE TABLE IF NOT EXISTS mandates (
+    id UUID PRIMARY KEY,
+    debtor_account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    creditor_account_number VARCHAR(255) NOT NULL REFERENCES accounts(account_number) ON DELETE CASCADE,
+    creditor_name VARCHAR(255) NOT NULL,
+    reference VARCHAR(255) NOT NULL,
+    max_amount INTEGER NOT NULL CHECK (max_amount > 0),
+    status mandatestatus NOT NULL,
+    revoked_at TIMESTAMP WITHOUT TIME ZONE,
+    bank_id UUID NOT NULL REFERENCES banks(id),
+    branch_id UUID NOT NULL REFERENCES branches(id),
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    UNIQUE (creditor_account_number, reference)
+);
+
+CREATE TABLE IF NOT EXISTS direct_debit_collections (
+    id UUID PRIMARY KEY,
+    mandate_id UUID NOT NULL REFERENCES mandates(id) ON DELETE CASCADE,
+    amount INTEGER NOT NULL,
+    status status NOT NULL,
+    rejection_code VARCHAR(4),
+    transaction_id UUID REFERENCES transactions(id),
+    refund_id UUID REFERENCES refunds(id),
+    refundable_until DATE,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
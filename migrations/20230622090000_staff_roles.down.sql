@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS staff_members;
+DROP TYPE IF EXISTS role;
@@ -0,0 +1,18 @@
+-- Add up migration script here
+CREATE TYPE role AS ENUM ('customer', 'teller', 'branch_manager', 'bank_admin', 'auditor');
+
+-- Bank employees. A NULL branch_id grants the role across the whole bank.
+CREATE TABLE IF NOT EXISTS staff_members (
+    id UUID PRIMARY KEY,
+    staff_name VARCHAR(255) NOT NULL,
+    email VARCHAR(255) NOT NULL UNIQUE,
+    password_hash VARCHAR(255) NOT NULL,
+    role role NOT NULL CHECK (role <> 'customer'),
+    bank_id UUID NOT NULL REFERENCES banks(id) ON DELETE CASCADE,
+    branch_id UUID REFERENCES branches(id) ON DELETE CASCADE,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    CHECK (role NOT IN ('teller', 'branch_manager') OR branch_id IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS staff_members_bank_id_idx ON staff_members (bank_id);
@@ -0,0 +1,25 @@
+-- Add up migration script here
+-- A machine client (typically a merchant integration) owned by a bank
+CREATE TABLE IF NOT EXISTS api_clients (
+    id UUID PRIMARY KEY,
+    client_name VARCHAR(255) NOT NULL,
+    bank_id UUID NOT NULL REFERENCES banks(id) ON DELETE CASCADE,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+
+-- Only the SHA-256 of the secret is stored; the prefix identifies the key in logs and lookups
+CREATE TABLE IF NOT EXISTS api_keys (
+    id UUID PRIMARY KEY,
+    client_id UUID NOT NULL REFERENCES api_clients(id) ON DELETE CASCADE,
+    bank_id UUID NOT NULL REFERENCES banks(id) ON DELETE CASCADE,
+    key_prefix VARCHAR(16) NOT NULL UNIQUE,
+    key_hash CHAR(64) NOT NULL,
+    scopes TEXT[] NOT NULL,
+    last_used_at TIMESTAMP WITHOUT TIME ZONE,
+    revoked_at TIMESTAMP WITHOUT TIME ZONE,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS api_keys_active_client_idx ON api_keys (client_id) WHERE revoked_at IS NULL;
@@ -0,0 +1,36 @@
+-- Add up migration script here
+-- Append-only, hash-chained record of every state-changing operation. Each row's hash
+-- covers its own content and the previous row's hash, so edits break the chain.
+CREATE TABLE IF NOT EXISTS audit_events (
+    sequence BIGINT PRIMARY KEY,
+    id UUID NOT NULL UNIQUE,
+    occurred_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    actor_type VARCHAR(64) NOT NULL,
+    actor_id UUID,
+    request_id VARCHAR(255),
+    action VARCHAR(128) NOT NULL,
+    entity_type VARCHAR(64) NOT NULL,
+    entity_id UUID NOT NULL,
+    bank_id UUID,
+    before_state JSONB,
+    after_state JSONB,
+    prev_hash CHAR(64) NOT NULL,
+    hash CHAR(64) NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS audit_events_entity_idx ON audit_events (entity_type, entity_id, occurred_at);
+CREATE INDEX IF NOT EXISTS audit_events_bank_idx ON audit_events (bank_id, occurred_at);
+
+CREATE OR REPLACE FUNCTION audit_events_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_events_no_update_or_delete
+    BEFORE UPDATE OR DELETE ON audit_events
+    FOR EACH ROW EXECUTE FUNCTION audit_events_append_only();
+
+CREATE TRIGGER audit_events_no_truncate
+    BEFORE TRUNCATE ON audit_events
+    FOR EACH STATEMENT EXECUTE FUNCTION audit_events_append_only();
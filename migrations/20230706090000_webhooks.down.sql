@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS webhook_deliveries;
+DROP TABLE IF EXISTS webhook_endpoints;
+DROP TYPE IF EXISTS webhookdeliverystatus;
@@ -0,0 +1,38 @@
+-- Add up migration script here
+CREATE TYPE webhookdeliverystatus AS ENUM ('pending', 'delivered', 'dead_lettered');
+
+-- A callback URL owned by an API client. The secret is kept in clear because it is
+-- needed to sign every delivery.
+CREATE TABLE IF NOT EXISTS webhook_endpoints (
+    id UUID PRIMARY KEY,
+    client_id UUID NOT NULL REFERENCES api_clients(id) ON DELETE CASCADE,
+    bank_id UUID NOT NULL REFERENCES banks(id) ON DELETE CASCADE,
+    url VARCHAR(2048) NOT NULL,
+    secret VARCHAR(64) NOT NULL,
+    event_types TEXT[] NOT NULL,
+    disabled_at TIMESTAMP WITHOUT TIME ZONE,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS webhook_endpoints_bank_idx ON webhook_endpoints (bank_id) WHERE disabled_at IS NULL;
+
+-- One row per (event, endpoint); retried with backoff until delivered or dead-lettered
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id UUID PRIMARY KEY,
+    endpoint_id UUID NOT NULL REFERENCES webhook_endpoints(id) ON DELETE CASCADE,
+    event_id UUID NOT NULL,
+    event_type VARCHAR(64) NOT NULL,
+    payload JSONB NOT NULL,
+    status webhookdeliverystatus NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_response_status INTEGER,
+    last_error TEXT,
+    next_attempt_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    delivered_at TIMESTAMP WITHOUT TIME ZONE,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    UNIQUE (endpoint_id, event_id)
+);
+
+CREATE INDEX IF NOT EXISTS webhook_deliveries_due_idx ON webhook_deliveries (next_attempt_at) WHERE status = 'pending';
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS fraud_evaluations;
+DROP TABLE IF EXISTS fraud_rules;
+DROP TYPE IF EXISTS frauddecision;
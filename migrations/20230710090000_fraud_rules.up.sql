@@ -0,0 +1,42 @@
+-- Add up migration script here
+CREATE TYPE frauddecision AS ENUM ('allow', 'review', 'deny');
+
+-- Rules are data: `condition` holds the rule type and its parameters as JSON, so a bank
+-- can tune thresholds without a deploy
+CREATE TABLE IF NOT EXISTS fraud_rules (
+    id UUID PRIMARY KEY,
+    bank_id UUID NOT NULL REFERENCES banks(id) ON DELETE CASCADE,
+    rule_name VARCHAR(255) NOT NULL,
+    condition JSONB NOT NULL,
+    action frauddecision NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS fraud_rules_bank_idx ON fraud_rules (bank_id) WHERE enabled;
+
+-- Every screened operation, whatever the decision. Velocity, travel and structuring
+-- rules look back over this history; flagged rows double as the review queue.
+CREATE TABLE IF NOT EXISTS fraud_evaluations (
+    id UUID PRIMARY KEY,
+    bank_id UUID NOT NULL REFERENCES banks(id) ON DELETE CASCADE,
+    account_number VARCHAR(255) NOT NULL,
+    card_number VARCHAR(255),
+    beneficiary_account_number VARCHAR(255),
+    amount INTEGER NOT NULL,
+    country CHAR(2),
+    merchant_category VARCHAR(4),
+    operation JSONB NOT NULL,
+    decision frauddecision NOT NULL,
+    reasons TEXT[] NOT NULL,
+    review_status status,
+    reviewed_by UUID,
+    reviewed_at TIMESTAMP WITHOUT TIME ZONE,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS fraud_evaluations_account_idx ON fraud_evaluations (account_number, inserted_at);
+CREATE INDEX IF NOT EXISTS fraud_evaluations_card_idx ON fraud_evaluations (card_number, inserted_at) WHERE card_number IS NOT NULL;
+CREATE INDEX IF NOT EXISTS fraud_evaluations_review_idx ON fraud_evaluations (bank_id, inserted_at) WHERE review_status = 'pending';
@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS sanctions_hits;
+DROP TABLE IF EXISTS watchlist_entries;
+DROP TABLE IF EXISTS watchlist_versions;
+DROP TYPE IF EXISTS sanctionshitstatus;
@@ -0,0 +1,46 @@
+-- Add up migration script here
+CREATE TYPE sanctionshitstatus AS ENUM ('pending', 'confirmed', 'cleared');
+
+-- One row per watchlist file load; the latest one is the list in force
+CREATE TABLE IF NOT EXISTS watchlist_versions (
+    id UUID PRIMARY KEY,
+    source VARCHAR(1024) NOT NULL,
+    content_hash CHAR(64) NOT NULL,
+    entry_count INTEGER NOT NULL,
+    loaded_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+
+-- Each name and alias is its own entry; `normalized_name` is what matching runs on
+CREATE TABLE IF NOT EXISTS watchlist_entries (
+    id UUID PRIMARY KEY,
+    version_id UUID NOT NULL REFERENCES watchlist_versions(id) ON DELETE CASCADE,
+    external_id VARCHAR(255),
+    entry_name VARCHAR(1024) NOT NULL,
+    normalized_name VARCHAR(1024) NOT NULL,
+    program VARCHAR(255)
+);
+
+CREATE INDEX IF NOT EXISTS watchlist_entries_version_idx ON watchlist_entries (version_id);
+
+-- Matches awaiting (or past) compliance review. `subject_id` is empty when the match
+-- blocked the subject from being created.
+CREATE TABLE IF NOT EXISTS sanctions_hits (
+    id UUID PRIMARY KEY,
+    bank_id UUID NOT NULL REFERENCES banks(id) ON DELETE CASCADE,
+    subject_type VARCHAR(32) NOT NULL,
+    subject_id UUID,
+    subject_name VARCHAR(1024) NOT NULL,
+    normalized_subject_name VARCHAR(1024) NOT NULL,
+    external_id VARCHAR(255),
+    entry_name VARCHAR(1024) NOT NULL,
+    program VARCHAR(255),
+    score DOUBLE PRECISION NOT NULL,
+    status sanctionshitstatus NOT NULL,
+    reviewed_by UUID,
+    reviewed_at TIMESTAMP WITHOUT TIME ZONE,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS sanctions_hits_pending_idx ON sanctions_hits (bank_id, inserted_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS sanctions_hits_subject_idx ON sanctions_hits (normalized_subject_name, entry_name);
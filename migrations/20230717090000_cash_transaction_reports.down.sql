@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS cash_transaction_reports;
+
+ALTER TABLE banks DROP COLUMN ctr_threshold;
@@ -0,0 +1,26 @@
+-- Add up migration script here
+-- Aggregated same-day cash in or out at or above this amount must be reported
+ALTER TABLE banks
+ADD COLUMN ctr_threshold integer DEFAULT 10000 NOT NULL;
+
+-- One report per customer and business day; cash in and cash out are aggregated separately
+CREATE TABLE IF NOT EXISTS cash_transaction_reports (
+    id UUID PRIMARY KEY,
+    bank_id UUID NOT NULL REFERENCES banks(id),
+    branch_id UUID NOT NULL REFERENCES branches(id),
+    customer_id UUID NOT NULL REFERENCES customers(id),
+    business_date DATE NOT NULL,
+    customer_name VARCHAR(255) NOT NULL,
+    cic_number VARCHAR(255) NOT NULL,
+    total_cash_in BIGINT NOT NULL,
+    total_cash_out BIGINT NOT NULL,
+    transaction_count INTEGER NOT NULL,
+    transaction_ids UUID[] NOT NULL,
+    threshold INTEGER NOT NULL,
+    exported_at TIMESTAMP WITHOUT TIME ZONE,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    UNIQUE (customer_id, business_date)
+);
+
+CREATE INDEX IF NOT EXISTS cash_transaction_reports_bank_date_idx
+    ON cash_transaction_reports (bank_id, business_date);
@@ -0,0 +1,12 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS accounts_dormancy_idx;
+
+ALTER TABLE accounts
+DROP COLUMN closed_at,
+DROP COLUMN last_activity_at,
+DROP COLUMN status_reason,
+DROP COLUMN status;
+
+ALTER TABLE banks DROP COLUMN dormancy_months;
+
+DROP TYPE IF EXISTS accountstatus;
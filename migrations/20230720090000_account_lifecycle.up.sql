@@ -0,0 +1,17 @@
+-- Add up migration script here
+ALTER TYPE transactiontype ADD VALUE IF NOT EXISTS 'closure_sweep';
+
+CREATE TYPE accountstatus AS ENUM ('active', 'frozen', 'dormant', 'closed');
+
+-- Months without customer activity after which an account becomes dormant
+ALTER TABLE banks
+ADD COLUMN dormancy_months integer DEFAULT 12 NOT NULL;
+
+-- last_activity_at only moves on customer-initiated activity, not on incoming credits
+ALTER TABLE accounts
+ADD COLUMN status accountstatus DEFAULT 'active' NOT NULL,
+ADD COLUMN status_reason TEXT,
+ADD COLUMN last_activity_at TIMESTAMP WITHOUT TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL,
+ADD COLUMN closed_at TIMESTAMP WITHOUT TIME ZONE;
+
+CREATE INDEX IF NOT EXISTS accounts_dormancy_idx ON accounts (last_activity_at) WHERE status = 'active';
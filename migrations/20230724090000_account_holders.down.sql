@@ -0,0 +1,16 @@
+-- Add down migration script here
+ALTER TABLE accounts
+ADD COLUMN customer_id UUID REFERENCES customers(id);
+
+UPDATE accounts AS a
+SET customer_id = h.customer_id
+FROM account_holders AS h
+WHERE h.account_id = a.id AND h.role = 'primary';
+
+ALTER TABLE accounts
+ALTER COLUMN customer_id SET NOT NULL,
+ADD CONSTRAINT accounts_customer_id_key UNIQUE (customer_id);
+
+DROP TABLE IF EXISTS account_holders;
+
+DROP TYPE IF EXISTS accountholderrole;
@@ -0,0 +1,24 @@
+-- Add up migration script here
+CREATE TYPE accountholderrole AS ENUM ('primary', 'joint', 'authorized_signer');
+
+-- Customers holding an account; every account has exactly one primary holder
+CREATE TABLE IF NOT EXISTS account_holders (
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    role accountholderrole NOT NULL,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    PRIMARY KEY (account_id, customer_id)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS account_holders_primary_idx
+    ON account_holders (account_id) WHERE role = 'primary';
+
+CREATE INDEX IF NOT EXISTS account_holders_customer_idx ON account_holders (customer_id);
+
+INSERT INTO account_holders (account_id, customer_id, role, inserted_at, updated_at)
+SELECT id, customer_id, 'primary', inserted_at, updated_at
+FROM accounts;
+
+-- Ownership now lives in account_holders, which also lifts the one account per customer limit
+ALTER TABLE accounts DROP COLUMN customer_id;
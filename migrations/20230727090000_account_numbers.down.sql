@@ -0,0 +1,4 @@
+-- Add down migration script here
+ALTER TABLE branches DROP COLUMN branch_code;
+
+DROP SEQUENCE IF EXISTS account_number_seq;
@@ -0,0 +1,19 @@
+-- Add up migration script here
+-- Account numbers are branch code (4 digits) + sequence (8 digits) + mod-97 check digits (2 digits)
+CREATE SEQUENCE IF NOT EXISTS account_number_seq MINVALUE 1 MAXVALUE 99999999 NO CYCLE;
+
+ALTER TABLE branches
+ADD COLUMN branch_code INTEGER;
+
+UPDATE branches AS b
+SET branch_code = n.code
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY bank_id ORDER BY inserted_at, id) AS code
+    FROM branches
+) AS n
+WHERE n.id = b.id;
+
+ALTER TABLE branches
+ALTER COLUMN branch_code SET NOT NULL,
+ADD CONSTRAINT branches_bank_id_branch_code_key UNIQUE (bank_id, branch_code),
+ADD CONSTRAINT branches_branch_code_check CHECK (branch_code BETWEEN 1 AND 9999);
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE banks DROP COLUMN bank_code;
@@ -0,0 +1,17 @@
+-- Add up migration script here
+-- Identifies the bank inside IBANs: country code + check digits + bank code + account number
+ALTER TABLE banks
+ADD COLUMN bank_code INTEGER;
+
+UPDATE banks AS b
+SET bank_code = n.code
+FROM (
+    SELECT id, ROW_NUMBER() OVER (ORDER BY inserted_at, id) AS code
+    FROM banks
+) AS n
+WHERE n.id = b.id;
+
+ALTER TABLE banks
+ALTER COLUMN bank_code SET NOT NULL,
+ADD CONSTRAINT banks_bank_code_key UNIQUE (bank_code),
+ADD CONSTRAINT banks_bank_code_check CHECK (bank_code BETWEEN 1 AND 9999);
@@ -0,0 +1,13 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS payment_batch_items;
+
+DROP TABLE IF EXISTS payment_batches;
+
+ALTER TABLE transactions
+DROP COLUMN IF EXISTS direction;
+
+DROP TYPE IF EXISTS paymentitemstatus;
+
+DROP TYPE IF EXISTS paymentbatchstatus;
+
+DROP TYPE IF EXISTS entrydirection;
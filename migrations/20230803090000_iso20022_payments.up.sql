@@ -0,0 +1,95 @@
+-- Add up migration script here
+CREATE TYPE entrydirection AS ENUM ('credit', 'debit');
+
+CREATE TYPE paymentbatchstatus AS ENUM ('accepted', 'processing', 'completed', 'partially_completed', 'rejected');
+
+CREATE TYPE paymentitemstatus AS ENUM ('pending', 'held', 'completed', 'rejected');
+
+-- Both legs of a transfer are posted with the same type, so the side of the ledger
+-- has to be recorded explicitly for statements
+ALTER TABLE transactions
+ADD COLUMN direction entrydirection;
+
+UPDATE transactions SET direction = 'credit' WHERE transaction_type = 'cash_deposit';
+
+-- Transfers only record the sending account; the matching leg on it is the debit
+UPDATE transactions AS t
+SET direction = 'debit'
+WHERE transaction_type = 'p2p' AND EXISTS (
+    SELECT 1 FROM transfers AS tr
+    WHERE tr.sender_account_number = t.account_number
+        AND tr.amount = t.amount
+        AND tr.transfer_date = t.transaction_date
+);
+
+UPDATE transactions AS t
+SET direction = CASE WHEN EXISTS (
+    SELECT 1 FROM mandates AS m
+    INNER JOIN accounts AS a ON a.id = m.debtor_account_id
+    WHERE a.account_number = t.account_number
+) THEN 'debit'::entrydirection ELSE 'credit'::entrydirection END
+WHERE transaction_type = 'direct_debit';
+
+UPDATE transactions AS t
+SET direction = CASE WHEN EXISTS (
+    SELECT 1 FROM mandates AS m
+    INNER JOIN accounts AS a ON a.id = m.debtor_account_id
+    WHERE a.account_number = t.account_number
+) THEN 'credit'::entrydirection ELSE 'debit'::entrydirection END
+WHERE transaction_type = 'direct_debit_refund';
+
+UPDATE transactions AS t
+SET direction = CASE WHEN EXISTS (
+    SELECT 1 FROM accounts AS a
+    WHERE a.account_number = t.account_number AND a.status = 'closed'
+) THEN 'debit'::entrydirection ELSE 'credit'::entrydirection END
+WHERE transaction_type = 'closure_sweep';
+
+UPDATE transactions SET direction = 'credit' WHERE transaction_type = 'p2p' AND direction IS NULL;
+
+-- Everything else takes money out of the account
+UPDATE transactions SET direction = 'debit' WHERE direction IS NULL;
+
+ALTER TABLE transactions
+ALTER COLUMN direction SET NOT NULL;
+
+-- One batch per payment information block of an initiation message; every block
+-- debits a single account
+CREATE TABLE IF NOT EXISTS payment_batches (
+    id UUID PRIMARY KEY,
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    debtor_account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    source_format VARCHAR(32) NOT NULL,
+    message_id VARCHAR(255) NOT NULL,
+    payment_information_id VARCHAR(255) NOT NULL,
+    requested_execution_date DATE NOT NULL,
+    item_count INTEGER NOT NULL,
+    control_sum BIGINT NOT NULL,
+    status paymentbatchstatus NOT NULL,
+    processed_at TIMESTAMP WITHOUT TIME ZONE,
+    bank_id UUID NOT NULL REFERENCES banks(id),
+    branch_id UUID NOT NULL REFERENCES branches(id),
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    UNIQUE (customer_id, message_id, payment_information_id)
+);
+
+CREATE INDEX IF NOT EXISTS payment_batches_due_idx ON payment_batches (requested_execution_date) WHERE status = 'accepted';
+
+CREATE TABLE IF NOT EXISTS payment_batch_items (
+    id UUID PRIMARY KEY,
+    batch_id UUID NOT NULL REFERENCES payment_batches(id) ON DELETE CASCADE,
+    line_number INTEGER NOT NULL,
+    end_to_end_id VARCHAR(255) NOT NULL,
+    creditor_name VARCHAR(255) NOT NULL,
+    creditor_account_number VARCHAR(255) NOT NULL,
+    amount INTEGER NOT NULL,
+    remittance_information TEXT,
+    status paymentitemstatus NOT NULL,
+    reason_code VARCHAR(4),
+    reason TEXT,
+    transfer_id UUID REFERENCES transfers(id),
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    UNIQUE (batch_id, line_number)
+);
@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE payment_batches
+DROP COLUMN IF EXISTS execution_mode;
+
+DROP TYPE IF EXISTS batchexecutionmode;
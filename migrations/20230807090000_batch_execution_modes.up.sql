@@ -0,0 +1,8 @@
+-- Add up migration script here
+CREATE TYPE batchexecutionmode AS ENUM ('best_effort', 'all_or_nothing');
+
+-- Items that passed validation but could not be carried out
+ALTER TYPE paymentitemstatus ADD VALUE IF NOT EXISTS 'failed';
+
+ALTER TABLE payment_batches
+ADD COLUMN execution_mode batchexecutionmode DEFAULT 'best_effort' NOT NULL;
@@ -0,0 +1,8 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS clearing_positions;
+
+DROP TABLE IF EXISTS clearing_items;
+
+DROP TABLE IF EXISTS clearing_cycles;
+
+DROP TYPE IF EXISTS clearingitemstatus;
@@ -0,0 +1,45 @@
+-- Add up migration script here
+ALTER TYPE transactiontype ADD VALUE IF NOT EXISTS 'clearing_return';
+
+CREATE TYPE clearingitemstatus AS ENUM ('queued', 'settled', 'returned');
+
+-- One netting and settlement run per cut-off
+CREATE TABLE IF NOT EXISTS clearing_cycles (
+    id UUID PRIMARY KEY,
+    cut_off_at TIMESTAMP WITHOUT TIME ZONE NOT NULL UNIQUE,
+    settled_count INTEGER NOT NULL,
+    returned_count INTEGER NOT NULL,
+    gross_amount BIGINT NOT NULL,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+
+-- A transfer to an account held at another bank. The debtor is debited when the item
+-- is queued; the creditor is credited when its cycle settles.
+CREATE TABLE IF NOT EXISTS clearing_items (
+    id UUID PRIMARY KEY,
+    transfer_id UUID NOT NULL REFERENCES transfers(id),
+    debtor_bank_id UUID NOT NULL REFERENCES banks(id),
+    debtor_branch_id UUID NOT NULL REFERENCES branches(id),
+    debtor_account_number VARCHAR(255) NOT NULL,
+    creditor_bank_id UUID NOT NULL REFERENCES banks(id),
+    creditor_account_number VARCHAR(255) NOT NULL,
+    amount INTEGER NOT NULL,
+    status clearingitemstatus NOT NULL,
+    cycle_id UUID REFERENCES clearing_cycles(id),
+    return_reason_code VARCHAR(4),
+    return_reason TEXT,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS clearing_items_queued_idx ON clearing_items (inserted_at) WHERE status = 'queued';
+
+-- Each bank's net position in a cycle: what it received less what it sent
+CREATE TABLE IF NOT EXISTS clearing_positions (
+    cycle_id UUID NOT NULL REFERENCES clearing_cycles(id) ON DELETE CASCADE,
+    bank_id UUID NOT NULL REFERENCES banks(id),
+    sent_amount BIGINT NOT NULL,
+    received_amount BIGINT NOT NULL,
+    net_position BIGINT NOT NULL,
+    PRIMARY KEY (cycle_id, bank_id)
+);
@@ -0,0 +1,9 @@
+-- Add down migration script here
+ALTER TABLE transactions
+DROP COLUMN IF EXISTS value_date;
+
+DROP TABLE IF EXISTS holidays;
+
+ALTER TABLE banks
+DROP COLUMN cut_off_time,
+DROP COLUMN weekend_days;
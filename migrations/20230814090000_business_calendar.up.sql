@@ -0,0 +1,26 @@
+-- Add up migration script here
+-- Weekend days are ISO weekday numbers (1 = Monday ... 7 = Sunday). Postings after the
+-- cut-off time take the next business day as their value date.
+ALTER TABLE banks
+ADD COLUMN weekend_days INTEGER[] NOT NULL DEFAULT '{6,7}',
+ADD COLUMN cut_off_time TIME WITHOUT TIME ZONE NOT NULL DEFAULT '16:00';
+
+-- Loaded from the holidays file; rows without a bank apply to every bank and to
+-- interbank clearing
+CREATE TABLE IF NOT EXISTS holidays (
+    id UUID PRIMARY KEY,
+    bank_id UUID REFERENCES banks(id) ON DELETE CASCADE,
+    holiday_date DATE NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS holidays_bank_date_idx ON holidays (bank_id, holiday_date);
+
+ALTER TABLE transactions
+ADD COLUMN value_date DATE;
+
+UPDATE transactions SET value_date = transaction_date;
+
+ALTER TABLE transactions
+ALTER COLUMN value_date SET NOT NULL;
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS batch_runs;
+
+DROP TYPE IF EXISTS batchrunstatus;
@@ -0,0 +1,18 @@
+-- Add up migration script here
+CREATE TYPE batchrunstatus AS ENUM ('running', 'completed', 'failed');
+
+-- Checkpoint of one end-of-day step for one bank and business date. Completed steps are
+-- skipped when the run is resumed.
+CREATE TABLE IF NOT EXISTS batch_runs (
+    id UUID PRIMARY KEY,
+    bank_id UUID NOT NULL REFERENCES banks(id) ON DELETE CASCADE,
+    business_date DATE NOT NULL,
+    step VARCHAR(64) NOT NULL,
+    status batchrunstatus NOT NULL,
+    attempts INTEGER NOT NULL,
+    processed BIGINT,
+    error TEXT,
+    started_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    finished_at TIMESTAMP WITHOUT TIME ZONE,
+    UNIQUE (bank_id, business_date, step)
+);
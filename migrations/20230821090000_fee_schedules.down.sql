@@ -0,0 +1,8 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS fee_charges;
+DROP TABLE IF EXISTS fee_waivers;
+DROP TABLE IF EXISTS fee_schedules;
+
+DROP TYPE IF EXISTS feestatus;
+DROP TYPE IF EXISTS feecalculation;
+DROP TYPE IF EXISTS feetype;
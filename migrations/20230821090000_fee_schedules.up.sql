@@ -0,0 +1,69 @@
+-- Add up migration script here
+ALTER TYPE transactiontype ADD VALUE IF NOT EXISTS 'fee';
+ALTER TYPE transactiontype ADD VALUE IF NOT EXISTS 'fee_reversal';
+
+CREATE TYPE feetype AS ENUM ('transaction', 'atm_withdrawal', 'monthly_maintenance');
+CREATE TYPE feecalculation AS ENUM ('flat', 'percentage');
+CREATE TYPE feestatus AS ENUM ('charged', 'waived', 'reversed');
+
+-- Transaction fees name the transaction type they apply to; the other fee types do not.
+-- A schedule without an account type applies to every account type. Percentage fees take
+-- `rate_bps` of the transaction amount (or of the balance, for maintenance fees), bounded
+-- by the optional minimum and maximum.
+CREATE TABLE IF NOT EXISTS fee_schedules (
+    id UUID PRIMARY KEY,
+    bank_id UUID NOT NULL REFERENCES banks(id) ON DELETE CASCADE,
+    fee_name VARCHAR(255) NOT NULL,
+    fee_type feetype NOT NULL,
+    transaction_type transactiontype,
+    account_type accounttype,
+    calculation feecalculation NOT NULL,
+    amount INTEGER NOT NULL DEFAULT 0,
+    rate_bps INTEGER NOT NULL DEFAULT 0,
+    min_amount INTEGER,
+    max_amount INTEGER,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS fee_schedules_bank_idx ON fee_schedules (bank_id, fee_type) WHERE enabled;
+
+-- A waiver without a fee type covers every fee on the account
+CREATE TABLE IF NOT EXISTS fee_waivers (
+    id UUID PRIMARY KEY,
+    bank_id UUID NOT NULL REFERENCES banks(id) ON DELETE CASCADE,
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    fee_type feetype,
+    reason TEXT NOT NULL,
+    valid_until DATE,
+    revoked_at TIMESTAMP WITHOUT TIME ZONE,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS fee_waivers_account_idx ON fee_waivers (account_id) WHERE revoked_at IS NULL;
+
+-- Every fee assessed, including waived ones. Transaction and ATM fees point at the
+-- transaction that triggered them; maintenance fees at the month they cover.
+CREATE TABLE IF NOT EXISTS fee_charges (
+    id UUID PRIMARY KEY,
+    bank_id UUID NOT NULL REFERENCES banks(id) ON DELETE CASCADE,
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    fee_schedule_id UUID REFERENCES fee_schedules(id) ON DELETE SET NULL,
+    fee_type feetype NOT NULL,
+    amount INTEGER NOT NULL,
+    status feestatus NOT NULL,
+    source_transaction_id UUID REFERENCES transactions(id),
+    fee_transaction_id UUID REFERENCES transactions(id),
+    reversal_transaction_id UUID REFERENCES transactions(id),
+    fee_waiver_id UUID REFERENCES fee_waivers(id),
+    period_start DATE,
+    reversal_reason TEXT,
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS fee_charges_account_idx ON fee_charges (account_id, inserted_at);
+CREATE INDEX IF NOT EXISTS fee_charges_source_idx ON fee_charges (source_transaction_id) WHERE source_transaction_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS fee_charges_maintenance_idx ON fee_charges (account_id, period_start) WHERE fee_type = 'monthly_maintenance';
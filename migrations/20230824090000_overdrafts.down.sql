@@ -0,0 +1,9 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS fee_charges_period_idx;
+CREATE UNIQUE INDEX IF NOT EXISTS fee_charges_maintenance_idx ON fee_charges (account_id, period_start) WHERE fee_type = 'monthly_maintenance';
+
+DROP TABLE IF EXISTS overdraft_accruals;
+
+ALTER TABLE accounts
+DROP COLUMN overdraft_rate_bps,
+DROP COLUMN overdraft_limit;
@@ -0,0 +1,29 @@
+-- Add up migration script here
+ALTER TYPE transactiontype ADD VALUE IF NOT EXISTS 'overdraft_interest';
+ALTER TYPE feetype ADD VALUE IF NOT EXISTS 'unarranged_overdraft';
+
+-- Debits may take the balance down to -overdraft_limit. Interest on the overdrawn
+-- balance is charged daily at overdraft_rate_bps a year.
+ALTER TABLE accounts
+ADD COLUMN overdraft_limit INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN overdraft_rate_bps INTEGER NOT NULL DEFAULT 0;
+
+-- One row per overdrawn account and day, so the daily accrual can be re-run safely
+CREATE TABLE IF NOT EXISTS overdraft_accruals (
+    id UUID PRIMARY KEY,
+    bank_id UUID NOT NULL REFERENCES banks(id) ON DELETE CASCADE,
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    accrual_date DATE NOT NULL,
+    balance INTEGER NOT NULL,
+    overdraft_limit INTEGER NOT NULL,
+    rate_bps INTEGER NOT NULL,
+    interest INTEGER NOT NULL,
+    transaction_id UUID REFERENCES transactions(id),
+    inserted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    UNIQUE (account_id, accrual_date)
+);
+
+-- Periodic fees (maintenance by month, unarranged overdraft by day) are charged once
+-- per account and period
+DROP INDEX IF EXISTS fee_charges_maintenance_idx;
+CREATE UNIQUE INDEX IF NOT EXISTS fee_charges_period_idx ON fee_charges (account_id, fee_type, period_start) WHERE period_start IS NOT NULL;
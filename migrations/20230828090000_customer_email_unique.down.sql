@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE customers DROP CONSTRAINT IF EXISTS customers_email_key;
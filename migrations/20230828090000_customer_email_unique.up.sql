@@ -0,0 +1,25 @@
+-- Add up migration script here
+-- Customers log in by email, so it has to identify exactly one of them. Which of several
+-- customers sharing an email keeps it is for the bank to decide, so the migration stops
+-- and names them rather than picking one.
+DO $$
+DECLARE
+    duplicates TEXT;
+BEGIN
+    SELECT string_agg(email || ' (' || customer_ids || ')', '; ')
+    INTO duplicates
+    FROM (
+        SELECT email, string_agg(id::TEXT, ', ' ORDER BY inserted_at) AS customer_ids
+        FROM customers
+        WHERE email IS NOT NULL
+        GROUP BY email
+        HAVING COUNT(*) > 1
+    ) AS shared;
+
+    IF duplicates IS NOT NULL THEN
+        RAISE EXCEPTION 'customers share an email address; give each a distinct email before migrating: %', duplicates;
+    END IF;
+END
+$$;
+
+ALTER TABLE customers ADD CONSTRAINT customers_email_key UNIQUE (email);
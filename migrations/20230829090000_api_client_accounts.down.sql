@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE api_clients DROP COLUMN IF EXISTS account_id;
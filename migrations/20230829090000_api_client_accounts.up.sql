@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- The account a machine client operates on, e.g. the merchant's settlement account.
+-- Collections and webhooks are limited to what concerns this account.
+ALTER TABLE api_clients ADD COLUMN account_id UUID REFERENCES accounts(id);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS audit_events_chain_idx;
+DROP SEQUENCE IF EXISTS audit_events_sequence_seq;
@@ -0,0 +1,8 @@
+-- Add up migration script here
+-- Each bank's events form their own chain (events without a bank form one more), so
+-- writers only serialize against writers of the same bank. Sequences stay globally
+-- unique and increase within each chain.
+CREATE SEQUENCE IF NOT EXISTS audit_events_sequence_seq;
+SELECT setval('audit_events_sequence_seq', COALESCE(MAX(sequence), 0) + 1, false) FROM audit_events;
+
+CREATE INDEX IF NOT EXISTS audit_events_chain_idx ON audit_events (bank_id, sequence);
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE cards DROP COLUMN IF EXISTS frozen_by_customer;
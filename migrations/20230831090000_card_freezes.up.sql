@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- Whether the card was made inactive by its holder, who may then reactivate it. Cards
+-- blocked by the bank can only be reactivated by staff.
+ALTER TABLE cards ADD COLUMN frozen_by_customer BOOLEAN NOT NULL DEFAULT FALSE;
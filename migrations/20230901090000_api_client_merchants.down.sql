@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE api_clients DROP COLUMN IF EXISTS merchant_category;
+ALTER TABLE api_clients DROP COLUMN IF EXISTS merchant_country;
@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- The merchant profile an acquiring client registers with the bank. Card operations it
+-- submits are screened against this rather than anything the cardholder reports.
+ALTER TABLE api_clients ADD COLUMN merchant_country CHAR(2);
+ALTER TABLE api_clients ADD COLUMN merchant_category VARCHAR(4);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS sanctions_hits_reference_idx;
+ALTER TABLE sanctions_hits DROP COLUMN IF EXISTS subject_reference;
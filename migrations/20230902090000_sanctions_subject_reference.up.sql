@@ -0,0 +1,17 @@
+-- Add up migration script here
+-- Identifies the screened subject even before it exists (a customer's CIC number, a
+-- beneficiary's account number), so a reviewer's clearance applies to that subject only
+-- and not to everyone sharing the name.
+ALTER TABLE sanctions_hits ADD COLUMN subject_reference VARCHAR(255);
+
+UPDATE sanctions_hits AS h
+SET subject_reference = c.cic_number
+FROM customers AS c
+WHERE h.subject_type = 'customer' AND h.subject_id = c.id;
+
+UPDATE sanctions_hits AS h
+SET subject_reference = b.beneficiary_account_number
+FROM beneficiaries AS b
+WHERE h.subject_type = 'beneficiary' AND h.subject_id = b.id;
+
+CREATE INDEX IF NOT EXISTS sanctions_hits_reference_idx ON sanctions_hits (bank_id, subject_type, subject_reference);
@@ -0,0 +1,2 @@
+-- Add down migration script here
+DROP SEQUENCE IF EXISTS branch_code_seq;
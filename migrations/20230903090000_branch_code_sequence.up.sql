@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- Branch codes come from a sequence so concurrent branch creation cannot pick the same one
+CREATE SEQUENCE IF NOT EXISTS branch_code_seq MINVALUE 1 MAXVALUE 9999 NO CYCLE;
+
+SELECT setval('branch_code_seq', COALESCE(MAX(branch_code), 0) + 1, false) FROM branches;
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE payment_batches DROP COLUMN IF EXISTS processing_started_at;
@@ -0,0 +1,6 @@
+-- Add up migration script here
+-- When the current run claimed the batch. A batch left processing for too long (the run
+-- died) is claimed again by the next one.
+ALTER TABLE payment_batches ADD COLUMN processing_started_at TIMESTAMP WITHOUT TIME ZONE;
+
+UPDATE payment_batches SET processing_started_at = updated_at WHERE status = 'processing';
@@ -0,0 +1,4 @@
+-- Add down migration script here
+UPDATE clearing_items
+SET inserted_at = (inserted_at AT TIME ZONE 'UTC') AT TIME ZONE current_setting('TimeZone')
+WHERE status = 'queued';
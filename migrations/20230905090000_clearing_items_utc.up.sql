@@ -0,0 +1,6 @@
+-- Add up migration script here
+-- Queued items were stamped on the server's clock while cut-offs are in UTC. Restate
+-- the ones still waiting for a cycle in UTC.
+UPDATE clearing_items
+SET inserted_at = (inserted_at AT TIME ZONE current_setting('TimeZone')) AT TIME ZONE 'UTC'
+WHERE status = 'queued';
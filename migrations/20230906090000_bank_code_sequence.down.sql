@@ -0,0 +1,2 @@
+-- Add down migration script here
+DROP SEQUENCE IF EXISTS bank_code_seq;
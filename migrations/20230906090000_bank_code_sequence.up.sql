@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- Bank codes come from a sequence so concurrent bank creation cannot pick the same one
+CREATE SEQUENCE IF NOT EXISTS bank_code_seq MINVALUE 1 MAXVALUE 9999 NO CYCLE;
+
+SELECT setval('bank_code_seq', COALESCE(MAX(bank_code), 0) + 1, false) FROM banks;
@@ -9,6 +9,30 @@ pub enum CustomerErrorReps  {
     InvalidInput(String),
     #[error("Database error: {0}")]
     DatabaseError(#[from] sqlx::Error),
+    #[error("Not found")]
+    NotFound,
+    #[error("Unauthorized")]
+    Unauthorized,
+    #[error("Forbidden")]
+    Forbidden,
+    #[error("Branch not found")]
+    BranchNotFound,
+    #[error("Insufficient funds")]
+    InsufficientFunds,
+    #[error("Insufficient cash at branch: {0} on hand")]
+    InsufficientBranchCash(i32),
+    #[error("Branch reserve would fall below the required {0}")]
+    ReserveRatioBreached(i64),
+    #[error("Credit limit exceeded: available credit is {0}")]
+    CreditLimitExceeded(i32),
+    #[error("Transaction declined: {0}")]
+    TransactionDeclined(String),
+    #[error("Transaction held for review: {0}")]
+    HeldForReview(Uuid),
+    #[error("Name matches a watchlist entry and is pending compliance review: {0}")]
+    SanctionsMatch(Uuid),
+    #[error("Account is {0}")]
+    AccountUnavailable(&'static str),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -99,10 +123,26 @@ pub fn validate_phone_number(phone_number: &str) -> ValidationResult {
     }
 }
 
+// Branch code + sequence + check digits
+pub const ACCOUNT_NUMBER_LENGTH: usize = 14;
+
+// Remainder of a decimal digit string divided by 97, computed digit by digit so
+// numbers of any length fit. `None` if the string has a non-digit.
+pub fn mod97(digits: &str) -> Option<u32> {
+    digits.chars().try_fold(0, |remainder, c| {
+        c.to_digit(10).map(|digit| (remainder * 10 + digit) % 97)
+    })
+}
+
+// ISO 7064 MOD 97-10 check digits for `base`: appending them makes the whole number
+// leave a remainder of 1 when divided by 97.
+pub fn mod97_check_digits(base: &str) -> Option<u32> {
+    mod97(&format!("{}00", base)).map(|remainder| 98 - remainder)
+}
+
 pub fn validate_account_number(account_number: &str) -> ValidationResult {
-    // Implement account number validation logic according to your requirements
-    // Example: Checking for a valid account number format and length
-    if account_number.len() >= 6 && account_number.len() <= 20 {
+    // Catches single-digit typos and most transpositions before any lookup
+    if account_number.len() == ACCOUNT_NUMBER_LENGTH && mod97(account_number) == Some(1) {
         ValidationResult {
             is_valid: true,
             error_message: None,
@@ -110,7 +150,7 @@ pub fn validate_account_number(account_number: &str) -> ValidationResult {
     } else {
         ValidationResult {
             is_valid: false,
-            error_message: Some("Invalid account number format or length.".to_string()),
+            error_message: Some("Invalid account number format or check digits.".to_string()),
         }
     }
 }
@@ -230,3 +270,42 @@ pub fn validate_account_opened_date(opened_date: NaiveDateTime) -> ValidationRes
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mod97_computes_the_remainder() {
+        assert_eq!(mod97("0"), Some(0));
+        assert_eq!(mod97("97"), Some(0));
+        assert_eq!(mod97("98"), Some(1));
+        assert_eq!(mod97("1234567890"), Some(1234567890 % 97));
+        assert_eq!(mod97("12a4"), None);
+    }
+
+    #[test]
+    fn mod97_accepts_known_ibans() {
+        // GB82WEST12345698765432 and DE89370400440532013000, rearranged and converted to
+        // digits as ISO 13616 prescribes
+        assert_eq!(mod97("3214282912345698765432161182"), Some(1));
+        assert_eq!(mod97("370400440532013000131489"), Some(1));
+    }
+
+    #[test]
+    fn mod97_check_digits_match_known_ibans() {
+        assert_eq!(mod97_check_digits("32142829123456987654321611"), Some(82));
+        assert_eq!(mod97_check_digits("3704004405320130001314"), Some(89));
+        assert_eq!(mod97_check_digits("12a4"), None);
+    }
+
+    #[test]
+    fn account_numbers_with_check_digits_validate() {
+        assert_eq!(mod97_check_digits("000100000001"), Some(46));
+        assert!(validate_account_number("00010000000146").is_valid);
+        // Swapped check digits and a transposition in the base are both caught
+        assert!(!validate_account_number("00010000000164").is_valid);
+        assert!(!validate_account_number("00100000000146").is_valid);
+        assert!(!validate_account_number("0001000000014").is_valid);
+    }
+}
@@ -1,16 +1,23 @@
 use chrono::{NaiveDate, NaiveDateTime};
-use rand::Rng;
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use uuid::Uuid;
 
 use crate::bank::helper::validation::{
-    validate_account_balance, validate_account_opened_date, validate_input, CustomerErrorReps,
+    mod97_check_digits, validate_account_balance, validate_account_opened_date, validate_input,
+    CustomerErrorReps,
 };
 
 use super::{
+    audit::{self, AuditContext, Change},
+    calendar,
     customer::{self, Customer},
-    types::AccountType,
+    iban,
+    outbox::{self, DomainEvent},
+    transactions,
+    types::{
+        AccountHolderRole, AccountStatus, AccountType, EntryDirection, Status, TransactionType,
+    },
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
@@ -21,15 +28,37 @@ pub struct Account {
     pub account_number: String,
     pub balance: i32,
     pub account_type: AccountType,
-    pub customer_id: Uuid,
     pub opened_date: NaiveDate,
     pub last_updated_date: NaiveDate,
     pub inserted_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub status: AccountStatus,
+    pub status_reason: Option<String>,
+    pub last_activity_at: NaiveDateTime,
+    pub closed_at: Option<NaiveDateTime>,
+    pub overdraft_limit: i32,
+    pub overdraft_rate_bps: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct AccountHolder {
+    pub account_id: Uuid,
+    pub customer_id: Uuid,
+    pub role: AccountHolderRole,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
 }
 
+pub fn format_account_number(branch_code: i32, sequence: i64) -> String {
+    let base = format!("{:04}{:08}", branch_code, sequence);
+    let check_digits = mod97_check_digits(&base).unwrap_or_default();
+    format!("{}{:02}", base, check_digits)
+}
+
+// `customer_id` becomes the account's primary holder
 pub async fn insert_account(
     pool: &PgPool,
+    audit: &AuditContext,
     branch_id: Uuid,
     bank_id: Uuid,
     balance: i32,
@@ -40,28 +69,45 @@ pub async fn insert_account(
     let validated_balance =
         validate_input(balance, validate_account_balance)
             .map_err(|validation_result| {
-                CustomerErrorReps::InvalidInput(validation_result.error_message.unwrap())
+                CustomerErrorReps::InvalidInput(validation_result.error_message.unwrap_or_default())
             })?;
 
     let validated_opened_date =
         validate_input(opened_date, validate_account_opened_date)
             .map_err(|validation_result| {
-                CustomerErrorReps::InvalidInput(validation_result.error_message.unwrap())
+                CustomerErrorReps::InvalidInput(validation_result.error_message.unwrap_or_default())
             })?;
 
     let account_type = AccountType::Checkings;
     let account_id = Uuid::new_v4();
     let current_timestamp = chrono::Utc::now().naive_utc();
-    let mut rng = rand::thread_rng();
-    let random_number: u64 = rng.gen_range(0..=999_999_999_99);
-    let account_number = format!("0{:011}", random_number);
+
+    let mut transaction = pool.begin().await?;
+
+    let branch_code = sqlx::query_scalar!(
+        r#"
+        SELECT branch_code FROM branches WHERE id = $1 AND bank_id = $2
+        "#,
+        branch_id,
+        bank_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::BranchNotFound)?;
+
+    // The sequence is shared by all branches, so numbers stay unique bank-wide even if
+    // the transaction is rolled back and a sequence value is skipped
+    let sequence = sqlx::query_scalar!(r#"SELECT nextval('account_number_seq') AS "sequence!""#)
+        .fetch_one(&mut transaction)
+        .await?;
+    let account_number = format_account_number(branch_code, sequence);
 
     let account = sqlx::query_as!(
         Account,
         r#"
-        INSERT INTO accounts (branch_id, bank_id, id, account_number, balance, account_type, customer_id, opened_date, last_updated_date, inserted_at, updated_at)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
-        RETURNING account_type as "account_type: _", branch_id, bank_id, id, account_number, balance, customer_id, opened_date, last_updated_date, inserted_at, updated_at
+        INSERT INTO accounts (branch_id, bank_id, id, account_number, balance, account_type, opened_date, last_updated_date, inserted_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        RETURNING account_type as "account_type: _", branch_id, bank_id, id, account_number, balance, opened_date, last_updated_date, inserted_at, updated_at, status as "status: _", status_reason, last_activity_at, closed_at, overdraft_limit, overdraft_rate_bps
         "#,
         branch_id,
         bank_id,
@@ -69,13 +115,658 @@ pub async fn insert_account(
         account_number,
         validated_balance,
         account_type as AccountType,
-        customer_id,
         validated_opened_date,
         current_timestamp,
     )
-    .fetch_one(pool)
+    .fetch_one(&mut transaction)
     .await
     .map_err(CustomerErrorReps::DatabaseError)?;
 
+    insert_holder(&mut transaction, account.id, customer_id, AccountHolderRole::Primary).await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("account.create", "account", account.id, Some(account.bank_id)).after(&account),
+    )
+    .await?;
+
+    outbox::publish(
+        &mut transaction,
+        Some(account.bank_id),
+        &DomainEvent::AccountOpened(account.clone()),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
     Ok(account)
 }
+
+pub async fn get_by_id(pool: &PgPool, account_id: Uuid) -> Result<Option<Account>, sqlx::Error> {
+    let account = sqlx::query_as!(
+        Account,
+        r#"
+        SELECT account_type as "account_type: _", branch_id, bank_id, id, account_number, balance, opened_date, last_updated_date, inserted_at, updated_at, status as "status: _", status_reason, last_activity_at, closed_at, overdraft_limit, overdraft_rate_bps
+        FROM accounts
+        WHERE id = $1
+        "#,
+        account_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(account)
+}
+
+// Every account the customer holds, in any role
+pub async fn get_by_customer_id(
+    pool: &PgPool,
+    customer_id: Uuid,
+) -> Result<Vec<Account>, sqlx::Error> {
+    let accounts = sqlx::query_as!(
+        Account,
+        r#"
+        SELECT a.account_type as "account_type: _", a.branch_id, a.bank_id, a.id, a.account_number, a.balance, a.opened_date, a.last_updated_date, a.inserted_at, a.updated_at, a.status as "status: _", a.status_reason, a.last_activity_at, a.closed_at, a.overdraft_limit, a.overdraft_rate_bps
+        FROM accounts AS a
+        INNER JOIN account_holders AS h ON h.account_id = a.id
+        WHERE h.customer_id = $1
+        ORDER BY a.opened_date, a.account_number
+        "#,
+        customer_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(accounts)
+}
+
+pub async fn get_holders(
+    pool: &PgPool,
+    account_id: Uuid,
+) -> Result<Vec<AccountHolder>, sqlx::Error> {
+    let holders = sqlx::query_as!(
+        AccountHolder,
+        r#"
+        SELECT account_id, customer_id, role as "role: _", inserted_at, updated_at
+        FROM account_holders
+        WHERE account_id = $1
+        ORDER BY role, inserted_at
+        "#,
+        account_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(holders)
+}
+
+async fn insert_holder(
+    conn: &mut PgConnection,
+    account_id: Uuid,
+    customer_id: Uuid,
+    role: AccountHolderRole,
+) -> Result<AccountHolder, sqlx::Error> {
+    sqlx::query_as!(
+        AccountHolder,
+        r#"
+        INSERT INTO account_holders (account_id, customer_id, role, inserted_at, updated_at)
+        VALUES ($1, $2, $3, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        RETURNING account_id, customer_id, role as "role: _", inserted_at, updated_at
+        "#,
+        account_id,
+        customer_id,
+        role as AccountHolderRole
+    )
+    .fetch_one(conn)
+    .await
+}
+
+// Adds a joint holder or authorized signer. The primary holder is set when the account
+// is opened and cannot be added or removed afterwards.
+pub async fn add_holder(
+    pool: &PgPool,
+    audit: &AuditContext,
+    account_id: Uuid,
+    customer_id: Uuid,
+    role: AccountHolderRole,
+) -> Result<AccountHolder, CustomerErrorReps> {
+    if role == AccountHolderRole::Primary {
+        return Err(CustomerErrorReps::InvalidInput(
+            "An account has exactly one primary holder.".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    let account = sqlx::query!(
+        r#"
+        SELECT bank_id, status as "status: AccountStatus" FROM accounts WHERE id = $1 FOR UPDATE
+        "#,
+        account_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+    ensure_can_credit(account.status)?;
+
+    let customer_bank_id = sqlx::query_scalar!(
+        r#"
+        SELECT bank_id FROM customers WHERE id = $1
+        "#,
+        customer_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    if customer_bank_id != account.bank_id {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Holders must be customers of the account's bank.".to_string(),
+        ));
+    }
+
+    let already_holder = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM account_holders WHERE account_id = $1 AND customer_id = $2
+        ) AS "exists!"
+        "#,
+        account_id,
+        customer_id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    if already_holder {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Customer already holds this account.".to_string(),
+        ));
+    }
+
+    let holder = insert_holder(&mut transaction, account_id, customer_id, role).await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("account_holder.add", "account", account_id, Some(account.bank_id))
+            .after(&holder),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(holder)
+}
+
+pub async fn remove_holder(
+    pool: &PgPool,
+    audit: &AuditContext,
+    account_id: Uuid,
+    customer_id: Uuid,
+) -> Result<AccountHolder, CustomerErrorReps> {
+    let mut transaction = pool.begin().await?;
+
+    let holder = sqlx::query_as!(
+        AccountHolder,
+        r#"
+        DELETE FROM account_holders
+        WHERE account_id = $1 AND customer_id = $2 AND role <> 'primary'
+        RETURNING account_id, customer_id, role as "role: _", inserted_at, updated_at
+        "#,
+        account_id,
+        customer_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    let bank_id = sqlx::query_scalar!(
+        r#"
+        SELECT bank_id FROM accounts WHERE id = $1
+        "#,
+        account_id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("account_holder.remove", "account", account_id, Some(bank_id))
+            .before(&holder),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(holder)
+}
+
+// Frozen and dormant accounts can still receive money; only closed accounts refuse credits.
+pub fn ensure_can_credit(status: AccountStatus) -> Result<(), CustomerErrorReps> {
+    match status {
+        AccountStatus::Closed => Err(CustomerErrorReps::AccountUnavailable(status.as_str())),
+        _ => Ok(()),
+    }
+}
+
+pub fn ensure_can_debit(status: AccountStatus) -> Result<(), CustomerErrorReps> {
+    match status {
+        AccountStatus::Active => Ok(()),
+        _ => Err(CustomerErrorReps::AccountUnavailable(status.as_str())),
+    }
+}
+
+// Customer debits may take the balance down to the negative of the arranged overdraft limit
+pub fn ensure_funds(
+    balance: i32,
+    overdraft_limit: i32,
+    amount: i32,
+) -> Result<(), CustomerErrorReps> {
+    if i64::from(balance) + i64::from(overdraft_limit) < i64::from(amount) {
+        return Err(CustomerErrorReps::InsufficientFunds);
+    }
+    Ok(())
+}
+
+async fn lock_branch_account(
+    conn: &mut PgConnection,
+    branch_id: Uuid,
+    account_id: Uuid,
+) -> Result<Account, CustomerErrorReps> {
+    sqlx::query_as!(
+        Account,
+        r#"
+        SELECT account_type as "account_type: _", branch_id, bank_id, id, account_number, balance, opened_date, last_updated_date, inserted_at, updated_at, status as "status: _", status_reason, last_activity_at, closed_at, overdraft_limit, overdraft_rate_bps
+        FROM accounts
+        WHERE id = $1 AND branch_id = $2
+        FOR UPDATE
+        "#,
+        account_id,
+        branch_id
+    )
+    .fetch_optional(conn)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)
+}
+
+async fn change_status(
+    pool: &PgPool,
+    audit: &AuditContext,
+    branch_id: Uuid,
+    account_id: Uuid,
+    allowed_from: &[AccountStatus],
+    status: AccountStatus,
+    reason: Option<String>,
+    action: &str,
+) -> Result<Account, CustomerErrorReps> {
+    let mut transaction = pool.begin().await?;
+
+    let before = lock_branch_account(&mut transaction, branch_id, account_id).await?;
+    if !allowed_from.contains(&before.status) {
+        return Err(CustomerErrorReps::InvalidInput(format!(
+            "A {} account cannot be made {}.",
+            before.status.as_str(),
+            status.as_str()
+        )));
+    }
+
+    // Returning to active restarts the dormancy clock
+    let after = sqlx::query_as!(
+        Account,
+        r#"
+        UPDATE accounts
+        SET status = $2,
+            status_reason = $3,
+            last_activity_at = CASE WHEN $2 = 'active'::accountstatus THEN CURRENT_TIMESTAMP ELSE last_activity_at END,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1
+        RETURNING account_type as "account_type: _", branch_id, bank_id, id, account_number, balance, opened_date, last_updated_date, inserted_at, updated_at, status as "status: _", status_reason, last_activity_at, closed_at, overdraft_limit, overdraft_rate_bps
+        "#,
+        account_id,
+        status as AccountStatus,
+        reason
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new(action, "account", after.id, Some(after.bank_id))
+            .before(&before)
+            .after(&after),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(after)
+}
+
+// Blocks debits until the account is unfrozen; credits still post
+pub async fn freeze_account(
+    pool: &PgPool,
+    audit: &AuditContext,
+    branch_id: Uuid,
+    account_id: Uuid,
+    reason: String,
+) -> Result<Account, CustomerErrorReps> {
+    change_status(
+        pool,
+        audit,
+        branch_id,
+        account_id,
+        &[AccountStatus::Active, AccountStatus::Dormant],
+        AccountStatus::Frozen,
+        Some(reason),
+        "account.freeze",
+    )
+    .await
+}
+
+pub async fn unfreeze_account(
+    pool: &PgPool,
+    audit: &AuditContext,
+    branch_id: Uuid,
+    account_id: Uuid,
+) -> Result<Account, CustomerErrorReps> {
+    change_status(
+        pool,
+        audit,
+        branch_id,
+        account_id,
+        &[AccountStatus::Frozen],
+        AccountStatus::Active,
+        None,
+        "account.unfreeze",
+    )
+    .await
+}
+
+// A dormant account is reactivated at the branch once the customer has been identified
+pub async fn reactivate_account(
+    pool: &PgPool,
+    audit: &AuditContext,
+    branch_id: Uuid,
+    account_id: Uuid,
+) -> Result<Account, CustomerErrorReps> {
+    change_status(
+        pool,
+        audit,
+        branch_id,
+        account_id,
+        &[AccountStatus::Dormant],
+        AccountStatus::Active,
+        None,
+        "account.reactivate",
+    )
+    .await
+}
+
+// Arranges (or with a limit of 0, removes) an overdraft on a checking account. Lowering
+// the limit below what is already overdrawn leaves the excess unarranged.
+pub async fn set_overdraft(
+    pool: &PgPool,
+    audit: &AuditContext,
+    branch_id: Uuid,
+    account_id: Uuid,
+    overdraft_limit: i32,
+    overdraft_rate_bps: i32,
+) -> Result<Account, CustomerErrorReps> {
+    if overdraft_limit < 0 {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Overdraft limit cannot be negative.".to_string(),
+        ));
+    }
+    if !(0..=10_000).contains(&overdraft_rate_bps) {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Overdraft rate must be between 0 and 10000 basis points.".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    let before = lock_branch_account(&mut transaction, branch_id, account_id).await?;
+    // Closing moves the balance out, so it needs what a debit needs; a frozen account
+    // stays open until it is unfrozen and a dormant one is reactivated first
+    if before.status == AccountStatus::Frozen {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Frozen accounts cannot be closed.".to_string(),
+        ));
+    }
+    ensure_can_debit(before.status)?;
+    if before.account_type != AccountType::Checkings {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Only checking accounts can have an overdraft.".to_string(),
+        ));
+    }
+
+    let after = sqlx::query_as!(
+        Account,
+        r#"
+        UPDATE accounts
+        SET overdraft_limit = $2, overdraft_rate_bps = $3, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1
+        RETURNING account_type as "account_type: _", branch_id, bank_id, id, account_number, balance, opened_date, last_updated_date, inserted_at, updated_at, status as "status: _", status_reason, last_activity_at, closed_at, overdraft_limit, overdraft_rate_bps
+        "#,
+        account_id,
+        overdraft_limit,
+        overdraft_rate_bps
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("account.overdraft", "account", after.id, Some(after.bank_id))
+            .before(&before)
+            .after(&after),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(after)
+}
+
+// Marks active accounts without customer activity for their bank's dormancy period as
+// dormant. Returns the number of accounts affected.
+// Only the given bank's accounts when `bank_id` is set
+pub async fn mark_dormant_accounts(
+    pool: &PgPool,
+    bank_id: Option<Uuid>,
+    now: NaiveDateTime,
+) -> Result<usize, sqlx::Error> {
+    let audit = AuditContext::system("dormancy");
+    let mut transaction = pool.begin().await?;
+
+    let accounts = sqlx::query!(
+        r#"
+        UPDATE accounts AS a
+        SET status = 'dormant', status_reason = 'No customer activity', updated_at = CURRENT_TIMESTAMP
+        FROM banks AS k
+        WHERE k.id = a.bank_id
+            AND a.status = 'active'
+            AND a.last_activity_at < $1::timestamp - make_interval(months => k.dormancy_months)
+            AND ($2::uuid IS NULL OR a.bank_id = $2)
+        RETURNING a.id, a.bank_id, a.account_number, a.last_activity_at
+        "#,
+        now,
+        bank_id
+    )
+    .fetch_all(&mut transaction)
+    .await?;
+
+    for account in &accounts {
+        audit::record(
+            &mut transaction,
+            &audit,
+            Change::new("account.dormant", "account", account.id, Some(account.bank_id)).after(
+                &serde_json::json!({
+                    "account_number": account.account_number,
+                    "status": AccountStatus::Dormant,
+                    "last_activity_at": account.last_activity_at,
+                }),
+            ),
+        )
+        .await?;
+    }
+
+    transaction.commit().await?;
+
+    Ok(accounts.len())
+}
+
+// Closes an account held at `branch_id`. Any remaining balance is swept to the nominated
+// account, which must be held with the same bank; accounts with a negative balance, open
+// loans, an unpaid credit line or transactions held for fraud review cannot be closed.
+pub async fn close_account(
+    pool: &PgPool,
+    audit: &AuditContext,
+    branch_id: Uuid,
+    account_id: Uuid,
+    sweep_to_account_number: Option<&str>,
+) -> Result<Account, CustomerErrorReps> {
+    let sweep_to_account_number = match sweep_to_account_number {
+        Some(number) => Some(iban::resolve_account_number(pool, number).await?),
+        None => None,
+    };
+
+    let mut transaction = pool.begin().await?;
+
+    let before = lock_branch_account(&mut transaction, branch_id, account_id).await?;
+    ensure_can_credit(before.status)?;
+
+    if before.balance < 0 {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Account has a negative balance.".to_string(),
+        ));
+    }
+
+    let blockers = sqlx::query!(
+        r#"
+        SELECT
+            EXISTS (
+                SELECT 1 FROM loans AS l
+                INNER JOIN cards AS c ON c.card_number = l.borrower_card_number
+                WHERE c.account_number = $1 AND l.status IN ('pending', 'approved')
+            ) AS "open_loans!",
+            EXISTS (
+                SELECT 1 FROM credit_accounts WHERE account_id = $2 AND current_balance > 0
+            ) AS "credit_owed!",
+            EXISTS (
+                SELECT 1 FROM fraud_evaluations WHERE account_number = $1 AND review_status = 'pending'
+            ) AS "held!"
+        "#,
+        before.account_number,
+        before.id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    if blockers.open_loans {
+        return Err(CustomerErrorReps::InvalidInput("Account has open loans.".to_string()));
+    }
+    if blockers.credit_owed {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Account has an outstanding credit card balance.".to_string(),
+        ));
+    }
+    if blockers.held {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Account has transactions held for review.".to_string(),
+        ));
+    }
+
+    if before.balance > 0 {
+        let sweep_to_account_number = sweep_to_account_number.as_deref().ok_or_else(|| {
+            CustomerErrorReps::InvalidInput(
+                "A nominated account is required to sweep the remaining balance.".to_string(),
+            )
+        })?;
+        if sweep_to_account_number == before.account_number {
+            return Err(CustomerErrorReps::InvalidInput(
+                "Cannot sweep the balance into the account being closed.".to_string(),
+            ));
+        }
+
+        let nominated = sqlx::query!(
+            r#"
+            UPDATE accounts
+            SET balance = balance + $1, last_updated_date = CURRENT_DATE, updated_at = CURRENT_TIMESTAMP
+            WHERE account_number = $2 AND bank_id = $3
+            RETURNING bank_id, branch_id, status as "status: AccountStatus"
+            "#,
+            before.balance,
+            sweep_to_account_number,
+            before.bank_id
+        )
+        .fetch_optional(&mut transaction)
+        .await?
+        .ok_or(CustomerErrorReps::NotFound)?;
+        ensure_can_credit(nominated.status)?;
+
+        let calendar = calendar::load(&mut transaction, before.bank_id).await?;
+        transactions::insert_transaction(
+            &mut transaction,
+            &calendar,
+            before.branch_id,
+            before.bank_id,
+            &before.account_number,
+            None,
+            TransactionType::ClosureSweep,
+            EntryDirection::Debit,
+            before.balance,
+            Status::Approved,
+        )
+        .await?;
+
+        transactions::insert_transaction(
+            &mut transaction,
+            &calendar,
+            nominated.branch_id,
+            nominated.bank_id,
+            sweep_to_account_number,
+            None,
+            TransactionType::ClosureSweep,
+            EntryDirection::Credit,
+            before.balance,
+            Status::Approved,
+        )
+        .await?;
+    }
+
+    let after = sqlx::query_as!(
+        Account,
+        r#"
+        UPDATE accounts
+        SET status = 'closed',
+            status_reason = NULL,
+            balance = 0,
+            closed_at = CURRENT_TIMESTAMP,
+            last_updated_date = CURRENT_DATE,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1
+        RETURNING account_type as "account_type: _", branch_id, bank_id, id, account_number, balance, opened_date, last_updated_date, inserted_at, updated_at, status as "status: _", status_reason, last_activity_at, closed_at, overdraft_limit, overdraft_rate_bps
+        "#,
+        before.id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("account.close", "account", after.id, Some(after.bank_id))
+            .before(&before)
+            .after(&after),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(after)
+}
@@ -0,0 +1,378 @@
+use chrono::NaiveDateTime;
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::bank::helper::validation::CustomerErrorReps;
+
+use super::{
+    audit::{self, AuditContext, Change},
+    fraud::ScreeningContext,
+};
+
+pub const SCOPE_PAYMENTS_WRITE: &str = "payments:write";
+pub const SCOPE_REFUNDS_WRITE: &str = "refunds:write";
+// Issued to the bank's ATMs, which pay out cash against debit cards
+pub const SCOPE_ATM_WRITE: &str = "atm:write";
+pub const KNOWN_SCOPES: [&str; 3] = [SCOPE_PAYMENTS_WRITE, SCOPE_REFUNDS_WRITE, SCOPE_ATM_WRITE];
+
+// Rotation keeps the previous key usable until the integration switches over
+pub const MAX_ACTIVE_KEYS: i64 = 2;
+
+const KEY_PREFIX_LEN: usize = 8;
+const KEY_SECRET_LEN: usize = 40;
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct ApiClient {
+    pub id: Uuid,
+    pub client_name: String,
+    pub bank_id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub merchant_country: Option<String>,
+    pub merchant_category: Option<String>,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub client_id: Uuid,
+    pub bank_id: Uuid,
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// Returned once when a key is issued; the plaintext is never stored
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssuedApiKey {
+    pub api_key: ApiKey,
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKeyPrincipal {
+    pub key_id: Uuid,
+    pub client_id: Uuid,
+    pub bank_id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub merchant_country: Option<String>,
+    pub merchant_category: Option<String>,
+    pub scopes: Vec<String>,
+}
+
+impl ApiKeyPrincipal {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|granted| granted == scope)
+    }
+
+    // Card operations are screened against the client's registered merchant profile
+    pub fn screening_context(&self) -> ScreeningContext {
+        ScreeningContext {
+            country: self.merchant_country.clone(),
+            merchant_category: self.merchant_category.clone(),
+        }
+    }
+}
+
+pub fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+fn random_alphanumeric(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+// Keys look like `bk_<prefix>_<secret>`; the prefix is stored in clear for lookup.
+pub fn split_key(key: &str) -> Option<(&str, &str)> {
+    let rest = key.strip_prefix("bk_")?;
+    let (prefix, secret) = rest.split_once('_')?;
+    if prefix.len() != KEY_PREFIX_LEN || secret.len() != KEY_SECRET_LEN {
+        return None;
+    }
+    Some((prefix, secret))
+}
+
+pub fn validate_scopes(scopes: &[String]) -> Result<(), CustomerErrorReps> {
+    if scopes.is_empty() {
+        return Err(CustomerErrorReps::InvalidInput(
+            "At least one scope is required.".to_string(),
+        ));
+    }
+    if let Some(unknown) = scopes
+        .iter()
+        .find(|scope| !KNOWN_SCOPES.contains(&scope.as_str()))
+    {
+        return Err(CustomerErrorReps::InvalidInput(format!(
+            "Unknown scope: {}",
+            unknown
+        )));
+    }
+    Ok(())
+}
+
+pub async fn create_client(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    client_name: String,
+    account_id: Option<Uuid>,
+    merchant_country: Option<String>,
+    merchant_category: Option<String>,
+) -> Result<ApiClient, CustomerErrorReps> {
+    if client_name.trim().is_empty() {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Client name is required.".to_string(),
+        ));
+    }
+    if merchant_country.as_ref().map_or(false, |country| {
+        country.len() != 2 || !country.chars().all(|c| c.is_ascii_uppercase())
+    }) {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Merchant country must be a two-letter ISO code.".to_string(),
+        ));
+    }
+    if merchant_category.as_ref().map_or(false, |category| {
+        category.len() != 4 || !category.chars().all(|c| c.is_ascii_digit())
+    }) {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Merchant category must be a four-digit code.".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    if let Some(account_id) = account_id {
+        sqlx::query_scalar!(
+            r#"
+            SELECT id FROM accounts WHERE id = $1 AND bank_id = $2
+            "#,
+            account_id,
+            bank_id
+        )
+        .fetch_optional(&mut transaction)
+        .await?
+        .ok_or(CustomerErrorReps::NotFound)?;
+    }
+
+    let client = sqlx::query_as!(
+        ApiClient,
+        r#"
+        INSERT INTO api_clients (id, client_name, bank_id, account_id, merchant_country, merchant_category, inserted_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        RETURNING id, client_name, bank_id, account_id, merchant_country, merchant_category, inserted_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        client_name,
+        bank_id,
+        account_id,
+        merchant_country,
+        merchant_category
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("api_client.create", "api_client", client.id, Some(bank_id)).after(&client),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(client)
+}
+
+// Issues a new key for the client. If that leaves more than MAX_ACTIVE_KEYS active,
+// the oldest ones are revoked, so issuing a key doubles as rotation.
+pub async fn issue_key(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    client_id: Uuid,
+    scopes: Vec<String>,
+) -> Result<IssuedApiKey, CustomerErrorReps> {
+    validate_scopes(&scopes)?;
+
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query_scalar!(
+        r#"
+        SELECT id FROM api_clients WHERE id = $1 AND bank_id = $2 FOR UPDATE
+        "#,
+        client_id,
+        bank_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    let key_prefix = random_alphanumeric(KEY_PREFIX_LEN);
+    let secret = random_alphanumeric(KEY_SECRET_LEN);
+
+    let api_key = sqlx::query_as!(
+        ApiKey,
+        r#"
+        INSERT INTO api_keys (id, client_id, bank_id, key_prefix, key_hash, scopes, inserted_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        RETURNING id, client_id, bank_id, key_prefix, scopes, last_used_at, revoked_at, inserted_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        client_id,
+        bank_id,
+        key_prefix,
+        hash_secret(&secret),
+        &scopes
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("api_key.issue", "api_key", api_key.id, Some(bank_id)).after(&api_key),
+    )
+    .await?;
+
+    let rotated_out = sqlx::query_scalar!(
+        r#"
+        UPDATE api_keys
+        SET revoked_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+        WHERE id IN (
+            SELECT id FROM api_keys
+            WHERE client_id = $1 AND revoked_at IS NULL
+            ORDER BY inserted_at DESC
+            OFFSET $2
+        )
+        RETURNING id
+        "#,
+        client_id,
+        MAX_ACTIVE_KEYS
+    )
+    .fetch_all(&mut transaction)
+    .await?;
+
+    for key_id in rotated_out {
+        audit::record(
+            &mut transaction,
+            audit,
+            Change::new("api_key.rotate_out", "api_key", key_id, Some(bank_id)),
+        )
+        .await?;
+    }
+
+    transaction.commit().await?;
+
+    let secret = format!("bk_{}_{}", api_key.key_prefix, secret);
+    Ok(IssuedApiKey { api_key, secret })
+}
+
+pub async fn revoke_key(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    key_id: Uuid,
+) -> Result<ApiKey, CustomerErrorReps> {
+    let mut transaction = pool.begin().await?;
+
+    let api_key = sqlx::query_as!(
+        ApiKey,
+        r#"
+        UPDATE api_keys
+        SET revoked_at = COALESCE(revoked_at, CURRENT_TIMESTAMP), updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND bank_id = $2
+        RETURNING id, client_id, bank_id, key_prefix, scopes, last_used_at, revoked_at, inserted_at, updated_at
+        "#,
+        key_id,
+        bank_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("api_key.revoke", "api_key", api_key.id, Some(bank_id)).after(&api_key),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(api_key)
+}
+
+pub async fn get_keys(
+    pool: &PgPool,
+    bank_id: Uuid,
+    client_id: Uuid,
+) -> Result<Vec<ApiKey>, sqlx::Error> {
+    let api_keys = sqlx::query_as!(
+        ApiKey,
+        r#"
+        SELECT id, client_id, bank_id, key_prefix, scopes, last_used_at, revoked_at, inserted_at, updated_at
+        FROM api_keys
+        WHERE client_id = $1 AND bank_id = $2
+        ORDER BY inserted_at DESC
+        "#,
+        client_id,
+        bank_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(api_keys)
+}
+
+// Resolves a presented key to its principal and records the use. Malformed, unknown,
+// revoked and mismatching keys all produce the same error.
+pub async fn authenticate_key(pool: &PgPool, key: &str) -> Result<ApiKeyPrincipal, CustomerErrorReps> {
+    let (key_prefix, secret) = split_key(key).ok_or(CustomerErrorReps::Unauthorized)?;
+
+    let api_key = sqlx::query!(
+        r#"
+        SELECT k.id, k.client_id, k.bank_id, k.key_hash, k.scopes, c.account_id, c.merchant_country, c.merchant_category
+        FROM api_keys AS k
+        INNER JOIN api_clients AS c ON c.id = k.client_id
+        WHERE k.key_prefix = $1 AND k.revoked_at IS NULL
+        "#,
+        key_prefix
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(CustomerErrorReps::Unauthorized)?;
+
+    if api_key.key_hash != hash_secret(secret) {
+        return Err(CustomerErrorReps::Unauthorized);
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE api_keys SET last_used_at = CURRENT_TIMESTAMP WHERE id = $1
+        "#,
+        api_key.id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(ApiKeyPrincipal {
+        key_id: api_key.id,
+        client_id: api_key.client_id,
+        bank_id: api_key.bank_id,
+        account_id: api_key.account_id,
+        merchant_country: api_key.merchant_country,
+        merchant_category: api_key.merchant_category,
+        scopes: api_key.scopes,
+    })
+}
@@ -0,0 +1,314 @@
+use chrono::{NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+// Together with the chain's bank, serializes writers so each event sees the hash of the
+// one before it in its chain
+const AUDIT_CHAIN_LOCK: i32 = 0x6175_6469;
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// Who is performing a mutation, carried from the request (or job) into the model layer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditContext {
+    pub actor_type: String,
+    pub actor_id: Option<Uuid>,
+    pub request_id: Option<String>,
+}
+
+impl AuditContext {
+    pub fn new(actor_type: impl Into<String>, actor_id: Option<Uuid>, request_id: Option<String>) -> Self {
+        Self {
+            actor_type: actor_type.into(),
+            actor_id,
+            request_id,
+        }
+    }
+
+    pub fn system(job: &str) -> Self {
+        Self::new(format!("system:{}", job), None, None)
+    }
+}
+
+// A single state change to record alongside the mutation that caused it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub action: &'static str,
+    pub entity_type: &'static str,
+    pub entity_id: Uuid,
+    pub bank_id: Option<Uuid>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl Change {
+    pub fn new(action: &'static str, entity_type: &'static str, entity_id: Uuid, bank_id: Option<Uuid>) -> Self {
+        Self {
+            action,
+            entity_type,
+            entity_id,
+            bank_id,
+            before: None,
+            after: None,
+        }
+    }
+
+    pub fn before(mut self, state: &impl Serialize) -> Self {
+        self.before = serde_json::to_value(state).ok();
+        self
+    }
+
+    pub fn after(mut self, state: &impl Serialize) -> Self {
+        self.after = serde_json::to_value(state).ok();
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub sequence: i64,
+    pub id: Uuid,
+    pub occurred_at: NaiveDateTime,
+    pub actor_type: String,
+    pub actor_id: Option<Uuid>,
+    pub request_id: Option<String>,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub bank_id: Option<Uuid>,
+    pub before_state: Option<Value>,
+    pub after_state: Option<Value>,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditFilter {
+    pub bank_id: Uuid,
+    pub entity_type: Option<String>,
+    pub entity_id: Option<Uuid>,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+    pub limit: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainVerification {
+    pub events_checked: i64,
+    pub first_broken_sequence: Option<i64>,
+    // Pass as `after_sequence` to continue with the next page
+    pub last_sequence: Option<i64>,
+}
+
+// JSON with object keys sorted at every level, so hashes do not depend on how Postgres
+// or serde happen to order JSONB keys
+fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let fields: Vec<String> = keys
+                .into_iter()
+                .map(|key| format!("{}:{}", Value::String(key.clone()), canonical_json(&map[key])))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+pub fn compute_hash(event: &AuditEvent) -> String {
+    let content = serde_json::json!({
+        "sequence": event.sequence,
+        "id": event.id,
+        "occurred_at": event.occurred_at.format("%Y-%m-%dT%H:%M:%S%.6f").to_string(),
+        "actor_type": event.actor_type,
+        "actor_id": event.actor_id,
+        "request_id": event.request_id,
+        "action": event.action,
+        "entity_type": event.entity_type,
+        "entity_id": event.entity_id,
+        "bank_id": event.bank_id,
+        "before_state": event.before_state,
+        "after_state": event.after_state,
+    });
+
+    let mut hasher = Sha256::new();
+    hasher.update(event.prev_hash.as_bytes());
+    hasher.update(canonical_json(&content).as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+// Must be called on the same connection/transaction as the mutation being recorded.
+// Events are chained per bank, so only writers for the same bank wait on each other.
+pub async fn record(
+    conn: &mut PgConnection,
+    audit: &AuditContext,
+    change: Change,
+) -> Result<AuditEvent, sqlx::Error> {
+    sqlx::query!(
+        "SELECT pg_advisory_xact_lock($1, hashtext(COALESCE($2::uuid::text, '')))",
+        AUDIT_CHAIN_LOCK,
+        change.bank_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let prev_hash = sqlx::query_scalar!(
+        r#"
+        SELECT hash FROM audit_events
+        WHERE ($1::uuid IS NULL AND bank_id IS NULL) OR bank_id = $1
+        ORDER BY sequence DESC
+        LIMIT 1
+        "#,
+        change.bank_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .unwrap_or_else(|| GENESIS_HASH.to_string());
+
+    // Taken under the chain lock, so sequences increase along each chain
+    let sequence = sqlx::query_scalar!(
+        r#"
+        SELECT nextval('audit_events_sequence_seq') AS "sequence!"
+        "#
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    // Postgres keeps microseconds; truncate first so the hash survives the round trip
+    let now = chrono::Utc::now().naive_utc();
+    let occurred_at = now.with_nanosecond(now.nanosecond() / 1_000 * 1_000).unwrap_or(now);
+
+    let mut event = AuditEvent {
+        sequence,
+        id: Uuid::new_v4(),
+        occurred_at,
+        actor_type: audit.actor_type.clone(),
+        actor_id: audit.actor_id,
+        request_id: audit.request_id.clone(),
+        action: change.action.to_string(),
+        entity_type: change.entity_type.to_string(),
+        entity_id: change.entity_id,
+        bank_id: change.bank_id,
+        before_state: change.before,
+        after_state: change.after,
+        prev_hash,
+        hash: String::new(),
+    };
+    event.hash = compute_hash(&event);
+
+    sqlx::query!(
+        r#"
+        INSERT INTO audit_events (sequence, id, occurred_at, actor_type, actor_id, request_id, action, entity_type, entity_id, bank_id, before_state, after_state, prev_hash, hash)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+        "#,
+        event.sequence,
+        event.id,
+        event.occurred_at,
+        event.actor_type,
+        event.actor_id,
+        event.request_id,
+        event.action,
+        event.entity_type,
+        event.entity_id,
+        event.bank_id,
+        event.before_state,
+        event.after_state,
+        event.prev_hash,
+        event.hash
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(event)
+}
+
+pub async fn query_events(pool: &PgPool, filter: &AuditFilter) -> Result<Vec<AuditEvent>, sqlx::Error> {
+    let events = sqlx::query_as!(
+        AuditEvent,
+        r#"
+        SELECT sequence, id, occurred_at, actor_type, actor_id, request_id, action, entity_type, entity_id, bank_id, before_state, after_state, prev_hash, hash
+        FROM audit_events
+        WHERE bank_id = $1
+          AND ($2::text IS NULL OR entity_type = $2)
+          AND ($3::uuid IS NULL OR entity_id = $3)
+          AND ($4::timestamp IS NULL OR occurred_at >= $4)
+          AND ($5::timestamp IS NULL OR occurred_at < $5)
+        ORDER BY sequence DESC
+        LIMIT $6
+        "#,
+        filter.bank_id,
+        filter.entity_type,
+        filter.entity_id,
+        filter.from,
+        filter.to,
+        filter.limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(events)
+}
+
+// Walks one bank's chain in order from `after_sequence`, recomputing up to `limit`
+// hashes. Reports the first event whose stored hash or back-link does not match.
+pub async fn verify_chain(
+    pool: &PgPool,
+    bank_id: Uuid,
+    after_sequence: Option<i64>,
+    limit: i64,
+) -> Result<ChainVerification, sqlx::Error> {
+    let mut expected_prev_hash = match after_sequence {
+        Some(sequence) => sqlx::query_scalar!(
+            r#"
+            SELECT hash FROM audit_events WHERE bank_id = $1 AND sequence = $2
+            "#,
+            bank_id,
+            sequence
+        )
+        .fetch_optional(pool)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?,
+        None => GENESIS_HASH.to_string(),
+    };
+
+    let events = sqlx::query_as!(
+        AuditEvent,
+        r#"
+        SELECT sequence, id, occurred_at, actor_type, actor_id, request_id, action, entity_type, entity_id, bank_id, before_state, after_state, prev_hash, hash
+        FROM audit_events
+        WHERE bank_id = $1 AND sequence > $2
+        ORDER BY sequence
+        LIMIT $3
+        "#,
+        bank_id,
+        after_sequence.unwrap_or(0),
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for (index, event) in events.iter().enumerate() {
+        if event.prev_hash != expected_prev_hash || event.hash != compute_hash(event) {
+            return Ok(ChainVerification {
+                events_checked: index as i64 + 1,
+                first_broken_sequence: Some(event.sequence),
+                last_sequence: Some(event.sequence),
+            });
+        }
+        expected_prev_hash = event.hash.clone();
+    }
+
+    Ok(ChainVerification {
+        events_checked: events.len() as i64,
+        first_broken_sequence: None,
+        last_sequence: events.last().map(|event| event.sequence),
+    })
+}
@@ -0,0 +1,379 @@
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::audit::{self, AuditContext, Change};
+use super::types::{AccountHolderRole, Role};
+use crate::bank::helper::validation::CustomerErrorReps;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claims {
+    // Customer id for customers, staff member id for everyone else
+    pub sub: Uuid,
+    pub role: Role,
+    pub bank_id: Uuid,
+    // Branch the role is limited to; None means bank-wide
+    pub branch_id: Option<Uuid>,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+#[derive(Clone)]
+pub struct JwtConfig {
+    secret: String,
+    pub ttl_seconds: i64,
+}
+
+impl JwtConfig {
+    pub fn new(secret: String, ttl_seconds: i64) -> Self {
+        Self {
+            secret,
+            ttl_seconds,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be in environment");
+        let ttl_seconds = std::env::var("JWT_TTL_SECONDS")
+            .ok()
+            .and_then(|ttl| ttl.parse().ok())
+            .unwrap_or(3600);
+        Self::new(secret, ttl_seconds)
+    }
+
+    pub fn issue_token(&self, principal: &Principal) -> Result<String, jsonwebtoken::errors::Error> {
+        let now = chrono::Utc::now().timestamp();
+        let claims = Claims {
+            sub: principal.subject_id,
+            role: principal.role,
+            bank_id: principal.bank_id,
+            branch_id: principal.branch_id,
+            iat: now,
+            exp: now + self.ttl_seconds,
+        };
+        encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(self.secret.as_bytes()),
+        )
+    }
+
+    pub fn decode_token(&self, token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+        let data = decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(self.secret.as_bytes()),
+            &Validation::new(Algorithm::HS256),
+        )?;
+        Ok(data.claims)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Principal {
+    pub subject_id: Uuid,
+    pub role: Role,
+    pub bank_id: Uuid,
+    pub branch_id: Option<Uuid>,
+}
+
+impl From<Claims> for Principal {
+    fn from(claims: Claims) -> Self {
+        Self {
+            subject_id: claims.sub,
+            role: claims.role,
+            bank_id: claims.bank_id,
+            branch_id: claims.branch_id,
+        }
+    }
+}
+
+impl Principal {
+    // Whether the principal may act on the given bank, and on the given branch when one
+    // is supplied. Branch-scoped roles only reach their own branch.
+    pub fn in_scope(&self, bank_id: Uuid, branch_id: Option<Uuid>) -> bool {
+        if self.bank_id != bank_id {
+            return false;
+        }
+        match (self.branch_id, branch_id) {
+            (Some(own_branch_id), Some(branch_id)) => own_branch_id == branch_id,
+            (Some(_), None) => false,
+            (None, _) => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StaffMember {
+    pub id: Uuid,
+    pub staff_name: String,
+    pub email: String,
+    pub role: Role,
+    pub bank_id: Uuid,
+    pub branch_id: Option<Uuid>,
+}
+
+pub fn validate_password(password: &str) -> Result<(), CustomerErrorReps> {
+    if password.chars().count() < 8 {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Password must be at least 8 characters long.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn hash_password(password: &str) -> Result<String, CustomerErrorReps> {
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let password_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|err| CustomerErrorReps::InvalidInput(err.to_string()))?
+        .to_string();
+    Ok(password_hash)
+}
+
+fn verify_password(password: &str, password_hash: &str) -> Result<(), CustomerErrorReps> {
+    let parsed_hash =
+        PasswordHash::new(password_hash).map_err(|_| CustomerErrorReps::Unauthorized)?;
+    Argon2::default()
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .map_err(|_| CustomerErrorReps::Unauthorized)
+}
+
+// First-time enrollment: the customer proves who they are with their CIC number and
+// email, and picks a password. Credentials cannot be overwritten through this path.
+pub async fn register_credentials(
+    pool: &PgPool,
+    audit: &AuditContext,
+    cic_number: &str,
+    email: &str,
+    password: &str,
+) -> Result<(), CustomerErrorReps> {
+    validate_password(password)?;
+
+    let customer = sqlx::query!(
+        r#"
+        SELECT id, bank_id FROM customers WHERE cic_number = $1 AND email = $2
+        "#,
+        cic_number,
+        email
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    let password_hash = hash_password(password)?;
+    let mut transaction = pool.begin().await?;
+
+    let inserted = sqlx::query!(
+        r#"
+        INSERT INTO customer_credentials (customer_id, password_hash, inserted_at, updated_at)
+        VALUES ($1, $2, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        ON CONFLICT (customer_id) DO NOTHING
+        "#,
+        customer.id,
+        password_hash
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    if inserted.rows_affected() == 0 {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Credentials are already registered for this customer.".to_string(),
+        ));
+    }
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("customer.register_credentials", "customer", customer.id, Some(customer.bank_id)),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+// Unknown emails and wrong passwords produce the same error.
+pub async fn authenticate(
+    pool: &PgPool,
+    email: &str,
+    password: &str,
+) -> Result<Principal, CustomerErrorReps> {
+    let credentials = sqlx::query!(
+        r#"
+        SELECT c.id, c.bank_id, c.branch_id, cc.password_hash
+        FROM customers AS c
+        INNER JOIN customer_credentials AS cc ON cc.customer_id = c.id
+        WHERE c.email = $1
+        "#,
+        email
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(CustomerErrorReps::Unauthorized)?;
+
+    verify_password(password, &credentials.password_hash)?;
+
+    Ok(Principal {
+        subject_id: credentials.id,
+        role: Role::Customer,
+        bank_id: credentials.bank_id,
+        branch_id: Some(credentials.branch_id),
+    })
+}
+
+pub async fn authenticate_staff(
+    pool: &PgPool,
+    email: &str,
+    password: &str,
+) -> Result<Principal, CustomerErrorReps> {
+    let staff_member = sqlx::query!(
+        r#"
+        SELECT id, role as "role: Role", bank_id, branch_id, password_hash
+        FROM staff_members
+        WHERE email = $1
+        "#,
+        email
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(CustomerErrorReps::Unauthorized)?;
+
+    verify_password(password, &staff_member.password_hash)?;
+
+    Ok(Principal {
+        subject_id: staff_member.id,
+        role: staff_member.role,
+        bank_id: staff_member.bank_id,
+        branch_id: staff_member.branch_id,
+    })
+}
+
+pub async fn create_staff_member(
+    pool: &PgPool,
+    audit: &AuditContext,
+    staff_name: String,
+    email: String,
+    password: &str,
+    role: Role,
+    bank_id: Uuid,
+    branch_id: Option<Uuid>,
+) -> Result<StaffMember, CustomerErrorReps> {
+    validate_password(password)?;
+
+    match (role, branch_id) {
+        (Role::Customer, _) => {
+            return Err(CustomerErrorReps::InvalidInput(
+                "Customers cannot be enrolled as staff.".to_string(),
+            ))
+        }
+        (Role::Teller | Role::BranchManager, None) => {
+            return Err(CustomerErrorReps::InvalidInput(
+                "Tellers and branch managers must be assigned to a branch.".to_string(),
+            ))
+        }
+        _ => {}
+    }
+
+    if let Some(branch_id) = branch_id {
+        if get_branch_bank_id(pool, branch_id).await? != bank_id {
+            return Err(CustomerErrorReps::InvalidInput(
+                "Branch does not belong to this bank.".to_string(),
+            ));
+        }
+    }
+
+    let password_hash = hash_password(password)?;
+    let mut transaction = pool.begin().await?;
+
+    let staff_member = sqlx::query_as!(
+        StaffMember,
+        r#"
+        INSERT INTO staff_members (id, staff_name, email, password_hash, role, bank_id, branch_id, inserted_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        RETURNING id, staff_name, email, role as "role: _", bank_id, branch_id
+        "#,
+        Uuid::new_v4(),
+        staff_name,
+        email,
+        password_hash,
+        role as Role,
+        bank_id,
+        branch_id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("staff_member.create", "staff_member", staff_member.id, Some(bank_id))
+            .after(&staff_member),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(staff_member)
+}
+
+pub async fn get_branch_bank_id(pool: &PgPool, branch_id: Uuid) -> Result<Uuid, CustomerErrorReps> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT bank_id FROM branches WHERE id = $1
+        "#,
+        branch_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(CustomerErrorReps::BranchNotFound)
+}
+
+// The customer's role on the account, or None when they do not hold it
+pub async fn customer_account_role(
+    pool: &PgPool,
+    customer_id: Uuid,
+    account_id: Uuid,
+) -> Result<Option<AccountHolderRole>, sqlx::Error> {
+    let role = sqlx::query_scalar!(
+        r#"
+        SELECT role as "role: AccountHolderRole"
+        FROM account_holders
+        WHERE account_id = $1 AND customer_id = $2
+        "#,
+        account_id,
+        customer_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(role)
+}
+
+// The customer's role on the account the card draws on
+pub async fn customer_card_role(
+    pool: &PgPool,
+    customer_id: Uuid,
+    card_number: &str,
+) -> Result<Option<AccountHolderRole>, sqlx::Error> {
+    let role = sqlx::query_scalar!(
+        r#"
+        SELECT h.role as "role: AccountHolderRole"
+        FROM cards AS c
+        INNER JOIN accounts AS a ON a.account_number = c.account_number
+        INNER JOIN account_holders AS h ON h.account_id = a.id
+        WHERE c.card_number = $1 AND h.customer_id = $2
+        "#,
+        card_number,
+        customer_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(role)
+}
@@ -1,14 +1,16 @@
-use chrono::NaiveDateTime;
+use chrono::{NaiveDateTime, NaiveTime};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use uuid::Uuid;
 
+use super::audit::{self, AuditContext, Change};
 use super::branchs::{get_branches_by_bank_id, Branch};
 
 #[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
 pub struct Bank {
     pub id: Uuid,
     pub bank_name: String,
+    // Flat fee on outgoing transfers while the bank has no transfer fee schedule
     pub fee: i32,
     pub total_money: i32,
     pub total_debt_to_collect: i32,
@@ -19,24 +21,46 @@ pub struct Bank {
     pub total_accounts: i32,
     pub total_transactions: i32,
     pub total_customers: i32,
+    pub reserve_ratio_bps: i32,
+    pub ctr_threshold: i32,
+    pub dormancy_months: i32,
+    pub bank_code: i32,
+    pub weekend_days: Vec<i32>,
+    pub cut_off_time: NaiveTime,
 }
 
-pub async fn insert(pool: &PgPool, bank_name: String, fee: i32) -> Result<Uuid, sqlx::Error> {
+pub async fn insert(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_name: String,
+    fee: i32,
+) -> Result<Uuid, sqlx::Error> {
     let bank_id = Uuid::new_v4();
+    let mut transaction = pool.begin().await?;
 
     let bank = sqlx::query!(
         r#"
-        INSERT INTO banks (id, bank_name, fee, total_money, total_debt_to_collect, total_loans_given, inserted_at, updated_at)
-        VALUES ($1, $2, $3, 0, 0, 0, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        INSERT INTO banks (id, bank_name, fee, total_money, total_debt_to_collect, total_loans_given, inserted_at, updated_at, bank_code)
+        VALUES ($1, $2, $3, 0, 0, 0, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, nextval('bank_code_seq'))
         RETURNING *
         "#,
         bank_id,
         bank_name,
         fee,
     )
-    .fetch_one(pool)
+    .fetch_one(&mut transaction)
     .await?;
 
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("bank.create", "bank", bank.id, Some(bank.id))
+            .after(&serde_json::json!({ "bank_name": bank.bank_name, "fee": bank.fee })),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
     Ok(bank.id)
 }
 
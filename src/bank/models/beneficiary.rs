@@ -1,6 +1,11 @@
+use super::audit::{self, AuditContext, Change};
 use super::customer::Customer;
+use super::iban;
+use super::sanctions;
+use crate::bank::helper::validation::{validate_account_number, validate_input, CustomerErrorReps};
 use chrono::NaiveDateTime;
 use serde::{Serialize, Deserialize};
+use sqlx::PgPool;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
@@ -14,3 +19,121 @@ pub struct Beneficiary {
     pub inserted_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
+
+// Registers a payee for the customer once the payee's name has cleared sanctions
+// screening.
+pub async fn add_beneficiary(
+    pool: &PgPool,
+    audit: &AuditContext,
+    customer_id: Uuid,
+    beneficiary_name: String,
+    beneficiary_account_number: String,
+) -> Result<Beneficiary, CustomerErrorReps> {
+    if beneficiary_name.trim().is_empty() || beneficiary_account_number.trim().is_empty() {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Beneficiary name and account number are required.".to_string(),
+        ));
+    }
+
+    let beneficiary_account_number =
+        iban::resolve_account_number(pool, &beneficiary_account_number).await?;
+
+    // Accounts opened before check digits were introduced keep their old numbers
+    let held_with_us = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM accounts WHERE account_number = $1
+        ) AS "exists!"
+        "#,
+        beneficiary_account_number
+    )
+    .fetch_one(pool)
+    .await?;
+
+    // IBANs were already checked while resolving
+    if !held_with_us && !iban::looks_like_iban(&beneficiary_account_number) {
+        validate_input(beneficiary_account_number.as_str(), |number| {
+            validate_account_number(number)
+        })
+        .map_err(|validation_result| {
+            CustomerErrorReps::InvalidInput(validation_result.error_message.unwrap_or_default())
+        })?;
+    }
+
+    let customer = sqlx::query_as!(
+        Customer,
+        r#"
+        SELECT branch_id, bank_id, id, customer_name, email, phone_number, cic_number, inserted_at, updated_at
+        FROM customers
+        WHERE id = $1
+        "#,
+        customer_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    sanctions::ensure_not_listed(
+        pool,
+        customer.bank_id,
+        sanctions::SUBJECT_BENEFICIARY,
+        &beneficiary_account_number,
+        &beneficiary_name,
+    )
+    .await?;
+
+    let mut transaction = pool.begin().await?;
+
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO beneficiaries (id, customer_id, beneficiary_name, beneficiary_account_number, bank_id, branch_id, inserted_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        RETURNING id, inserted_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        customer.id,
+        beneficiary_name,
+        beneficiary_account_number,
+        customer.bank_id,
+        customer.branch_id
+    )
+    .fetch_one(&mut transaction)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::Database(ref db_err)
+            if db_err.constraint() == Some("beneficiaries_beneficiary_account_number_key") =>
+        {
+            CustomerErrorReps::InvalidInput(
+                "Beneficiary account is already registered.".to_string(),
+            )
+        }
+        err => CustomerErrorReps::DatabaseError(err),
+    })?;
+
+    let beneficiary = Beneficiary {
+        branch_id: customer.branch_id,
+        bank_id: customer.bank_id,
+        id: row.id,
+        customer,
+        beneficiary_name,
+        beneficiary_account_number,
+        inserted_at: row.inserted_at,
+        updated_at: row.updated_at,
+    };
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("beneficiary.create", "beneficiary", beneficiary.id, Some(beneficiary.bank_id))
+            .after(&serde_json::json!({
+                "customer_id": beneficiary.customer.id,
+                "beneficiary_name": beneficiary.beneficiary_name,
+                "beneficiary_account_number": beneficiary.beneficiary_account_number,
+            })),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(beneficiary)
+}
@@ -1,9 +1,13 @@
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use uuid::Uuid;
 
-use super::{bank::{get_bank_by_id, update_total_money}, transactions};
+use super::{
+    audit::{self, AuditContext, Change},
+    bank::update_total_money,
+    transactions,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
 pub struct Branch {
@@ -20,10 +24,12 @@ pub struct Branch {
     pub total_accounts: i32,
     pub total_transactions: i32,
     pub total_customers: i32,
+    pub branch_code: i32,
 }
 
 pub async fn create_branch(
     pool: &PgPool,
+    audit: &AuditContext,
     bank_id: Uuid,
     pre_deposit_amount: i32,
 ) -> Result<Branch, sqlx::Error> {
@@ -58,8 +64,8 @@ pub async fn create_branch(
     // Insert the new branch with the calculated total_money
     let branch = sqlx::query!(
         r#"
-        INSERT INTO branches (id, branch_name, bank_id, pre_deposit_amount, total_money, debt_to_collect, loans_given, inserted_at, updated_at, total_cards, total_accounts, total_transactions, total_customers)
-        VALUES ($1, $2, $3, $4, $5, 0, 0, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, 0, 0, 0, 0)
+        INSERT INTO branches (id, branch_name, bank_id, pre_deposit_amount, total_money, debt_to_collect, loans_given, inserted_at, updated_at, total_cards, total_accounts, total_transactions, total_customers, branch_code)
+        VALUES ($1, $2, $3, $4, $5, 0, 0, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, 0, 0, 0, 0, nextval('branch_code_seq'))
         RETURNING *
         "#,
         branch_id,
@@ -74,6 +80,16 @@ pub async fn create_branch(
     // Update the bank's total money
     update_total_money(&mut transaction, bank_id).await?;
 
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("branch.create", "branch", branch_id, Some(bank_id)).after(&serde_json::json!({
+            "branch_name": branch_name,
+            "pre_deposit_amount": pre_deposit_amount,
+        })),
+    )
+    .await?;
+
     transaction.commit().await?; // Commit the transaction
 
     Ok(branch)
@@ -103,7 +119,7 @@ pub async fn get_branches_by_bank_id(
     let branches = sqlx::query!(
         r#"
         SELECT b.id, b.branch_name, b.bank_id, b.pre_deposit_amount, b.total_money, b.debt_to_collect, b.loans_given, b.inserted_at, b.updated_at, COUNT(c.id) AS customers_count,
-        SUM(b.total_cards) AS total_cards, SUM(b.total_accounts) AS total_accounts, SUM(b.total_transactions) AS total_transactions, SUM(b.total_customers) AS total_customers
+        SUM(b.total_cards) AS total_cards, SUM(b.total_accounts) AS total_accounts, SUM(b.total_transactions) AS total_transactions, SUM(b.total_customers) AS total_customers, b.branch_code
         FROM branches AS b
         LEFT JOIN customers AS c ON c.branch_id = b.id
         WHERE b.bank_id = $1
@@ -130,56 +146,43 @@ pub async fn get_branches_by_bank_id(
             total_accounts: info.total_accounts.unwrap_or(0),
             total_transactions: info.total_transactions.unwrap_or(0),
             total_customers: info.total_customers.unwrap_or(0),
+            branch_code: info.branch_code,
         })
         .collect();
 
     Ok(branches)
 }
 
+// Moves cash in or out of a branch vault (negative `amount` for withdrawals) and keeps
+// the bank's total in step. Meant to run inside the caller's transaction.
 pub async fn update_total_money_on_deposit(
-    pool: &PgPool,
+    conn: &mut PgConnection,
     branch_id: Uuid,
-    new_deposit: i32,
+    amount: i32,
 ) -> Result<(), sqlx::Error> {
-    let branch = get_branch_by_id(pool, branch_id).await?; // Retrieve the branch details
-    let initial_deposit = branch.pre_deposit_amount; // Get the initial deposit from the branch
-
-    // Calculate the gap between the new deposit and initial deposit
-    let gap = new_deposit - initial_deposit;
-
-    // Calculate the new total money for the branch
-    let new_total_money = branch.total_money + gap;
-
-    // Update the total money of the branch
-    sqlx::query!(
+    let bank_id: Uuid = sqlx::query_scalar!(
         r#"
         UPDATE branches
-        SET total_money = $1
+        SET total_money = total_money + $1, total_transactions = total_transactions + 1, updated_at = CURRENT_TIMESTAMP
         WHERE id = $2
+        RETURNING bank_id
         "#,
-        new_total_money,
+        amount,
         branch_id
     )
-    .execute(pool)
+    .fetch_one(&mut *conn)
     .await?;
 
-    let bank_id = branch.bank_id;
-    let bank = get_bank_by_id(pool, bank_id).await?; // Retrieve the bank details
-
-    // Calculate the new total money for the bank
-    let new_bank_total_money = bank.total_money - gap;
-
-    // Update the total money of the bank
     sqlx::query!(
         r#"
         UPDATE banks
-        SET total_money = $1
+        SET total_money = total_money + $1, total_transactions = total_transactions + 1, updated_at = CURRENT_TIMESTAMP
         WHERE id = $2
         "#,
-        new_bank_total_money,
+        amount,
         bank_id
     )
-    .execute(pool)
+    .execute(&mut *conn)
     .await?;
 
     Ok(())
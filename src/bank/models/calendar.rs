@@ -0,0 +1,344 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::Path,
+};
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgExecutor, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::bank::helper::validation::CustomerErrorReps;
+
+use super::audit::{self, AuditContext, Change};
+
+// Used by interbank clearing, which follows no single bank's calendar. Banks start out
+// with the same defaults.
+pub const DEFAULT_WEEKEND_DAYS: [Weekday; 2] = [Weekday::Sat, Weekday::Sun];
+pub const DEFAULT_CUT_OFF_HOUR: u32 = 16;
+
+// Weekends, holidays and the same-day cut-off of one bank
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BusinessCalendar {
+    pub weekend_days: Vec<Weekday>,
+    pub cut_off_time: NaiveTime,
+    pub holidays: BTreeSet<NaiveDate>,
+}
+
+impl BusinessCalendar {
+    pub fn is_business_day(&self, date: NaiveDate) -> bool {
+        !self.weekend_days.contains(&date.weekday()) && !self.holidays.contains(&date)
+    }
+
+    // The first business day strictly after `date`
+    pub fn next_business_day(&self, date: NaiveDate) -> NaiveDate {
+        self.business_day_on_or_after(date + Duration::days(1))
+    }
+
+    // The last business day strictly before `date`
+    pub fn previous_business_day(&self, date: NaiveDate) -> NaiveDate {
+        let mut day = date - Duration::days(1);
+        while !self.is_business_day(day) {
+            day -= Duration::days(1);
+        }
+        day
+    }
+
+    // `date` itself when it is a business day, the next one otherwise
+    pub fn business_day_on_or_after(&self, date: NaiveDate) -> NaiveDate {
+        let mut day = date;
+        while !self.is_business_day(day) {
+            day += Duration::days(1);
+        }
+        day
+    }
+
+    pub fn add_business_days(&self, date: NaiveDate, days: u32) -> NaiveDate {
+        (0..days).fold(date, |day, _| self.next_business_day(day))
+    }
+
+    // The date from which a posting made at `posted_at` counts: the same day before the
+    // cut-off on a business day, the next business day otherwise
+    pub fn value_date(&self, posted_at: NaiveDateTime) -> NaiveDate {
+        let date = posted_at.date();
+        if self.is_business_day(date) && posted_at.time() < self.cut_off_time {
+            date
+        } else {
+            self.next_business_day(date)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct Holiday {
+    pub id: Uuid,
+    pub bank_id: Option<Uuid>,
+    pub holiday_date: NaiveDate,
+    pub name: String,
+    pub inserted_at: NaiveDateTime,
+}
+
+// A bank's calendar settings, upcoming holidays and how it treats a posting made now
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CalendarOverview {
+    pub weekend_days: Vec<Weekday>,
+    pub cut_off_time: NaiveTime,
+    pub today: NaiveDate,
+    pub is_business_day: bool,
+    pub next_business_day: NaiveDate,
+    pub value_date: NaiveDate,
+    pub holidays: Vec<Holiday>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CalendarSettings {
+    pub weekend_days: Vec<Weekday>,
+    pub cut_off_time: NaiveTime,
+}
+
+// Holidays file rows. Rows with an empty bank code apply to every bank.
+#[derive(Debug, Deserialize)]
+struct HolidayRecord {
+    bank_code: Option<i32>,
+    date: NaiveDate,
+    name: String,
+}
+
+fn weekday_from_iso(day: i32) -> Option<Weekday> {
+    match day {
+        1 => Some(Weekday::Mon),
+        2 => Some(Weekday::Tue),
+        3 => Some(Weekday::Wed),
+        4 => Some(Weekday::Thu),
+        5 => Some(Weekday::Fri),
+        6 => Some(Weekday::Sat),
+        7 => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+pub async fn load(
+    executor: impl PgExecutor<'_>,
+    bank_id: Uuid,
+) -> Result<BusinessCalendar, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT k.weekend_days, k.cut_off_time,
+            ARRAY(SELECT h.holiday_date FROM holidays AS h WHERE h.bank_id = k.id OR h.bank_id IS NULL) AS "holidays!"
+        FROM banks AS k
+        WHERE k.id = $1
+        "#,
+        bank_id
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(BusinessCalendar {
+        weekend_days: row
+            .weekend_days
+            .into_iter()
+            .filter_map(weekday_from_iso)
+            .collect(),
+        cut_off_time: row.cut_off_time,
+        holidays: row.holidays.into_iter().collect(),
+    })
+}
+
+// The calendar of interbank clearing: default weekends and holidays common to all banks
+pub async fn load_system(executor: impl PgExecutor<'_>) -> Result<BusinessCalendar, sqlx::Error> {
+    let holidays = sqlx::query_scalar!(
+        r#"
+        SELECT holiday_date FROM holidays WHERE bank_id IS NULL
+        "#
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(BusinessCalendar {
+        weekend_days: DEFAULT_WEEKEND_DAYS.to_vec(),
+        cut_off_time: NaiveTime::from_hms_opt(DEFAULT_CUT_OFF_HOUR, 0, 0)
+            .expect("default cut-off is a valid time"),
+        holidays: holidays.into_iter().collect(),
+    })
+}
+
+// Calendars of the banks a batch job posts to, each loaded the first time it is needed
+#[derive(Debug, Default)]
+pub struct Calendars(HashMap<Uuid, BusinessCalendar>);
+
+impl Calendars {
+    pub async fn get(
+        &mut self,
+        conn: &mut PgConnection,
+        bank_id: Uuid,
+    ) -> Result<&BusinessCalendar, sqlx::Error> {
+        if !self.0.contains_key(&bank_id) {
+            let calendar = load(conn, bank_id).await?;
+            self.0.insert(bank_id, calendar);
+        }
+        Ok(&self.0[&bank_id])
+    }
+}
+
+pub async fn update_settings(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    settings: CalendarSettings,
+) -> Result<CalendarSettings, CustomerErrorReps> {
+    let weekend_days: BTreeSet<i32> = settings
+        .weekend_days
+        .iter()
+        .map(|day| day.number_from_monday() as i32)
+        .collect();
+    if weekend_days.len() >= 7 {
+        return Err(CustomerErrorReps::InvalidInput(
+            "At least one day of the week must be a business day.".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    let before = sqlx::query!(
+        r#"
+        SELECT weekend_days, cut_off_time FROM banks WHERE id = $1 FOR UPDATE
+        "#,
+        bank_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    sqlx::query!(
+        r#"
+        UPDATE banks
+        SET weekend_days = $1, cut_off_time = $2, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $3
+        "#,
+        &weekend_days.into_iter().collect::<Vec<i32>>(),
+        settings.cut_off_time,
+        bank_id
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    let updated = load(&mut transaction, bank_id).await?;
+    let updated = CalendarSettings {
+        weekend_days: updated.weekend_days,
+        cut_off_time: updated.cut_off_time,
+    };
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("calendar.update", "bank", bank_id, Some(bank_id))
+            .before(&serde_json::json!({
+                "weekend_days": before.weekend_days,
+                "cut_off_time": before.cut_off_time,
+            }))
+            .after(&updated),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(updated)
+}
+
+// Replaces all holidays with those in the CSV file at `path` (bank_code,date,name)
+pub async fn load_holidays_file(pool: &PgPool, path: &Path) -> Result<usize, CustomerErrorReps> {
+    let content = std::fs::read(path).map_err(|err| {
+        CustomerErrorReps::InvalidInput(format!("Cannot read holidays {}: {}", path.display(), err))
+    })?;
+    let records = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(content.as_slice())
+        .deserialize::<HolidayRecord>()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| {
+            CustomerErrorReps::InvalidInput(format!("Invalid holidays {}: {}", path.display(), err))
+        })?;
+
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!("DELETE FROM holidays")
+        .execute(&mut transaction)
+        .await?;
+
+    for record in &records {
+        let bank_id = match record.bank_code {
+            Some(bank_code) => Some(
+                sqlx::query_scalar!("SELECT id FROM banks WHERE bank_code = $1", bank_code)
+                    .fetch_optional(&mut transaction)
+                    .await?
+                    .ok_or_else(|| {
+                        CustomerErrorReps::InvalidInput(format!("Unknown bank code {}.", bank_code))
+                    })?,
+            ),
+            None => None,
+        };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO holidays (id, bank_id, holiday_date, name, inserted_at)
+            VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP)
+            "#,
+            Uuid::new_v4(),
+            bank_id,
+            record.date,
+            record.name
+        )
+        .execute(&mut transaction)
+        .await?;
+    }
+
+    transaction.commit().await?;
+
+    Ok(records.len())
+}
+
+// The bank's own holidays and the common ones, from `from` onwards
+pub async fn get_holidays(
+    pool: &PgPool,
+    bank_id: Uuid,
+    from: NaiveDate,
+) -> Result<Vec<Holiday>, sqlx::Error> {
+    let holidays = sqlx::query_as!(
+        Holiday,
+        r#"
+        SELECT id, bank_id, holiday_date, name, inserted_at
+        FROM holidays
+        WHERE (bank_id = $1 OR bank_id IS NULL) AND holiday_date >= $2
+        ORDER BY holiday_date
+        "#,
+        bank_id,
+        from
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(holidays)
+}
+
+pub async fn get_overview(
+    pool: &PgPool,
+    bank_id: Uuid,
+    now: NaiveDateTime,
+) -> Result<Option<CalendarOverview>, sqlx::Error> {
+    let calendar = match load(pool, bank_id).await {
+        Ok(calendar) => calendar,
+        Err(sqlx::Error::RowNotFound) => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    let today = now.date();
+
+    Ok(Some(CalendarOverview {
+        today,
+        is_business_day: calendar.is_business_day(today),
+        next_business_day: calendar.next_business_day(today),
+        value_date: calendar.value_date(now),
+        holidays: get_holidays(pool, bank_id, today).await?,
+        weekend_days: calendar.weekend_days,
+        cut_off_time: calendar.cut_off_time,
+    }))
+}
@@ -1,7 +1,10 @@
+use super::audit::{self, AuditContext, Change};
+use super::outbox::{self, DomainEvent};
 use super::types::{CardStatus, CardType};
+use crate::bank::helper::validation::CustomerErrorReps;
 use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{postgres::PgExecutor, PgPool};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
@@ -23,6 +26,7 @@ pub struct Card {
 
 pub async fn insert_card(
     pool: &PgPool,
+    audit: &AuditContext,
     bank_id: Uuid,
     branch_id: Uuid,
     card_number: String,
@@ -35,6 +39,7 @@ pub async fn insert_card(
     card_type: CardType,
 ) -> Result<Uuid, sqlx::Error> {
     let id = Uuid::new_v4();
+    let mut transaction = pool.begin().await?;
 
     let card_id = sqlx::query!(
         r#"
@@ -54,15 +59,31 @@ pub async fn insert_card(
         bank_id,
         branch_id,
     )
-    .fetch_one(pool)
+    .fetch_one(&mut transaction)
     .await?
     .id;
 
+    // The CVV and full card number stay out of the audit trail
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("card.issue", "card", card_id, Some(bank_id)).after(&serde_json::json!({
+            "card_number_last4": &card_number[card_number.len().saturating_sub(4)..],
+            "account_number": account_number,
+            "expiration_date": expiration_date,
+            "card_status": card_status,
+            "card_type": card_type,
+        })),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
     Ok(card_id)
 }
 
 pub async fn get_by_card_number(
-    pool: &PgPool,
+    executor: impl PgExecutor<'_>,
     card_number: &str,
 ) -> Result<Option<Card>, sqlx::Error> {
     let card = sqlx::query_as!(
@@ -75,8 +96,136 @@ pub async fn get_by_card_number(
         "#,
         card_number
     )
-    .fetch_optional(pool)
+    .fetch_optional(executor)
     .await?;
 
     Ok(card)
 }
+
+pub async fn get_by_id(
+    executor: impl PgExecutor<'_>,
+    card_id: Uuid,
+) -> Result<Option<Card>, sqlx::Error> {
+    let card = sqlx::query_as!(
+        Card,
+        r#"
+        SELECT id, card_number, account_number, expiration_date, cvv, issued_date, inserted_at, updated_at, balance, card_status as "card_status: CardStatus", card_type as "card_type: CardType", bank_id, branch_id
+        FROM cards
+        WHERE id = $1
+        "#,
+        card_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(card)
+}
+
+// Who asks for a card status change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusChangeBy {
+    // May only freeze an active card and unfreeze a card they froze themselves
+    Customer,
+    // Any change, on cards issued by the branch
+    Staff { branch_id: Uuid },
+}
+
+// Blocks, unblocks or closes a card. Closing is final.
+pub async fn update_card_status(
+    pool: &PgPool,
+    audit: &AuditContext,
+    card_number: &str,
+    card_status: CardStatus,
+    changed_by: StatusChangeBy,
+) -> Result<Card, CustomerErrorReps> {
+    let mut transaction = pool.begin().await?;
+
+    let card = sqlx::query_as!(
+        Card,
+        r#"
+        SELECT id, card_number, account_number, expiration_date, cvv, issued_date, inserted_at, updated_at, balance, card_status as "card_status: CardStatus", card_type as "card_type: CardType", bank_id, branch_id
+        FROM cards
+        WHERE card_number = $1
+        FOR UPDATE
+        "#,
+        card_number
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    if let StatusChangeBy::Staff { branch_id } = changed_by {
+        if card.branch_id != branch_id {
+            return Err(CustomerErrorReps::NotFound);
+        }
+    }
+
+    if card.card_status == CardStatus::Closed {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Card is closed.".to_string(),
+        ));
+    }
+    if card.card_status == card_status {
+        return Ok(card);
+    }
+
+    let frozen_by_customer = sqlx::query_scalar!(
+        r#"
+        SELECT frozen_by_customer FROM cards WHERE id = $1
+        "#,
+        card.id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    if changed_by == StatusChangeBy::Customer {
+        let permitted = match (&card.card_status, &card_status) {
+            (CardStatus::Active, CardStatus::Inactive) => true,
+            (CardStatus::Inactive, CardStatus::Active) => frozen_by_customer,
+            _ => false,
+        };
+        if !permitted {
+            return Err(CustomerErrorReps::Forbidden);
+        }
+    }
+
+    let updated = sqlx::query_as!(
+        Card,
+        r#"
+        UPDATE cards
+        SET card_status = $1, frozen_by_customer = $2, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $3
+        RETURNING id, card_number, account_number, expiration_date, cvv, issued_date, inserted_at, updated_at, balance, card_status as "card_status: CardStatus", card_type as "card_type: CardType", bank_id, branch_id
+        "#,
+        card_status.clone() as CardStatus,
+        changed_by == StatusChangeBy::Customer && card_status == CardStatus::Inactive,
+        card.id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("card.update_status", "card", card.id, Some(card.bank_id))
+            .before(&serde_json::json!({ "card_status": card.card_status }))
+            .after(&serde_json::json!({ "card_status": updated.card_status })),
+    )
+    .await?;
+
+    outbox::publish(
+        &mut transaction,
+        Some(card.bank_id),
+        &DomainEvent::CardStatusChanged {
+            card_id: card.id,
+            account_number: card.account_number,
+            previous_status: card.card_status,
+            card_status: updated.card_status.clone(),
+        },
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(updated)
+}
@@ -0,0 +1,511 @@
+use chrono::{NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::bank::helper::validation::CustomerErrorReps;
+
+use super::{
+    accounts,
+    audit::{self, AuditContext, Change},
+    calendar::{self, BusinessCalendar, Calendars},
+    outbox::{self, DomainEvent},
+    payment_batches::{ReasonCode, Rejection},
+    transactions,
+    transfer::Transfer,
+    types::{AccountStatus, ClearingItemStatus, EntryDirection, Status, TransactionType},
+};
+
+// Cut-off times (UTC) at which queued interbank transfers are netted and settled on
+// clearing business days
+pub const DEFAULT_CUT_OFF_TIMES: &str = "10:00,14:00,16:30";
+
+// A transfer to an account held at another bank, waiting for or done with settlement
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct ClearingItem {
+    pub id: Uuid,
+    pub transfer_id: Uuid,
+    pub debtor_bank_id: Uuid,
+    pub debtor_branch_id: Uuid,
+    pub debtor_account_number: String,
+    pub creditor_bank_id: Uuid,
+    pub creditor_account_number: String,
+    pub amount: i32,
+    pub status: ClearingItemStatus,
+    pub cycle_id: Option<Uuid>,
+    pub return_reason_code: Option<String>,
+    pub return_reason: Option<String>,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct ClearingCycle {
+    pub id: Uuid,
+    pub cut_off_at: NaiveDateTime,
+    pub settled_count: i32,
+    pub returned_count: i32,
+    pub gross_amount: i64,
+    pub inserted_at: NaiveDateTime,
+}
+
+// A bank's net position in one cycle. Positive when it received more than it sent.
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct ClearingPosition {
+    pub cycle_id: Uuid,
+    pub bank_id: Uuid,
+    pub sent_amount: i64,
+    pub received_amount: i64,
+    pub net_position: i64,
+    pub cut_off_at: NaiveDateTime,
+}
+
+pub fn cut_off_times() -> Vec<NaiveTime> {
+    std::env::var("CLEARING_CUT_OFF_TIMES")
+        .ok()
+        .map(|value| parse_cut_off_times(&value))
+        .filter(|times| !times.is_empty())
+        .unwrap_or_else(|| parse_cut_off_times(DEFAULT_CUT_OFF_TIMES))
+}
+
+// "10:00,14:00" -> sorted times. Malformed entries are skipped.
+pub fn parse_cut_off_times(value: &str) -> Vec<NaiveTime> {
+    let mut times: Vec<NaiveTime> = value
+        .split(',')
+        .filter_map(|time| NaiveTime::parse_from_str(time.trim(), "%H:%M").ok())
+        .collect();
+    times.sort();
+    times.dedup();
+    times
+}
+
+// The most recent cut-off at or before `now` on a clearing business day, looking back to
+// the last cut-off of the previous business day when none has passed yet today
+pub fn latest_cut_off(
+    cut_off_times: &[NaiveTime],
+    calendar: &BusinessCalendar,
+    now: NaiveDateTime,
+) -> Option<NaiveDateTime> {
+    let today = now.date();
+    let passed_today = cut_off_times
+        .iter()
+        .rev()
+        .map(|time| today.and_time(*time))
+        .find(|cut_off| *cut_off <= now)
+        .filter(|_| calendar.is_business_day(today));
+
+    match passed_today {
+        Some(cut_off) => Some(cut_off),
+        None => {
+            let last = cut_off_times.last()?;
+            Some(calendar.previous_business_day(today).and_time(*last))
+        }
+    }
+}
+
+// Queues a transfer whose beneficiary is held at another bank. The debtor has already
+// been debited; the creditor is credited when the next cycle settles.
+pub async fn enqueue(
+    conn: &mut PgConnection,
+    transfer: &Transfer,
+    creditor_bank_id: Uuid,
+) -> Result<ClearingItem, CustomerErrorReps> {
+    // Stamped in UTC, the same clock the cut-offs are on
+    let now = chrono::Utc::now().naive_utc();
+
+    let item = sqlx::query_as!(
+        ClearingItem,
+        r#"
+        INSERT INTO clearing_items (id, transfer_id, debtor_bank_id, debtor_branch_id, debtor_account_number, creditor_bank_id, creditor_account_number, amount, status, inserted_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 'queued', $9, $9)
+        RETURNING id, transfer_id, debtor_bank_id, debtor_branch_id, debtor_account_number, creditor_bank_id, creditor_account_number, amount, status as "status: _", cycle_id, return_reason_code, return_reason, inserted_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        transfer.id,
+        transfer.bank_id,
+        transfer.branch_id,
+        transfer.sender_account_number,
+        creditor_bank_id,
+        transfer.beneficiary_account_number,
+        transfer.amount,
+        now
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(item)
+}
+
+// Credits the creditor of a queued item, or says why the receiving bank refuses it
+async fn credit_creditor(
+    conn: &mut PgConnection,
+    calendars: &mut Calendars,
+    item: &ClearingItem,
+) -> Result<Option<Rejection>, CustomerErrorReps> {
+    let creditor = sqlx::query!(
+        r#"
+        SELECT id, branch_id, status as "status: AccountStatus"
+        FROM accounts
+        WHERE account_number = $1 AND bank_id = $2
+        FOR UPDATE
+        "#,
+        item.creditor_account_number,
+        item.creditor_bank_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let creditor = match creditor {
+        Some(creditor) => creditor,
+        None => {
+            return Ok(Some((
+                ReasonCode::AC01,
+                "Creditor account not found.".to_string(),
+            )))
+        }
+    };
+    if let Err(err) = accounts::ensure_can_credit(creditor.status) {
+        return Ok(Some((ReasonCode::AC04, err.to_string())));
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE accounts
+        SET balance = balance + $1, last_updated_date = CURRENT_DATE, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $2
+        "#,
+        item.amount,
+        creditor.id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let calendar = calendars.get(&mut *conn, item.creditor_bank_id).await?;
+    transactions::insert_transaction(
+        &mut *conn,
+        calendar,
+        creditor.branch_id,
+        item.creditor_bank_id,
+        &item.creditor_account_number,
+        None,
+        TransactionType::P2P,
+        EntryDirection::Credit,
+        item.amount,
+        Status::Approved,
+    )
+    .await?;
+
+    Ok(None)
+}
+
+// Credits the amount back to the debtor account, whatever its status, since the money
+// has nowhere else to go
+async fn return_item(
+    conn: &mut PgConnection,
+    audit: &AuditContext,
+    calendars: &mut Calendars,
+    item: &ClearingItem,
+    cycle_id: Option<Uuid>,
+    (reason_code, reason): Rejection,
+) -> Result<ClearingItem, CustomerErrorReps> {
+    sqlx::query!(
+        r#"
+        UPDATE accounts
+        SET balance = balance + $1, last_updated_date = CURRENT_DATE, updated_at = CURRENT_TIMESTAMP
+        WHERE account_number = $2
+        "#,
+        item.amount,
+        item.debtor_account_number
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let calendar = calendars.get(&mut *conn, item.debtor_bank_id).await?;
+    transactions::insert_transaction(
+        &mut *conn,
+        calendar,
+        item.debtor_branch_id,
+        item.debtor_bank_id,
+        &item.debtor_account_number,
+        None,
+        TransactionType::ClearingReturn,
+        EntryDirection::Credit,
+        item.amount,
+        Status::Approved,
+    )
+    .await?;
+
+    let returned = sqlx::query_as!(
+        ClearingItem,
+        r#"
+        UPDATE clearing_items
+        SET status = 'returned', cycle_id = $2, return_reason_code = $3, return_reason = $4, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1
+        RETURNING id, transfer_id, debtor_bank_id, debtor_branch_id, debtor_account_number, creditor_bank_id, creditor_account_number, amount, status as "status: _", cycle_id, return_reason_code, return_reason, inserted_at, updated_at
+        "#,
+        item.id,
+        cycle_id,
+        reason_code.as_str(),
+        reason
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    audit::record(
+        &mut *conn,
+        audit,
+        Change::new(
+            "clearing_item.return",
+            "clearing_item",
+            item.id,
+            Some(item.creditor_bank_id),
+        )
+        .before(item)
+        .after(&returned),
+    )
+    .await?;
+
+    outbox::publish(
+        &mut *conn,
+        Some(item.debtor_bank_id),
+        &DomainEvent::ClearingItemReturned(returned.clone()),
+    )
+    .await?;
+
+    Ok(returned)
+}
+
+// Lets the receiving bank refuse an item before its cycle settles
+pub async fn return_queued_item(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    item_id: Uuid,
+    rejection: Rejection,
+) -> Result<ClearingItem, CustomerErrorReps> {
+    let mut transaction = pool.begin().await?;
+
+    let item = sqlx::query_as!(
+        ClearingItem,
+        r#"
+        SELECT id, transfer_id, debtor_bank_id, debtor_branch_id, debtor_account_number, creditor_bank_id, creditor_account_number, amount, status as "status: _", cycle_id, return_reason_code, return_reason, inserted_at, updated_at
+        FROM clearing_items
+        WHERE id = $1 AND creditor_bank_id = $2
+        FOR UPDATE
+        "#,
+        item_id,
+        bank_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    if item.status != ClearingItemStatus::Queued {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Only queued items can be returned.".to_string(),
+        ));
+    }
+
+    let mut calendars = Calendars::default();
+    let returned = return_item(
+        &mut transaction,
+        audit,
+        &mut calendars,
+        &item,
+        None,
+        rejection,
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(returned)
+}
+
+// Settles every item queued up to `cut_off_at`: creditors are credited, refused items
+// are returned to their debtors, and each bank's vault moves by its net position.
+// Returns `None` when the cycle for this cut-off has already run.
+pub async fn run_cycle(
+    pool: &PgPool,
+    cut_off_at: NaiveDateTime,
+) -> Result<Option<ClearingCycle>, CustomerErrorReps> {
+    let audit = AuditContext::system("clearing");
+    let mut transaction = pool.begin().await?;
+
+    // The unique cut-off keeps concurrent schedulers from settling the same cycle twice
+    let cycle_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO clearing_cycles (id, cut_off_at, settled_count, returned_count, gross_amount, inserted_at)
+        VALUES ($1, $2, 0, 0, 0, CURRENT_TIMESTAMP)
+        ON CONFLICT (cut_off_at) DO NOTHING
+        RETURNING id
+        "#,
+        Uuid::new_v4(),
+        cut_off_at
+    )
+    .fetch_optional(&mut transaction)
+    .await?;
+
+    let cycle_id = match cycle_id {
+        Some(cycle_id) => cycle_id,
+        None => return Ok(None),
+    };
+
+    let items = sqlx::query_as!(
+        ClearingItem,
+        r#"
+        SELECT id, transfer_id, debtor_bank_id, debtor_branch_id, debtor_account_number, creditor_bank_id, creditor_account_number, amount, status as "status: _", cycle_id, return_reason_code, return_reason, inserted_at, updated_at
+        FROM clearing_items
+        WHERE status = 'queued' AND inserted_at <= $1
+        ORDER BY inserted_at
+        FOR UPDATE
+        "#,
+        cut_off_at
+    )
+    .fetch_all(&mut transaction)
+    .await?;
+
+    // Items of many banks settle together; each bank's calendar is loaded once
+    let mut calendars = Calendars::default();
+    for item in &items {
+        match credit_creditor(&mut transaction, &mut calendars, item).await? {
+            None => {
+                sqlx::query!(
+                    r#"
+                    UPDATE clearing_items
+                    SET status = 'settled', cycle_id = $2, updated_at = CURRENT_TIMESTAMP
+                    WHERE id = $1
+                    "#,
+                    item.id,
+                    cycle_id
+                )
+                .execute(&mut transaction)
+                .await?;
+            }
+            Some(rejection) => {
+                return_item(
+                    &mut transaction,
+                    &audit,
+                    &mut calendars,
+                    item,
+                    Some(cycle_id),
+                    rejection,
+                )
+                .await?;
+            }
+        }
+    }
+
+    // Only net amounts move between banks
+    sqlx::query!(
+        r#"
+        INSERT INTO clearing_positions (cycle_id, bank_id, sent_amount, received_amount, net_position)
+        SELECT $1, bank_id, SUM(sent), SUM(received), SUM(received) - SUM(sent)
+        FROM (
+            SELECT debtor_bank_id AS bank_id, amount::BIGINT AS sent, 0::BIGINT AS received
+            FROM clearing_items WHERE cycle_id = $1 AND status = 'settled'
+            UNION ALL
+            SELECT creditor_bank_id AS bank_id, 0::BIGINT AS sent, amount::BIGINT AS received
+            FROM clearing_items WHERE cycle_id = $1 AND status = 'settled'
+        ) AS legs
+        GROUP BY bank_id
+        "#,
+        cycle_id
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE banks AS b
+        SET total_money = b.total_money + p.net_position::INTEGER, updated_at = CURRENT_TIMESTAMP
+        FROM clearing_positions AS p
+        WHERE p.cycle_id = $1 AND p.bank_id = b.id
+        "#,
+        cycle_id
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    let cycle = sqlx::query_as!(
+        ClearingCycle,
+        r#"
+        UPDATE clearing_cycles
+        SET settled_count = (SELECT COUNT(*) FROM clearing_items WHERE cycle_id = $1 AND status = 'settled')::INTEGER,
+            returned_count = (SELECT COUNT(*) FROM clearing_items WHERE cycle_id = $1 AND status = 'returned')::INTEGER,
+            gross_amount = (SELECT COALESCE(SUM(amount), 0) FROM clearing_items WHERE cycle_id = $1 AND status = 'settled')::BIGINT
+        WHERE id = $1
+        RETURNING id, cut_off_at, settled_count as "settled_count!", returned_count as "returned_count!", gross_amount as "gross_amount!", inserted_at
+        "#,
+        cycle_id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        &audit,
+        Change::new("clearing.settle", "clearing_cycle", cycle.id, None).after(&cycle),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(Some(cycle))
+}
+
+// Runs the cycle of the latest cut-off that has passed, if it has not run yet. Items
+// queued before any missed cut-offs are picked up by it as well.
+pub async fn run_due_cycle(
+    pool: &PgPool,
+    now: NaiveDateTime,
+) -> Result<Option<ClearingCycle>, CustomerErrorReps> {
+    let calendar = calendar::load_system(pool).await?;
+    match latest_cut_off(&cut_off_times(), &calendar, now) {
+        Some(cut_off_at) => run_cycle(pool, cut_off_at).await,
+        None => Ok(None),
+    }
+}
+
+pub async fn get_items(
+    pool: &PgPool,
+    bank_id: Uuid,
+    status: Option<ClearingItemStatus>,
+) -> Result<Vec<ClearingItem>, sqlx::Error> {
+    let items = sqlx::query_as!(
+        ClearingItem,
+        r#"
+        SELECT id, transfer_id, debtor_bank_id, debtor_branch_id, debtor_account_number, creditor_bank_id, creditor_account_number, amount, status as "status: _", cycle_id, return_reason_code, return_reason, inserted_at, updated_at
+        FROM clearing_items
+        WHERE (debtor_bank_id = $1 OR creditor_bank_id = $1) AND ($2::clearingitemstatus IS NULL OR status = $2)
+        ORDER BY inserted_at DESC
+        "#,
+        bank_id,
+        status as Option<ClearingItemStatus>
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(items)
+}
+
+pub async fn get_positions(
+    pool: &PgPool,
+    bank_id: Uuid,
+) -> Result<Vec<ClearingPosition>, sqlx::Error> {
+    let positions = sqlx::query_as!(
+        ClearingPosition,
+        r#"
+        SELECT p.cycle_id, p.bank_id, p.sent_amount, p.received_amount, p.net_position, c.cut_off_at
+        FROM clearing_positions AS p
+        INNER JOIN clearing_cycles AS c ON c.id = p.cycle_id
+        WHERE p.bank_id = $1
+        ORDER BY c.cut_off_at DESC
+        "#,
+        bank_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(positions)
+}
@@ -0,0 +1,750 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::bank::helper::validation::CustomerErrorReps;
+
+use super::{
+    accounts,
+    audit::{self, AuditContext, Change},
+    calendar::{self, Calendars},
+    cards,
+    fraud::{self, ScreenedOperation, ScreeningContext},
+    overdraft,
+    transactions::{self, Transaction},
+    types::{
+        AccountStatus, AccountType, CardStatus, CardType, EntryDirection, StatementStatus,
+        Status, TransactionType,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct CreditAccount {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub card_number: String,
+    pub credit_limit: i32,
+    pub current_balance: i32,
+    pub statement_day: i32,
+    pub grace_period_days: i32,
+    pub apr_bps: i32,
+    pub min_payment_bps: i32,
+    pub min_payment_floor: i32,
+    pub late_fee: i32,
+    pub bank_id: Uuid,
+    pub branch_id: Uuid,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl CreditAccount {
+    pub fn available_credit(&self) -> i32 {
+        (self.credit_limit - self.current_balance).max(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct CreditStatement {
+    pub id: Uuid,
+    pub credit_account_id: Uuid,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub statement_balance: i32,
+    pub minimum_payment_due: i32,
+    pub due_date: NaiveDate,
+    pub amount_paid: i32,
+    pub interest_charged: i32,
+    pub late_fee_charged: i32,
+    pub status: StatementStatus,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreditTerms {
+    pub credit_limit: i32,
+    pub statement_day: i32,
+    pub grace_period_days: i32,
+    pub apr_bps: i32,
+    pub min_payment_bps: i32,
+    pub min_payment_floor: i32,
+    pub late_fee: i32,
+}
+
+// Minimum payment is a percentage of the statement balance, never below the floor
+// and never above what is actually owed.
+pub fn minimum_payment_due(statement_balance: i32, min_payment_bps: i32, floor: i32) -> i32 {
+    if statement_balance <= 0 {
+        return 0;
+    }
+    let percentage = (statement_balance as i64 * min_payment_bps as i64 / 10_000) as i32;
+    percentage.max(floor).min(statement_balance)
+}
+
+// One month of interest on a carried balance at the given APR.
+pub fn monthly_interest(carried_balance: i32, apr_bps: i32) -> i32 {
+    if carried_balance <= 0 {
+        return 0;
+    }
+    (carried_balance as i64 * apr_bps as i64 / 10_000 / 12) as i32
+}
+
+pub async fn open_credit_account(
+    pool: &PgPool,
+    audit: &AuditContext,
+    branch_id: Uuid,
+    account_id: Uuid,
+    card_number: String,
+    terms: CreditTerms,
+) -> Result<CreditAccount, CustomerErrorReps> {
+    if terms.credit_limit <= 0 {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Credit limit must be greater than 0.".to_string(),
+        ));
+    }
+    if !(1..=28).contains(&terms.statement_day) {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Statement day must be between 1 and 28.".to_string(),
+        ));
+    }
+    if terms.grace_period_days < 0 || terms.apr_bps < 0 || terms.min_payment_bps < 0 {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Credit terms cannot be negative.".to_string(),
+        ));
+    }
+
+    let account = accounts::get_by_id(pool, account_id)
+        .await?
+        .filter(|account| account.branch_id == branch_id)
+        .ok_or(CustomerErrorReps::NotFound)?;
+
+    if account.account_type != AccountType::Credits {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Only credit accounts can have a credit line.".to_string(),
+        ));
+    }
+
+    let card = cards::get_by_card_number(pool, &card_number)
+        .await?
+        .ok_or(CustomerErrorReps::NotFound)?;
+
+    if card.card_type != CardType::Credit || card.account_number != account.account_number {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Card must be a credit card issued on this account.".to_string(),
+        ));
+    }
+
+    let id = Uuid::new_v4();
+    let mut transaction = pool.begin().await?;
+
+    let credit_account = sqlx::query_as!(
+        CreditAccount,
+        r#"
+        INSERT INTO credit_accounts (id, account_id, card_number, credit_limit, current_balance, statement_day, grace_period_days, apr_bps, min_payment_bps, min_payment_floor, late_fee, bank_id, branch_id, inserted_at, updated_at)
+        VALUES ($1, $2, $3, $4, 0, $5, $6, $7, $8, $9, $10, $11, $12, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        RETURNING *
+        "#,
+        id,
+        account.id,
+        card.card_number,
+        terms.credit_limit,
+        terms.statement_day,
+        terms.grace_period_days,
+        terms.apr_bps,
+        terms.min_payment_bps,
+        terms.min_payment_floor,
+        terms.late_fee,
+        account.bank_id,
+        account.branch_id,
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("credit_account.open", "credit_account", credit_account.id, Some(credit_account.bank_id))
+            .after(&credit_account),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(credit_account)
+}
+
+pub async fn get_by_account_id(
+    pool: &PgPool,
+    account_id: Uuid,
+) -> Result<Option<CreditAccount>, sqlx::Error> {
+    let credit_account = sqlx::query_as!(
+        CreditAccount,
+        r#"
+        SELECT * FROM credit_accounts WHERE account_id = $1
+        "#,
+        account_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(credit_account)
+}
+
+pub async fn get_statements(
+    pool: &PgPool,
+    credit_account_id: Uuid,
+) -> Result<Vec<CreditStatement>, sqlx::Error> {
+    let statements = sqlx::query_as!(
+        CreditStatement,
+        r#"
+        SELECT id, credit_account_id, period_start, period_end, statement_balance, minimum_payment_due, due_date, amount_paid, interest_charged, late_fee_charged, status as "status: _", inserted_at, updated_at
+        FROM credit_statements
+        WHERE credit_account_id = $1
+        ORDER BY period_end DESC
+        "#,
+        credit_account_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(statements)
+}
+
+// Screens and posts a charge submitted by a merchant of `bank_id`; cards of other banks
+// are not found
+pub async fn charge(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    card_number: &str,
+    amount: i32,
+    context: ScreeningContext,
+) -> Result<Transaction, CustomerErrorReps> {
+    if amount <= 0 {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Charge amount must be greater than 0.".to_string(),
+        ));
+    }
+
+    sqlx::query_scalar!(
+        r#"
+        SELECT c.id
+        FROM cards AS c
+        INNER JOIN accounts AS a ON a.account_number = c.account_number
+        WHERE c.card_number = $1 AND a.bank_id = $2
+        "#,
+        card_number,
+        bank_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    let mut transaction = fraud::screen(
+        pool,
+        audit,
+        ScreenedOperation::CardCharge {
+            card_number: card_number.to_string(),
+            amount,
+        },
+        context,
+    )
+    .await?;
+
+    let posted = post_charge(&mut transaction, audit, card_number, amount).await?;
+
+    transaction.commit().await?;
+
+    Ok(posted)
+}
+
+// Posts a charge that has already been screened, on the caller's transaction
+pub async fn post_charge(
+    conn: &mut PgConnection,
+    audit: &AuditContext,
+    card_number: &str,
+    amount: i32,
+) -> Result<Transaction, CustomerErrorReps> {
+    let card = cards::get_by_card_number(&mut *conn, card_number)
+        .await?
+        .ok_or(CustomerErrorReps::NotFound)?;
+
+    if card.card_status != CardStatus::Active {
+        return Err(CustomerErrorReps::InvalidInput("Card is not active.".to_string()));
+    }
+
+    // Lock the credit line so concurrent charges cannot overdraw it
+    let credit_account = sqlx::query_as!(
+        CreditAccount,
+        r#"
+        SELECT * FROM credit_accounts WHERE card_number = $1 FOR UPDATE
+        "#,
+        card_number
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    if amount > credit_account.available_credit() {
+        return Err(CustomerErrorReps::CreditLimitExceeded(
+            credit_account.available_credit(),
+        ));
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE credit_accounts
+        SET current_balance = current_balance + $1, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $2
+        "#,
+        amount,
+        credit_account.id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let calendar = calendar::load(&mut *conn, credit_account.bank_id).await?;
+    let posted = transactions::insert_transaction(
+        &mut *conn,
+        &calendar,
+        credit_account.branch_id,
+        credit_account.bank_id,
+        &card.account_number,
+        Some(card_number),
+        TransactionType::CreditCardCharge,
+        EntryDirection::Debit,
+        amount,
+        Status::Approved,
+    )
+    .await?;
+
+    audit::record(
+        conn,
+        audit,
+        Change::new("credit_account.charge", "transaction", posted.id, Some(posted.bank_id))
+            .after(&posted),
+    )
+    .await?;
+
+    Ok(posted)
+}
+
+pub async fn make_payment(
+    pool: &PgPool,
+    audit: &AuditContext,
+    account_id: Uuid,
+    amount: i32,
+) -> Result<Transaction, CustomerErrorReps> {
+    if amount <= 0 {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Payment amount must be greater than 0.".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    // The payment comes out of the linked deposit account. Lock it before the credit line,
+    // as card charges do.
+    let account = sqlx::query!(
+        r#"
+        SELECT account_number, balance, overdraft_limit, status as "status: AccountStatus"
+        FROM accounts
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        account_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    let credit_account = sqlx::query_as!(
+        CreditAccount,
+        r#"
+        SELECT * FROM credit_accounts WHERE account_id = $1 FOR UPDATE
+        "#,
+        account_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    if amount > credit_account.current_balance {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Payment cannot exceed the outstanding balance.".to_string(),
+        ));
+    }
+
+    accounts::ensure_can_debit(account.status)?;
+    accounts::ensure_funds(account.balance, account.overdraft_limit, amount)?;
+
+    sqlx::query!(
+        r#"
+        UPDATE accounts
+        SET balance = balance - $1, last_updated_date = CURRENT_DATE, last_activity_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $2
+        "#,
+        amount,
+        account_id
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE credit_accounts
+        SET current_balance = current_balance - $1, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $2
+        "#,
+        amount,
+        credit_account.id
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    // Statement balances are cumulative, so a statement never has more outstanding than
+    // the whole line. Unpaid statements are settled oldest first.
+    let unpaid = sqlx::query_as!(
+        CreditStatement,
+        r#"
+        SELECT id, credit_account_id, period_start, period_end, statement_balance, minimum_payment_due, due_date, amount_paid, interest_charged, late_fee_charged, status as "status: _", inserted_at, updated_at
+        FROM credit_statements
+        WHERE credit_account_id = $1 AND status <> 'paid'
+        ORDER BY period_end
+        FOR UPDATE
+        "#,
+        credit_account.id
+    )
+    .fetch_all(&mut transaction)
+    .await?;
+
+    let new_balance = credit_account.current_balance - amount;
+    let mut remaining = amount;
+    for statement in unpaid {
+        let outstanding = statement.statement_balance - statement.amount_paid;
+        let paid_now = remaining.min(outstanding).max(0);
+        remaining -= paid_now;
+        let applied = paid_now.max(outstanding - new_balance);
+        if applied <= 0 {
+            continue;
+        }
+
+        let amount_paid = statement.amount_paid + applied;
+        let status = if amount_paid >= statement.statement_balance {
+            StatementStatus::Paid
+        } else if amount_paid >= statement.minimum_payment_due
+            && statement.status != StatementStatus::Overdue
+        {
+            StatementStatus::MinimumPaid
+        } else {
+            statement.status
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE credit_statements
+            SET amount_paid = $1, status = $2, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $3
+            "#,
+            amount_paid,
+            status as StatementStatus,
+            statement.id
+        )
+        .execute(&mut transaction)
+        .await?;
+    }
+
+    let calendar = calendar::load(&mut transaction, credit_account.bank_id).await?;
+    let posted = transactions::insert_transaction(
+        &mut transaction,
+        &calendar,
+        credit_account.branch_id,
+        credit_account.bank_id,
+        &account.account_number,
+        Some(&credit_account.card_number),
+        TransactionType::CreditCardPayment,
+        EntryDirection::Debit,
+        amount,
+        Status::Approved,
+    )
+    .await?;
+    overdraft::notify_if_overdrawn(&mut transaction, account_id, account.balance).await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("credit_account.payment", "transaction", posted.id, Some(posted.bank_id))
+            .after(&posted),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(posted)
+}
+
+// Charges the late fee on every statement whose due date has passed without the
+// minimum payment being met, and marks it overdue.
+pub async fn assess_late_fees(
+    pool: &PgPool,
+    bank_id: Option<Uuid>,
+    as_of: NaiveDate,
+) -> Result<u64, sqlx::Error> {
+    let overdue = sqlx::query!(
+        r#"
+        SELECT s.id, s.credit_account_id, c.late_fee, c.card_number, c.bank_id, c.branch_id, a.account_number
+        FROM credit_statements AS s
+        INNER JOIN credit_accounts AS c ON c.id = s.credit_account_id
+        INNER JOIN accounts AS a ON a.id = c.account_id
+        WHERE s.due_date < $1 AND s.status = 'open' AND s.amount_paid < s.minimum_payment_due
+            AND c.current_balance > s.statement_balance - s.minimum_payment_due
+            AND ($2::uuid IS NULL OR c.bank_id = $2)
+        "#,
+        as_of,
+        bank_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let audit = AuditContext::system("statement_cycle");
+    let mut calendars = Calendars::default();
+    let mut assessed = 0;
+    for statement in overdue {
+        let mut transaction = pool.begin().await?;
+
+        // A payment may have landed since the statement was selected
+        let marked = sqlx::query!(
+            r#"
+            UPDATE credit_statements
+            SET status = 'overdue', late_fee_charged = $1, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $2 AND status = 'open' AND amount_paid < minimum_payment_due
+            "#,
+            statement.late_fee,
+            statement.id
+        )
+        .execute(&mut transaction)
+        .await?;
+        if marked.rows_affected() == 0 {
+            transaction.rollback().await?;
+            continue;
+        }
+
+        audit::record(
+            &mut transaction,
+            &audit,
+            Change::new("credit_statement.overdue", "credit_statement", statement.id, Some(statement.bank_id))
+                .after(&serde_json::json!({ "late_fee_charged": statement.late_fee })),
+        )
+        .await?;
+
+        if statement.late_fee > 0 {
+            sqlx::query!(
+                r#"
+                UPDATE credit_accounts
+                SET current_balance = current_balance + $1, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $2
+                "#,
+                statement.late_fee,
+                statement.credit_account_id
+            )
+            .execute(&mut transaction)
+            .await?;
+
+            let calendar = calendars.get(&mut transaction, statement.bank_id).await?;
+            transactions::insert_transaction(
+                &mut transaction,
+                calendar,
+                statement.branch_id,
+                statement.bank_id,
+                &statement.account_number,
+                Some(&statement.card_number),
+                TransactionType::LateFee,
+                EntryDirection::Debit,
+                statement.late_fee,
+                Status::Approved,
+            )
+            .await?;
+        }
+
+        transaction.commit().await?;
+        assessed += 1;
+    }
+
+    Ok(assessed)
+}
+
+// Closes the statement cycle for every credit account whose statement day is `as_of`.
+// Interest is charged on whatever part of the previous statement was carried over,
+// i.e. the grace period only applies when the previous statement was paid in full.
+pub async fn close_statement_cycle(
+    pool: &PgPool,
+    bank_id: Option<Uuid>,
+    as_of: NaiveDate,
+) -> Result<u64, sqlx::Error> {
+    let credit_accounts = sqlx::query_as!(
+        CreditAccount,
+        r#"
+        SELECT * FROM credit_accounts WHERE statement_day = $1 AND ($2::uuid IS NULL OR bank_id = $2)
+        "#,
+        as_of.day() as i32,
+        bank_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let audit = AuditContext::system("statement_cycle");
+    let mut calendars = Calendars::default();
+    let mut closed = 0;
+    for credit_account in credit_accounts {
+        let mut transaction = pool.begin().await?;
+
+        let credit_account = sqlx::query_as!(
+            CreditAccount,
+            r#"
+            SELECT * FROM credit_accounts WHERE id = $1 FOR UPDATE
+            "#,
+            credit_account.id
+        )
+        .fetch_one(&mut transaction)
+        .await?;
+
+        let previous = sqlx::query_as!(
+            CreditStatement,
+            r#"
+            SELECT id, credit_account_id, period_start, period_end, statement_balance, minimum_payment_due, due_date, amount_paid, interest_charged, late_fee_charged, status as "status: _", inserted_at, updated_at
+            FROM credit_statements
+            WHERE credit_account_id = $1
+            ORDER BY period_end DESC
+            LIMIT 1
+            "#,
+            credit_account.id
+        )
+        .fetch_optional(&mut transaction)
+        .await?;
+
+        // The cycle for this date was already closed
+        if previous.as_ref().map(|s| s.period_end) == Some(as_of) {
+            transaction.rollback().await?;
+            continue;
+        }
+
+        let period_start = match &previous {
+            Some(statement) => statement.period_end + Duration::days(1),
+            None => credit_account.inserted_at.date(),
+        };
+
+        let carried_balance = previous
+            .as_ref()
+            .filter(|s| s.status != StatementStatus::Paid)
+            .map(|s| s.statement_balance - s.amount_paid)
+            .unwrap_or(0);
+        let interest = monthly_interest(carried_balance, credit_account.apr_bps);
+
+        let account_number: String = sqlx::query_scalar!(
+            r#"
+            SELECT account_number FROM accounts WHERE id = $1
+            "#,
+            credit_account.account_id
+        )
+        .fetch_one(&mut transaction)
+        .await?;
+
+        let calendar = calendars.get(&mut transaction, credit_account.bank_id).await?;
+        if interest > 0 {
+            sqlx::query!(
+                r#"
+                UPDATE credit_accounts
+                SET current_balance = current_balance + $1, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $2
+                "#,
+                interest,
+                credit_account.id
+            )
+            .execute(&mut transaction)
+            .await?;
+
+            transactions::insert_transaction(
+                &mut transaction,
+                calendar,
+                credit_account.branch_id,
+                credit_account.bank_id,
+                &account_number,
+                Some(&credit_account.card_number),
+                TransactionType::InterestCharge,
+                EntryDirection::Debit,
+                interest,
+                Status::Approved,
+            )
+            .await?;
+        }
+
+        let statement_balance = credit_account.current_balance + interest;
+        let minimum_payment = minimum_payment_due(
+            statement_balance,
+            credit_account.min_payment_bps,
+            credit_account.min_payment_floor,
+        );
+        let status = if statement_balance <= 0 {
+            StatementStatus::Paid
+        } else {
+            StatementStatus::Open
+        };
+        // A due date on a weekend or holiday moves to the next business day
+        let due_date = as_of + Duration::days(credit_account.grace_period_days as i64);
+        let due_date = calendar.business_day_on_or_after(due_date);
+
+        let statement = sqlx::query_as!(
+            CreditStatement,
+            r#"
+            INSERT INTO credit_statements (id, credit_account_id, period_start, period_end, statement_balance, minimum_payment_due, due_date, amount_paid, interest_charged, late_fee_charged, status, inserted_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, 0, $8, 0, $9, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            RETURNING id, credit_account_id, period_start, period_end, statement_balance, minimum_payment_due, due_date, amount_paid, interest_charged, late_fee_charged, status as "status: _", inserted_at, updated_at
+            "#,
+            Uuid::new_v4(),
+            credit_account.id,
+            period_start,
+            as_of,
+            statement_balance,
+            minimum_payment,
+            due_date,
+            interest,
+            status as StatementStatus,
+        )
+        .fetch_one(&mut transaction)
+        .await?;
+
+        audit::record(
+            &mut transaction,
+            &audit,
+            Change::new("credit_statement.close", "credit_statement", statement.id, Some(credit_account.bank_id))
+                .after(&statement),
+        )
+        .await?;
+
+        transaction.commit().await?;
+        closed += 1;
+    }
+
+    Ok(closed)
+}
+
+// Daily statement-cycle batch: late fees first so that they land on the statement
+// being closed today. Returns the number of late fees assessed and statements closed.
+pub async fn run_statement_cycle(
+    pool: &PgPool,
+    bank_id: Option<Uuid>,
+    as_of: NaiveDate,
+) -> Result<u64, sqlx::Error> {
+    let late_fees = assess_late_fees(pool, bank_id, as_of).await?;
+    let statements = close_statement_cycle(pool, bank_id, as_of).await?;
+
+    tracing::info!(
+        "statement cycle for {}: {} late fees assessed, {} statements closed",
+        as_of,
+        late_fees,
+        statements
+    );
+
+    Ok(late_fees + statements)
+}
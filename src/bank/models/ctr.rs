@@ -0,0 +1,195 @@
+use std::path::Path;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::bank::helper::validation::CustomerErrorReps;
+
+// Currency transaction report: a customer's teller cash activity for one business day
+// that reached the bank's reporting threshold in either direction.
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct CashTransactionReport {
+    pub id: Uuid,
+    pub bank_id: Uuid,
+    pub branch_id: Uuid,
+    pub customer_id: Uuid,
+    pub business_date: NaiveDate,
+    pub customer_name: String,
+    pub cic_number: String,
+    pub total_cash_in: i64,
+    pub total_cash_out: i64,
+    pub transaction_count: i32,
+    pub transaction_ids: Vec<Uuid>,
+    pub threshold: i32,
+    pub exported_at: Option<NaiveDateTime>,
+    pub inserted_at: NaiveDateTime,
+}
+
+// The document handed to the regulator for one bank and business day
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CtrFiling {
+    pub bank_id: Uuid,
+    pub business_date: NaiveDate,
+    pub generated_at: NaiveDateTime,
+    pub report_count: usize,
+    pub reports: Vec<CashTransactionReport>,
+}
+
+// Aggregates the day's approved cash deposits and withdrawals per customer and records a
+// report for every customer whose cash in or cash out reached the bank's threshold, so
+// structuring one large amount into several smaller ones is still reported. Cash on a
+// shared account counts towards each primary and joint holder; authorized signers act
+// for the owners and are not attributed cash. Re-running for the same date refreshes
+// that day's reports. `bank_id` limits the run to one bank's customers.
+pub async fn generate_reports(
+    pool: &PgPool,
+    bank_id: Option<Uuid>,
+    business_date: NaiveDate,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO cash_transaction_reports (id, bank_id, branch_id, customer_id, business_date, customer_name, cic_number, total_cash_in, total_cash_out, transaction_count, transaction_ids, threshold, inserted_at)
+        SELECT gen_random_uuid(), c.bank_id, c.branch_id, c.id, $1, c.customer_name, c.cic_number,
+            d.total_cash_in, d.total_cash_out, d.transaction_count, d.transaction_ids, k.ctr_threshold, CURRENT_TIMESTAMP
+        FROM (
+            SELECT h.customer_id,
+                COALESCE(SUM(t.amount) FILTER (WHERE t.transaction_type = 'cash_deposit'), 0)::BIGINT AS total_cash_in,
+                COALESCE(SUM(t.amount) FILTER (WHERE t.transaction_type = 'cash_withdrawal'), 0)::BIGINT AS total_cash_out,
+                COUNT(t.id)::INTEGER AS transaction_count,
+                ARRAY_AGG(t.id ORDER BY t.inserted_at) AS transaction_ids
+            FROM transactions AS t
+            INNER JOIN accounts AS a ON a.account_number = t.account_number
+            INNER JOIN account_holders AS h ON h.account_id = a.id AND h.role IN ('primary', 'joint')
+            WHERE t.transaction_date = $1
+                AND t.transaction_type IN ('cash_deposit', 'cash_withdrawal')
+                AND t.status = 'approved'
+            GROUP BY h.customer_id
+        ) AS d
+        INNER JOIN customers AS c ON c.id = d.customer_id
+        INNER JOIN banks AS k ON k.id = c.bank_id
+        WHERE (d.total_cash_in >= k.ctr_threshold OR d.total_cash_out >= k.ctr_threshold)
+            AND ($2::uuid IS NULL OR c.bank_id = $2)
+        ON CONFLICT (customer_id, business_date) DO UPDATE
+        SET total_cash_in = EXCLUDED.total_cash_in,
+            total_cash_out = EXCLUDED.total_cash_out,
+            transaction_count = EXCLUDED.transaction_count,
+            transaction_ids = EXCLUDED.transaction_ids,
+            threshold = EXCLUDED.threshold,
+            exported_at = NULL,
+            inserted_at = EXCLUDED.inserted_at
+        "#,
+        business_date,
+        bank_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn get_reports(
+    pool: &PgPool,
+    bank_id: Uuid,
+    business_date: NaiveDate,
+) -> Result<Vec<CashTransactionReport>, sqlx::Error> {
+    let reports = sqlx::query_as!(
+        CashTransactionReport,
+        r#"
+        SELECT * FROM cash_transaction_reports
+        WHERE bank_id = $1 AND business_date = $2
+        ORDER BY customer_name, customer_id
+        "#,
+        bank_id,
+        business_date
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(reports)
+}
+
+// Builds the filing for one bank and day and marks its reports as exported
+pub async fn export_filing(
+    pool: &PgPool,
+    bank_id: Uuid,
+    business_date: NaiveDate,
+) -> Result<CtrFiling, sqlx::Error> {
+    let filing = build_filing(pool, bank_id, business_date).await?;
+    mark_exported(pool, &filing).await?;
+
+    Ok(filing)
+}
+
+async fn build_filing(
+    pool: &PgPool,
+    bank_id: Uuid,
+    business_date: NaiveDate,
+) -> Result<CtrFiling, sqlx::Error> {
+    let reports = get_reports(pool, bank_id, business_date).await?;
+
+    Ok(CtrFiling {
+        bank_id,
+        business_date,
+        generated_at: chrono::Utc::now().naive_utc(),
+        report_count: reports.len(),
+        reports,
+    })
+}
+
+// Marks exactly the reports in the filing; one regenerated since it was built keeps
+// waiting for the next export
+async fn mark_exported(pool: &PgPool, filing: &CtrFiling) -> Result<(), sqlx::Error> {
+    let ids: Vec<Uuid> = filing.reports.iter().map(|report| report.id).collect();
+    let inserted_at: Vec<NaiveDateTime> =
+        filing.reports.iter().map(|report| report.inserted_at).collect();
+
+    sqlx::query!(
+        r#"
+        UPDATE cash_transaction_reports AS r
+        SET exported_at = CURRENT_TIMESTAMP
+        FROM UNNEST($1::uuid[], $2::timestamp[]) AS f(id, inserted_at)
+        WHERE r.id = f.id AND r.inserted_at = f.inserted_at
+        "#,
+        &ids,
+        &inserted_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// Writes one `ctr_<bank_id>_<date>.json` filing into `directory` for every bank and day
+// with reports not yet exported, so days missed by an earlier run are caught up. A
+// filing's reports are only marked exported once its file is written. Returns the
+// number of files written.
+pub async fn export_filings(pool: &PgPool, directory: &Path) -> Result<usize, CustomerErrorReps> {
+    let pending = sqlx::query!(
+        r#"
+        SELECT DISTINCT bank_id, business_date
+        FROM cash_transaction_reports
+        WHERE exported_at IS NULL
+        ORDER BY business_date, bank_id
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for filing_key in &pending {
+        let filing = build_filing(pool, filing_key.bank_id, filing_key.business_date).await?;
+        let path = directory.join(format!(
+            "ctr_{}_{}.json",
+            filing_key.bank_id, filing_key.business_date
+        ));
+        let content = serde_json::to_vec_pretty(&filing)
+            .map_err(|err| CustomerErrorReps::InvalidInput(err.to_string()))?;
+        std::fs::write(&path, content).map_err(|err| {
+            CustomerErrorReps::InvalidInput(format!("could not write {}: {}", path.display(), err))
+        })?;
+        mark_exported(pool, &filing).await?;
+    }
+
+    Ok(pending.len())
+}
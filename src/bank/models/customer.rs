@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use uuid::Uuid;
 use crate::bank::helper::{ validation::{*}};
-use super::{accounts, cards};
+use super::{accounts, audit::{self, AuditContext, Change}, cards, outbox::{self, DomainEvent}, sanctions};
 
 
 
@@ -22,6 +22,7 @@ pub struct Customer {
 }
 pub async fn create_customer(
     pool: &PgPool,
+    audit: &AuditContext,
     branch_id: Uuid,
     bank_id: Uuid,
     customer_name: String,
@@ -81,9 +82,20 @@ pub async fn create_customer(
     let validated_cic_number = validate_input(cic_number.clone(), validate_cic_number)
         .map_err(|validation_result| CustomerErrorReps::InvalidInput(validation_result.error_message.unwrap()))?;
 
+    sanctions::ensure_not_listed(
+        pool,
+        bank_id,
+        sanctions::SUBJECT_CUSTOMER,
+        &validated_cic_number,
+        &validated_customer_name,
+    )
+    .await?;
+
     let customer_id = Uuid::new_v4();
     let current_timestamp = chrono::Utc::now().naive_utc();
 
+    let mut transaction = pool.begin().await?;
+
     let customer = sqlx::query!(
         r#"
         INSERT INTO customers (branch_id, bank_id, id, customer_name, email, phone_number, cic_number, inserted_at, updated_at)
@@ -98,11 +110,16 @@ pub async fn create_customer(
         validated_phone_number,
         validated_cic_number,
     )
-    .fetch_one(pool)
+    .fetch_one(&mut transaction)
     .await
-    .map_err(CustomerErrorReps::DatabaseError)?;
-
-    Ok(Customer {
+    .map_err(|err| match err {
+        sqlx::Error::Database(ref db_err) if db_err.constraint() == Some("customers_email_key") => {
+            CustomerErrorReps::InvalidInput("Email is already registered.".to_string())
+        }
+        err => CustomerErrorReps::DatabaseError(err),
+    })?;
+
+    let customer = Customer {
         branch_id: customer.branch_id,
         bank_id: customer.bank_id,
         id: customer.id,
@@ -112,7 +129,25 @@ pub async fn create_customer(
         cic_number: customer.cic_number,
         inserted_at: customer.inserted_at,
         updated_at: customer.updated_at,
-    })
+    };
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("customer.create", "customer", customer.id, Some(customer.bank_id)).after(&customer),
+    )
+    .await?;
+
+    outbox::publish(
+        &mut transaction,
+        Some(customer.bank_id),
+        &DomainEvent::CustomerCreated(customer.clone()),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(customer)
 }
 
 
@@ -0,0 +1,545 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::bank::helper::validation::CustomerErrorReps;
+
+use super::{
+    accounts, calendar, credit, ctr, fees, overdraft, payment_batches, standing_orders, treasury,
+    types::BatchRunStatus,
+};
+
+// Checkpoint of one step for one bank and business date
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct BatchRun {
+    pub id: Uuid,
+    pub bank_id: Uuid,
+    pub business_date: NaiveDate,
+    pub step: String,
+    pub status: BatchRunStatus,
+    pub attempts: i32,
+    pub processed: Option<i64>,
+    pub error: Option<String>,
+    pub started_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+// The bank and business date a run is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EodContext {
+    pub bank_id: Uuid,
+    pub business_date: NaiveDate,
+}
+
+impl EodContext {
+    // Jobs that look at the clock see the last moment of the business date
+    pub fn end_of_day(&self) -> NaiveDateTime {
+        self.business_date
+            .and_time(NaiveTime::from_hms_opt(23, 59, 59).expect("end of day is a valid time"))
+    }
+}
+
+// One job of the end-of-day run. A step that fails part way is run again from the start
+// on resume, so every step must be safe to repeat for the same bank and date.
+#[async_trait]
+pub trait EodStep: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // Returns the number of records processed
+    async fn run(&self, pool: &PgPool, context: &EodContext) -> Result<u64, CustomerErrorReps>;
+}
+
+// Standing orders and retries that came due during the day
+pub struct StandingOrdersStep;
+
+#[async_trait]
+impl EodStep for StandingOrdersStep {
+    fn name(&self) -> &'static str {
+        "standing_orders"
+    }
+
+    async fn run(&self, pool: &PgPool, context: &EodContext) -> Result<u64, CustomerErrorReps> {
+        let executed = standing_orders::run_due_standing_orders(
+            pool,
+            Some(context.bank_id),
+            context.end_of_day(),
+        )
+        .await?;
+        Ok(executed)
+    }
+}
+
+// Payment batches requested for the day that are still waiting
+pub struct PaymentBatchesStep;
+
+#[async_trait]
+impl EodStep for PaymentBatchesStep {
+    fn name(&self) -> &'static str {
+        "payment_batches"
+    }
+
+    async fn run(&self, pool: &PgPool, context: &EodContext) -> Result<u64, CustomerErrorReps> {
+        let processed = payment_batches::process_due_batches(
+            pool,
+            Some(context.bank_id),
+            context.business_date,
+        )
+        .await?;
+        Ok(processed as u64)
+    }
+}
+
+pub struct MaintenanceFeesStep;
+
+#[async_trait]
+impl EodStep for MaintenanceFeesStep {
+    fn name(&self) -> &'static str {
+        "maintenance_fees"
+    }
+
+    async fn run(&self, pool: &PgPool, context: &EodContext) -> Result<u64, CustomerErrorReps> {
+        fees::charge_maintenance_fees(pool, Some(context.bank_id), context.business_date).await
+    }
+}
+
+// Interest and unarranged overdraft fees on the balances the day's postings left
+pub struct OverdraftInterestStep;
+
+#[async_trait]
+impl EodStep for OverdraftInterestStep {
+    fn name(&self) -> &'static str {
+        "overdraft_interest"
+    }
+
+    async fn run(&self, pool: &PgPool, context: &EodContext) -> Result<u64, CustomerErrorReps> {
+        overdraft::accrue_interest(pool, Some(context.bank_id), context.business_date).await
+    }
+}
+
+pub struct StatementCycleStep;
+
+#[async_trait]
+impl EodStep for StatementCycleStep {
+    fn name(&self) -> &'static str {
+        "statement_cycle"
+    }
+
+    async fn run(&self, pool: &PgPool, context: &EodContext) -> Result<u64, CustomerErrorReps> {
+        let processed =
+            credit::run_statement_cycle(pool, Some(context.bank_id), context.business_date).await?;
+        Ok(processed)
+    }
+}
+
+pub struct DormancyStep;
+
+#[async_trait]
+impl EodStep for DormancyStep {
+    fn name(&self) -> &'static str {
+        "dormancy"
+    }
+
+    async fn run(&self, pool: &PgPool, context: &EodContext) -> Result<u64, CustomerErrorReps> {
+        let marked =
+            accounts::mark_dormant_accounts(pool, Some(context.bank_id), context.end_of_day())
+                .await?;
+        Ok(marked as u64)
+    }
+}
+
+// Branch vault positions once the day's cash movements are done
+pub struct CashPositionsStep;
+
+#[async_trait]
+impl EodStep for CashPositionsStep {
+    fn name(&self) -> &'static str {
+        "cash_positions"
+    }
+
+    async fn run(&self, pool: &PgPool, context: &EodContext) -> Result<u64, CustomerErrorReps> {
+        let snapshots =
+            treasury::snapshot_cash_positions(pool, Some(context.bank_id), context.business_date)
+                .await?;
+        Ok(snapshots)
+    }
+}
+
+pub struct CashTransactionReportsStep;
+
+#[async_trait]
+impl EodStep for CashTransactionReportsStep {
+    fn name(&self) -> &'static str {
+        "cash_transaction_reports"
+    }
+
+    async fn run(&self, pool: &PgPool, context: &EodContext) -> Result<u64, CustomerErrorReps> {
+        let reported =
+            ctr::generate_reports(pool, Some(context.bank_id), context.business_date).await?;
+        Ok(reported)
+    }
+}
+
+// Steps in the order they run: postings first, then the cycles and snapshots that
+// depend on the day's balances
+pub fn registry() -> Vec<Box<dyn EodStep>> {
+    vec![
+        Box::new(StandingOrdersStep),
+        Box::new(PaymentBatchesStep),
+        Box::new(MaintenanceFeesStep),
+        Box::new(OverdraftInterestStep),
+        Box::new(StatementCycleStep),
+        Box::new(DormancyStep),
+        Box::new(CashPositionsStep),
+        Box::new(CashTransactionReportsStep),
+    ]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StepOutcome {
+    Completed,
+    Failed,
+    // Completed by an earlier run for the same date
+    Skipped,
+    // Dry run: the step would run
+    Pending,
+    // An earlier step failed
+    NotRun,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StepReport {
+    pub step: &'static str,
+    pub outcome: StepOutcome,
+    pub processed: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl StepReport {
+    fn new(step: &'static str, outcome: StepOutcome) -> Self {
+        Self {
+            step,
+            outcome,
+            processed: None,
+            error: None,
+        }
+    }
+}
+
+// `None` when another instance holds the bank's lock
+pub type BankRunResult = Result<Option<Vec<StepReport>>, CustomerErrorReps>;
+
+fn lock_key(bank_id: Uuid) -> String {
+    format!("eod:{}", bank_id)
+}
+
+// Transaction-level advisory lock, held by a transaction of its own for the whole run. It
+// goes with the transaction however the run ends, so the pooled connection never keeps it.
+async fn try_lock(conn: &mut PgConnection, bank_id: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT pg_try_advisory_xact_lock(hashtextextended($1, 0)) AS "locked!"
+        "#,
+        lock_key(bank_id)
+    )
+    .fetch_one(conn)
+    .await
+}
+
+// Also restarts a step left running by a run that died; holding the bank's lock rules
+// out a live one
+async fn start_step(
+    pool: &PgPool,
+    context: &EodContext,
+    step: &str,
+) -> Result<BatchRun, sqlx::Error> {
+    sqlx::query_as!(
+        BatchRun,
+        r#"
+        INSERT INTO batch_runs (id, bank_id, business_date, step, status, attempts, started_at)
+        VALUES ($1, $2, $3, $4, 'running', 1, CURRENT_TIMESTAMP)
+        ON CONFLICT (bank_id, business_date, step) DO UPDATE
+        SET status = 'running', attempts = batch_runs.attempts + 1, processed = NULL, error = NULL, started_at = CURRENT_TIMESTAMP, finished_at = NULL
+        RETURNING id, bank_id, business_date, step, status as "status: _", attempts, processed, error, started_at, finished_at
+        "#,
+        Uuid::new_v4(),
+        context.bank_id,
+        context.business_date,
+        step
+    )
+    .fetch_one(pool)
+    .await
+}
+
+async fn finish_step(
+    pool: &PgPool,
+    run_id: Uuid,
+    status: BatchRunStatus,
+    processed: Option<i64>,
+    error: Option<String>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE batch_runs
+        SET status = $2, processed = $3, error = $4, finished_at = CURRENT_TIMESTAMP
+        WHERE id = $1
+        "#,
+        run_id,
+        status as BatchRunStatus,
+        processed,
+        error
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn run_steps(
+    pool: &PgPool,
+    context: &EodContext,
+    dry_run: bool,
+) -> Result<Vec<StepReport>, CustomerErrorReps> {
+    let completed: HashSet<String> = sqlx::query_scalar!(
+        r#"
+        SELECT step FROM batch_runs
+        WHERE bank_id = $1 AND business_date = $2 AND status = 'completed'
+        "#,
+        context.bank_id,
+        context.business_date
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+
+    let mut reports = Vec::new();
+    let mut failed = false;
+    for step in registry() {
+        let name = step.name();
+        if completed.contains(name) {
+            reports.push(StepReport::new(name, StepOutcome::Skipped));
+            continue;
+        }
+        if failed {
+            reports.push(StepReport::new(name, StepOutcome::NotRun));
+            continue;
+        }
+        if dry_run {
+            reports.push(StepReport::new(name, StepOutcome::Pending));
+            continue;
+        }
+
+        let run = start_step(pool, context, name).await?;
+        match step.run(pool, context).await {
+            Ok(processed) => {
+                finish_step(
+                    pool,
+                    run.id,
+                    BatchRunStatus::Completed,
+                    Some(processed as i64),
+                    None,
+                )
+                .await?;
+                reports.push(StepReport {
+                    processed: Some(processed),
+                    ..StepReport::new(name, StepOutcome::Completed)
+                });
+            }
+            Err(err) => {
+                tracing::error!(
+                    "end of day step {} failed for bank {} on {}: {}",
+                    name,
+                    context.bank_id,
+                    context.business_date,
+                    err
+                );
+                finish_step(
+                    pool,
+                    run.id,
+                    BatchRunStatus::Failed,
+                    None,
+                    Some(err.to_string()),
+                )
+                .await?;
+                reports.push(StepReport {
+                    error: Some(err.to_string()),
+                    ..StepReport::new(name, StepOutcome::Failed)
+                });
+                failed = true;
+            }
+        }
+    }
+
+    Ok(reports)
+}
+
+// Runs the end-of-day steps for one bank and business date, resuming after the last
+// completed step. A dry run reports which steps would run without running them.
+pub async fn run(
+    pool: &PgPool,
+    bank_id: Uuid,
+    business_date: NaiveDate,
+    dry_run: bool,
+) -> BankRunResult {
+    let mut lock = pool.begin().await?;
+    if !try_lock(&mut lock, bank_id).await? {
+        return Ok(None);
+    }
+
+    let context = EodContext {
+        bank_id,
+        business_date,
+    };
+    let result = run_steps(pool, &context, dry_run).await;
+    lock.rollback().await?;
+
+    result.map(Some)
+}
+
+// The bank's business dates still waiting for their end of day, oldest first: every
+// business day after the last one whose steps all completed, up to the last business day
+// before `today`. Only that last business day when no run has completed yet.
+pub async fn due_dates(
+    pool: &PgPool,
+    bank_id: Uuid,
+    today: NaiveDate,
+) -> Result<Vec<NaiveDate>, sqlx::Error> {
+    let last_completed = sqlx::query_scalar!(
+        r#"
+        SELECT MAX(business_date) FROM (
+            SELECT business_date FROM batch_runs
+            WHERE bank_id = $1 AND status = 'completed'
+            GROUP BY business_date
+            HAVING COUNT(*) >= $2
+        ) AS completed
+        "#,
+        bank_id,
+        registry().len() as i64
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let calendar = calendar::load(pool, bank_id).await?;
+    let latest = calendar.previous_business_day(today);
+    let mut date = match last_completed {
+        Some(last_completed) => calendar.next_business_day(last_completed),
+        None => latest,
+    };
+
+    let mut dates = Vec::new();
+    while date <= latest {
+        dates.push(date);
+        date = calendar.next_business_day(date);
+    }
+
+    Ok(dates)
+}
+
+fn is_complete(result: &BankRunResult) -> bool {
+    match result {
+        Ok(Some(reports)) => reports
+            .iter()
+            .all(|report| !matches!(report.outcome, StepOutcome::Failed | StepOutcome::NotRun)),
+        _ => false,
+    }
+}
+
+// Runs each due business date of the bank in turn. Stops at the first date that does not
+// complete, so no date runs on balances an earlier one has yet to settle.
+pub async fn run_due(
+    pool: &PgPool,
+    bank_id: Uuid,
+    today: NaiveDate,
+    dry_run: bool,
+) -> Result<Vec<(NaiveDate, BankRunResult)>, sqlx::Error> {
+    let mut results = Vec::new();
+    for business_date in due_dates(pool, bank_id, today).await? {
+        let result = run(pool, bank_id, business_date, dry_run).await;
+        let stop = !dry_run && !is_complete(&result);
+        results.push((business_date, result));
+        if stop {
+            break;
+        }
+    }
+
+    Ok(results)
+}
+
+// Runs every bank in turn; a failing bank does not hold up the others
+pub async fn run_all(
+    pool: &PgPool,
+    business_date: NaiveDate,
+    dry_run: bool,
+) -> Result<Vec<(Uuid, BankRunResult)>, sqlx::Error> {
+    let bank_ids = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM banks ORDER BY bank_code
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut results = Vec::with_capacity(bank_ids.len());
+    for bank_id in bank_ids {
+        let result = run(pool, bank_id, business_date, dry_run).await;
+        results.push((bank_id, result));
+    }
+
+    Ok(results)
+}
+
+// `run_due` for every bank in turn; a failing bank does not hold up the others
+pub async fn run_all_due(
+    pool: &PgPool,
+    today: NaiveDate,
+    dry_run: bool,
+) -> Result<Vec<(Uuid, NaiveDate, BankRunResult)>, sqlx::Error> {
+    let bank_ids = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM banks ORDER BY bank_code
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut results = Vec::new();
+    for bank_id in bank_ids {
+        match run_due(pool, bank_id, today, dry_run).await {
+            Ok(runs) => results.extend(
+                runs.into_iter()
+                    .map(|(business_date, result)| (bank_id, business_date, result)),
+            ),
+            Err(err) => {
+                tracing::error!("end of day dates for bank {} unavailable: {}", bank_id, err)
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+pub async fn get_runs(
+    pool: &PgPool,
+    bank_id: Uuid,
+    business_date: NaiveDate,
+) -> Result<Vec<BatchRun>, sqlx::Error> {
+    let runs = sqlx::query_as!(
+        BatchRun,
+        r#"
+        SELECT id, bank_id, business_date, step, status as "status: _", attempts, processed, error, started_at, finished_at
+        FROM batch_runs
+        WHERE bank_id = $1 AND business_date = $2
+        ORDER BY started_at
+        "#,
+        bank_id,
+        business_date
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(runs)
+}
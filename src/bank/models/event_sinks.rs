@@ -0,0 +1,191 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use hyper::{client::HttpConnector, Body, Client, Request, StatusCode, Uri};
+use sqlx::PgPool;
+
+use super::{outbox::OutboxMessage, webhooks};
+
+pub const NOTIFY_CHANNEL: &str = "domain_events";
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum SinkError {
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("HTTP error: {0}")]
+    Http(#[from] hyper::Error),
+    #[error("Invalid request: {0}")]
+    Request(#[from] hyper::http::Error),
+    #[error("Endpoint responded with {0}")]
+    Rejected(StatusCode),
+    #[error("Timed out")]
+    Timeout,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SinkConfigError {
+    #[error("unknown outbox sink: {0}")]
+    UnknownSink(String),
+    #[error("OUTBOX_WEBHOOK_URL must be in environment for the webhook sink")]
+    MissingWebhookUrl,
+    #[error("OUTBOX_WEBHOOK_URL must be a valid URL: {0}")]
+    InvalidWebhookUrl(#[from] hyper::http::uri::InvalidUri),
+}
+
+// A downstream destination for outbox messages. Delivery must be idempotent from the
+// consumer's point of view, as the relay retries until every sink succeeds.
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn deliver(&self, message: &OutboxMessage) -> Result<(), SinkError>;
+}
+
+// Writes each message as one JSON line on stdout, for log shippers
+pub struct StdoutSink;
+
+#[async_trait]
+impl EventSink for StdoutSink {
+    fn name(&self) -> &'static str {
+        "stdout"
+    }
+
+    async fn deliver(&self, message: &OutboxMessage) -> Result<(), SinkError> {
+        println!("{}", serde_json::to_string(message)?);
+        Ok(())
+    }
+}
+
+// Signals listeners on NOTIFY_CHANNEL. Postgres caps payloads at 8000 bytes, so only
+// the envelope is sent and listeners read the payload from the outbox table.
+pub struct PgNotifySink {
+    pool: PgPool,
+}
+
+impl PgNotifySink {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl EventSink for PgNotifySink {
+    fn name(&self) -> &'static str {
+        "pg_notify"
+    }
+
+    async fn deliver(&self, message: &OutboxMessage) -> Result<(), SinkError> {
+        let envelope = serde_json::json!({
+            "sequence": message.sequence,
+            "id": message.id,
+            "event_type": message.event_type,
+            "aggregate_type": message.aggregate_type,
+            "aggregate_id": message.aggregate_id,
+        });
+
+        sqlx::query!(
+            "SELECT pg_notify($1, $2)",
+            NOTIFY_CHANNEL,
+            envelope.to_string()
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+// POSTs each message as JSON to a fixed endpoint; any non-2xx response is a failure
+pub struct WebhookSink {
+    url: Uri,
+    client: Client<HttpConnector>,
+}
+
+impl WebhookSink {
+    pub fn new(url: Uri) -> Self {
+        Self {
+            url,
+            client: Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl EventSink for WebhookSink {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn deliver(&self, message: &OutboxMessage) -> Result<(), SinkError> {
+        let request = Request::post(self.url.clone())
+            .header("content-type", "application/json")
+            .header("x-event-id", message.id.to_string())
+            .header("x-event-type", message.event_type.as_str())
+            .body(Body::from(serde_json::to_vec(message)?))?;
+
+        let response = tokio::time::timeout(WEBHOOK_TIMEOUT, self.client.request(request))
+            .await
+            .map_err(|_| SinkError::Timeout)??;
+        if !response.status().is_success() {
+            return Err(SinkError::Rejected(response.status()));
+        }
+
+        Ok(())
+    }
+}
+
+// Queues signed deliveries for the API clients' registered webhook endpoints; the
+// webhook dispatcher sends them.
+pub struct WebhookFanoutSink {
+    pool: PgPool,
+}
+
+impl WebhookFanoutSink {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl EventSink for WebhookFanoutSink {
+    fn name(&self) -> &'static str {
+        "webhooks"
+    }
+
+    async fn deliver(&self, message: &OutboxMessage) -> Result<(), SinkError> {
+        let mut conn = self.pool.acquire().await?;
+        webhooks::enqueue(&mut conn, message).await?;
+        Ok(())
+    }
+}
+
+// Builds the sinks named in OUTBOX_SINKS (comma separated: stdout, pg_notify, webhook,
+// webhooks). The single webhook sink posts to OUTBOX_WEBHOOK_URL. Called at startup so
+// a misconfiguration stops the server instead of the relay.
+pub fn from_env(pool: &PgPool) -> Result<Vec<Box<dyn EventSink>>, SinkConfigError> {
+    let names = std::env::var("OUTBOX_SINKS").unwrap_or_else(|_| "stdout".to_string());
+
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| -> Result<Box<dyn EventSink>, SinkConfigError> {
+            match name {
+                "stdout" => Ok(Box::new(StdoutSink)),
+                "pg_notify" => Ok(Box::new(PgNotifySink::new(pool.clone()))),
+                "webhooks" => Ok(Box::new(WebhookFanoutSink::new(pool.clone()))),
+                "webhook" => {
+                    let url = std::env::var("OUTBOX_WEBHOOK_URL")
+                        .map_err(|_| SinkConfigError::MissingWebhookUrl)?
+                        .parse()?;
+                    Ok(Box::new(WebhookSink::new(url)))
+                }
+                other => Err(SinkConfigError::UnknownSink(other.to_string())),
+            }
+        })
+        .collect()
+}
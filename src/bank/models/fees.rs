@@ -0,0 +1,922 @@
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::bank::helper::validation::CustomerErrorReps;
+
+use super::{
+    accounts,
+    audit::{self, AuditContext, Change},
+    calendar::{self, BusinessCalendar, Calendars},
+    transactions::{self, Transaction},
+    types::{
+        AccountStatus, AccountType, EntryDirection, FeeCalculation, FeeStatus, FeeType, Status,
+        TransactionType,
+    },
+};
+
+// Transaction types a transaction fee can be charged on
+pub const CHARGEABLE_TRANSACTION_TYPES: [TransactionType; 3] = [
+    TransactionType::P2P,
+    TransactionType::CashWithdrawal,
+    TransactionType::CashDeposit,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    pub id: Uuid,
+    pub bank_id: Uuid,
+    pub fee_name: String,
+    pub fee_type: FeeType,
+    pub transaction_type: Option<TransactionType>,
+    pub account_type: Option<AccountType>,
+    pub calculation: FeeCalculation,
+    pub amount: i32,
+    pub rate_bps: i32,
+    pub min_amount: Option<i32>,
+    pub max_amount: Option<i32>,
+    pub enabled: bool,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl FeeSchedule {
+    // The fee on `base`: the transaction amount, or the balance for maintenance fees
+    pub fn fee_for(&self, base: i32) -> i32 {
+        let fee = match self.calculation {
+            FeeCalculation::Flat => self.amount,
+            FeeCalculation::Percentage => percentage_of(base, self.rate_bps),
+        };
+        let fee = self
+            .min_amount
+            .map_or(fee, |min_amount| fee.max(min_amount));
+        let fee = self
+            .max_amount
+            .map_or(fee, |max_amount| fee.min(max_amount));
+        fee.max(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeScheduleSettings {
+    pub fee_name: String,
+    pub fee_type: FeeType,
+    pub transaction_type: Option<TransactionType>,
+    pub account_type: Option<AccountType>,
+    pub calculation: FeeCalculation,
+    pub amount: Option<i32>,
+    pub rate_bps: Option<i32>,
+    pub min_amount: Option<i32>,
+    pub max_amount: Option<i32>,
+    pub enabled: Option<bool>,
+}
+
+impl FeeScheduleSettings {
+    pub fn validate(&self) -> Result<(), CustomerErrorReps> {
+        let invalid = |message: &str| Err(CustomerErrorReps::InvalidInput(message.to_string()));
+
+        if self.fee_name.trim().is_empty() {
+            return invalid("Fee name is required.");
+        }
+        match (self.fee_type, &self.transaction_type) {
+            (FeeType::Transaction, Some(transaction_type))
+                if CHARGEABLE_TRANSACTION_TYPES.contains(transaction_type) => {}
+            (FeeType::Transaction, Some(_)) => {
+                return invalid("Fees cannot be charged on this transaction type.")
+            }
+            (FeeType::Transaction, None) => {
+                return invalid("Transaction fees need a transaction type.")
+            }
+            (_, Some(_)) => return invalid("Only transaction fees have a transaction type."),
+            (_, None) => {}
+        }
+        match self.calculation {
+            FeeCalculation::Flat if self.amount.unwrap_or(0) <= 0 => {
+                return invalid("Flat fees need an amount greater than 0.")
+            }
+            FeeCalculation::Percentage if !(1..=10_000).contains(&self.rate_bps.unwrap_or(0)) => {
+                return invalid("Percentage fees need a rate between 1 and 10000 basis points.")
+            }
+            _ => {}
+        }
+        if self.min_amount.map_or(false, |min_amount| min_amount < 0)
+            || self.max_amount.map_or(false, |max_amount| max_amount < 0)
+        {
+            return invalid("Fee bounds cannot be negative.");
+        }
+        if let (Some(min_amount), Some(max_amount)) = (self.min_amount, self.max_amount) {
+            if min_amount > max_amount {
+                return invalid("Minimum fee cannot exceed the maximum fee.");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct FeeWaiver {
+    pub id: Uuid,
+    pub bank_id: Uuid,
+    pub account_id: Uuid,
+    pub fee_type: Option<FeeType>,
+    pub reason: String,
+    pub valid_until: Option<NaiveDate>,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct FeeCharge {
+    pub id: Uuid,
+    pub bank_id: Uuid,
+    pub account_id: Uuid,
+    pub fee_schedule_id: Option<Uuid>,
+    pub fee_type: FeeType,
+    pub amount: i32,
+    pub status: FeeStatus,
+    pub source_transaction_id: Option<Uuid>,
+    pub fee_transaction_id: Option<Uuid>,
+    pub reversal_transaction_id: Option<Uuid>,
+    pub fee_waiver_id: Option<Uuid>,
+    pub period_start: Option<NaiveDate>,
+    pub reversal_reason: Option<String>,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// The account a fee is charged to, locked by the caller
+struct FeeAccount {
+    id: Uuid,
+    bank_id: Uuid,
+    branch_id: Uuid,
+    account_number: String,
+    account_type: AccountType,
+    balance: i32,
+    overdraft_limit: i32,
+}
+
+// A fee owed before waivers are applied. Fees from `Bank.fee` have no schedule.
+struct AssessedFee {
+    fee_schedule_id: Option<Uuid>,
+    fee_type: FeeType,
+    amount: i32,
+}
+
+// Rounded half up
+pub fn percentage_of(base: i32, rate_bps: i32) -> i32 {
+    if base <= 0 {
+        return 0;
+    }
+    ((base as i64 * rate_bps as i64 + 5_000) / 10_000) as i32
+}
+
+pub async fn get_schedules(pool: &PgPool, bank_id: Uuid) -> Result<Vec<FeeSchedule>, sqlx::Error> {
+    let schedules = sqlx::query_as!(
+        FeeSchedule,
+        r#"
+        SELECT id, bank_id, fee_name, fee_type as "fee_type: _", transaction_type as "transaction_type: _", account_type as "account_type: _", calculation as "calculation: _", amount, rate_bps, min_amount, max_amount, enabled, inserted_at, updated_at
+        FROM fee_schedules
+        WHERE bank_id = $1
+        ORDER BY fee_type, fee_name
+        "#,
+        bank_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(schedules)
+}
+
+pub async fn create_schedule(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    settings: FeeScheduleSettings,
+) -> Result<FeeSchedule, CustomerErrorReps> {
+    settings.validate()?;
+
+    let mut transaction = pool.begin().await?;
+
+    let schedule = sqlx::query_as!(
+        FeeSchedule,
+        r#"
+        INSERT INTO fee_schedules (id, bank_id, fee_name, fee_type, transaction_type, account_type, calculation, amount, rate_bps, min_amount, max_amount, enabled, inserted_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        RETURNING id, bank_id, fee_name, fee_type as "fee_type: _", transaction_type as "transaction_type: _", account_type as "account_type: _", calculation as "calculation: _", amount, rate_bps, min_amount, max_amount, enabled, inserted_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        bank_id,
+        settings.fee_name.trim(),
+        settings.fee_type as FeeType,
+        settings.transaction_type as Option<TransactionType>,
+        settings.account_type as Option<AccountType>,
+        settings.calculation as FeeCalculation,
+        settings.amount.unwrap_or(0),
+        settings.rate_bps.unwrap_or(0),
+        settings.min_amount,
+        settings.max_amount,
+        settings.enabled.unwrap_or(true)
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new(
+            "fee_schedule.create",
+            "fee_schedule",
+            schedule.id,
+            Some(bank_id),
+        )
+        .after(&schedule),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(schedule)
+}
+
+pub async fn update_schedule(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    schedule_id: Uuid,
+    settings: FeeScheduleSettings,
+) -> Result<FeeSchedule, CustomerErrorReps> {
+    settings.validate()?;
+
+    let mut transaction = pool.begin().await?;
+
+    let before = sqlx::query_as!(
+        FeeSchedule,
+        r#"
+        SELECT id, bank_id, fee_name, fee_type as "fee_type: _", transaction_type as "transaction_type: _", account_type as "account_type: _", calculation as "calculation: _", amount, rate_bps, min_amount, max_amount, enabled, inserted_at, updated_at
+        FROM fee_schedules
+        WHERE id = $1 AND bank_id = $2
+        FOR UPDATE
+        "#,
+        schedule_id,
+        bank_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    let schedule = sqlx::query_as!(
+        FeeSchedule,
+        r#"
+        UPDATE fee_schedules
+        SET fee_name = $2, fee_type = $3, transaction_type = $4, account_type = $5, calculation = $6, amount = $7, rate_bps = $8, min_amount = $9, max_amount = $10, enabled = $11, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1
+        RETURNING id, bank_id, fee_name, fee_type as "fee_type: _", transaction_type as "transaction_type: _", account_type as "account_type: _", calculation as "calculation: _", amount, rate_bps, min_amount, max_amount, enabled, inserted_at, updated_at
+        "#,
+        schedule_id,
+        settings.fee_name.trim(),
+        settings.fee_type as FeeType,
+        settings.transaction_type as Option<TransactionType>,
+        settings.account_type as Option<AccountType>,
+        settings.calculation as FeeCalculation,
+        settings.amount.unwrap_or(0),
+        settings.rate_bps.unwrap_or(0),
+        settings.min_amount,
+        settings.max_amount,
+        settings.enabled.unwrap_or(true)
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new(
+            "fee_schedule.update",
+            "fee_schedule",
+            schedule.id,
+            Some(bank_id),
+        )
+        .before(&before)
+        .after(&schedule),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(schedule)
+}
+
+// The enabled schedule for the fee, preferring one specific to the account type
+async fn find_schedule(
+    conn: &mut PgConnection,
+    bank_id: Uuid,
+    fee_type: FeeType,
+    transaction_type: Option<TransactionType>,
+    account_type: AccountType,
+) -> Result<Option<FeeSchedule>, sqlx::Error> {
+    sqlx::query_as!(
+        FeeSchedule,
+        r#"
+        SELECT id, bank_id, fee_name, fee_type as "fee_type: _", transaction_type as "transaction_type: _", account_type as "account_type: _", calculation as "calculation: _", amount, rate_bps, min_amount, max_amount, enabled, inserted_at, updated_at
+        FROM fee_schedules
+        WHERE bank_id = $1 AND enabled AND fee_type = $2
+            AND transaction_type IS NOT DISTINCT FROM $3
+            AND (account_type IS NULL OR account_type = $4)
+        ORDER BY account_type IS NULL, inserted_at
+        LIMIT 1
+        "#,
+        bank_id,
+        fee_type as FeeType,
+        transaction_type as Option<TransactionType>,
+        account_type as AccountType
+    )
+    .fetch_optional(conn)
+    .await
+}
+
+// What the posted transaction costs the account holder. Card cash withdrawals are ATM
+// withdrawals. Outgoing transfers of a bank without a transfer fee schedule cost the
+// bank's flat `fee`.
+async fn assess(
+    conn: &mut PgConnection,
+    account: &FeeAccount,
+    posted: &Transaction,
+) -> Result<Option<AssessedFee>, sqlx::Error> {
+    let (fee_type, transaction_type) = match posted.transaction_type {
+        TransactionType::CashWithdrawal if posted.card_number.is_some() => {
+            (FeeType::AtmWithdrawal, None)
+        }
+        ref transaction_type if CHARGEABLE_TRANSACTION_TYPES.contains(transaction_type) => {
+            (FeeType::Transaction, Some(transaction_type.clone()))
+        }
+        _ => return Ok(None),
+    };
+
+    let schedule = find_schedule(
+        &mut *conn,
+        account.bank_id,
+        fee_type,
+        transaction_type.clone(),
+        account.account_type.clone(),
+    )
+    .await?;
+
+    let assessed = match schedule {
+        Some(schedule) => AssessedFee {
+            fee_schedule_id: Some(schedule.id),
+            fee_type,
+            amount: schedule.fee_for(posted.amount),
+        },
+        None if transaction_type == Some(TransactionType::P2P) => AssessedFee {
+            fee_schedule_id: None,
+            fee_type,
+            amount: sqlx::query_scalar!("SELECT fee FROM banks WHERE id = $1", account.bank_id)
+                .fetch_one(&mut *conn)
+                .await?,
+        },
+        None => return Ok(None),
+    };
+
+    Ok(Some(assessed).filter(|assessed| assessed.amount > 0))
+}
+
+async fn active_waiver(
+    conn: &mut PgConnection,
+    account_id: Uuid,
+    fee_type: FeeType,
+    on: NaiveDate,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT id FROM fee_waivers
+        WHERE account_id = $1 AND revoked_at IS NULL
+            AND (fee_type IS NULL OR fee_type = $2)
+            AND (valid_until IS NULL OR valid_until >= $3)
+        ORDER BY inserted_at
+        LIMIT 1
+        "#,
+        account_id,
+        fee_type as FeeType,
+        on
+    )
+    .fetch_optional(conn)
+    .await
+}
+
+// Debits the fee as its own transaction, or records it as waived. Fails with
+// `InsufficientFunds` when the balance and arranged overdraft do not cover it.
+async fn post_fee(
+    conn: &mut PgConnection,
+    audit: &AuditContext,
+    calendar: &BusinessCalendar,
+    account: &FeeAccount,
+    fee: AssessedFee,
+    source_transaction_id: Option<Uuid>,
+    period_start: Option<NaiveDate>,
+) -> Result<FeeCharge, CustomerErrorReps> {
+    let today = chrono::Utc::now().naive_utc().date();
+    let waiver_id = active_waiver(&mut *conn, account.id, fee.fee_type, today).await?;
+
+    let fee_transaction_id = match waiver_id {
+        Some(_) => None,
+        None => {
+            // An unarranged overdraft fee is owed because the account is already past its limit
+            if fee.fee_type != FeeType::UnarrangedOverdraft {
+                accounts::ensure_funds(account.balance, account.overdraft_limit, fee.amount)?;
+            }
+
+            sqlx::query!(
+                r#"
+                UPDATE accounts
+                SET balance = balance - $1, last_updated_date = CURRENT_DATE, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $2
+                "#,
+                fee.amount,
+                account.id
+            )
+            .execute(&mut *conn)
+            .await?;
+
+            let posted = transactions::insert_transaction(
+                &mut *conn,
+                calendar,
+                account.branch_id,
+                account.bank_id,
+                &account.account_number,
+                None,
+                TransactionType::Fee,
+                EntryDirection::Debit,
+                fee.amount,
+                Status::Approved,
+            )
+            .await?;
+            Some(posted.id)
+        }
+    };
+    let status = match waiver_id {
+        Some(_) => FeeStatus::Waived,
+        None => FeeStatus::Charged,
+    };
+
+    let charge = sqlx::query_as!(
+        FeeCharge,
+        r#"
+        INSERT INTO fee_charges (id, bank_id, account_id, fee_schedule_id, fee_type, amount, status, source_transaction_id, fee_transaction_id, fee_waiver_id, period_start, inserted_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        RETURNING id, bank_id, account_id, fee_schedule_id, fee_type as "fee_type: _", amount, status as "status: _", source_transaction_id, fee_transaction_id, reversal_transaction_id, fee_waiver_id, period_start, reversal_reason, inserted_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        account.bank_id,
+        account.id,
+        fee.fee_schedule_id,
+        fee.fee_type as FeeType,
+        fee.amount,
+        status as FeeStatus,
+        source_transaction_id,
+        fee_transaction_id,
+        waiver_id,
+        period_start
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let action = match status {
+        FeeStatus::Waived => "fee.waive",
+        _ => "fee.charge",
+    };
+    audit::record(
+        conn,
+        audit,
+        Change::new(action, "fee_charge", charge.id, Some(charge.bank_id)).after(&charge),
+    )
+    .await?;
+
+    Ok(charge)
+}
+
+// Charges the fee, if any, on a transaction the caller has just posted, on the caller's
+// transaction. The fee must be covered by what the posting left of the balance and
+// arranged overdraft.
+pub async fn charge_transaction_fee(
+    conn: &mut PgConnection,
+    audit: &AuditContext,
+    calendar: &BusinessCalendar,
+    posted: &Transaction,
+) -> Result<Option<FeeCharge>, CustomerErrorReps> {
+    let account = sqlx::query_as!(
+        FeeAccount,
+        r#"
+        SELECT id, bank_id, branch_id, account_number, account_type as "account_type: _", balance, overdraft_limit
+        FROM accounts
+        WHERE account_number = $1
+        FOR UPDATE
+        "#,
+        posted.account_number
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    match assess(&mut *conn, &account, posted).await? {
+        Some(fee) => Ok(Some(
+            post_fee(conn, audit, calendar, &account, fee, Some(posted.id), None).await?,
+        )),
+        None => Ok(None),
+    }
+}
+
+// Charges the monthly maintenance fee for the month of `as_of` to every open account not
+// yet charged for it. Accounts that cannot cover the fee are tried again the next day.
+pub async fn charge_maintenance_fees(
+    pool: &PgPool,
+    bank_id: Option<Uuid>,
+    as_of: NaiveDate,
+) -> Result<u64, CustomerErrorReps> {
+    let period_start = as_of
+        .with_day(1)
+        .expect("the first of the month is a valid date");
+
+    let account_ids = sqlx::query_scalar!(
+        r#"
+        SELECT a.id FROM accounts AS a
+        WHERE a.status <> 'closed' AND ($1::uuid IS NULL OR a.bank_id = $1)
+            AND EXISTS (
+                SELECT 1 FROM fee_schedules AS s
+                WHERE s.bank_id = a.bank_id AND s.enabled AND s.fee_type = 'monthly_maintenance'
+            )
+            AND NOT EXISTS (
+                SELECT 1 FROM fee_charges AS c
+                WHERE c.account_id = a.id AND c.fee_type = 'monthly_maintenance' AND c.period_start = $2
+            )
+        "#,
+        bank_id,
+        period_start
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let audit = AuditContext::system("maintenance_fees");
+    let mut calendars = Calendars::default();
+    let mut charged = 0;
+    for account_id in account_ids {
+        let mut transaction = pool.begin().await?;
+
+        let account = sqlx::query_as!(
+            FeeAccount,
+            r#"
+            SELECT id, bank_id, branch_id, account_number, account_type as "account_type: _", balance, overdraft_limit
+            FROM accounts
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            account_id
+        )
+        .fetch_one(&mut transaction)
+        .await?;
+
+        let schedule = find_schedule(
+            &mut transaction,
+            account.bank_id,
+            FeeType::MonthlyMaintenance,
+            None,
+            account.account_type.clone(),
+        )
+        .await?;
+        let fee = match schedule {
+            Some(schedule) => AssessedFee {
+                fee_schedule_id: Some(schedule.id),
+                fee_type: FeeType::MonthlyMaintenance,
+                amount: schedule.fee_for(account.balance),
+            },
+            None => continue,
+        };
+        if fee.amount <= 0 {
+            continue;
+        }
+
+        let calendar = calendars.get(&mut transaction, account.bank_id).await?;
+        match post_fee(
+            &mut transaction,
+            &audit,
+            calendar,
+            &account,
+            fee,
+            None,
+            Some(period_start),
+        )
+        .await
+        {
+            Ok(_) => {}
+            Err(CustomerErrorReps::InsufficientFunds) => {
+                tracing::warn!(
+                    "account {} cannot cover its maintenance fee for {}",
+                    account.id,
+                    period_start
+                );
+                continue;
+            }
+            Err(err) => return Err(err),
+        }
+
+        transaction.commit().await?;
+        charged += 1;
+    }
+
+    Ok(charged)
+}
+
+// Charges the unarranged overdraft fee for `as_of` when `balance`, the account's balance at
+// the end of that day, is overdrawn past its arranged limit, at most once a day. Percentage
+// fees apply to the unarranged part.
+pub async fn charge_unarranged_overdraft_fee(
+    conn: &mut PgConnection,
+    audit: &AuditContext,
+    calendar: &BusinessCalendar,
+    account_id: Uuid,
+    balance: i32,
+    as_of: NaiveDate,
+) -> Result<Option<FeeCharge>, CustomerErrorReps> {
+    let account = sqlx::query_as!(
+        FeeAccount,
+        r#"
+        SELECT id, bank_id, branch_id, account_number, account_type as "account_type: _", balance, overdraft_limit
+        FROM accounts
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        account_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let unarranged = -(balance + account.overdraft_limit);
+    if unarranged <= 0 {
+        return Ok(None);
+    }
+
+    let charged = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM fee_charges WHERE account_id = $1 AND fee_type = $2 AND period_start = $3
+        ) AS "exists!"
+        "#,
+        account.id,
+        FeeType::UnarrangedOverdraft as FeeType,
+        as_of
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if charged {
+        return Ok(None);
+    }
+
+    let schedule = find_schedule(
+        &mut *conn,
+        account.bank_id,
+        FeeType::UnarrangedOverdraft,
+        None,
+        account.account_type.clone(),
+    )
+    .await?;
+    let fee = match schedule {
+        Some(schedule) => AssessedFee {
+            fee_schedule_id: Some(schedule.id),
+            fee_type: FeeType::UnarrangedOverdraft,
+            amount: schedule.fee_for(unarranged),
+        },
+        None => return Ok(None),
+    };
+    if fee.amount <= 0 {
+        return Ok(None);
+    }
+
+    let charge = post_fee(conn, audit, calendar, &account, fee, None, Some(as_of)).await?;
+    Ok(Some(charge))
+}
+
+pub async fn get_charges(pool: &PgPool, account_id: Uuid) -> Result<Vec<FeeCharge>, sqlx::Error> {
+    let charges = sqlx::query_as!(
+        FeeCharge,
+        r#"
+        SELECT id, bank_id, account_id, fee_schedule_id, fee_type as "fee_type: _", amount, status as "status: _", source_transaction_id, fee_transaction_id, reversal_transaction_id, fee_waiver_id, period_start, reversal_reason, inserted_at, updated_at
+        FROM fee_charges
+        WHERE account_id = $1
+        ORDER BY inserted_at DESC
+        "#,
+        account_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(charges)
+}
+
+// Credits a charged fee back to the account as a fee reversal transaction
+pub async fn reverse_fee(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    fee_id: Uuid,
+    reason: String,
+) -> Result<FeeCharge, CustomerErrorReps> {
+    if reason.trim().is_empty() {
+        return Err(CustomerErrorReps::InvalidInput(
+            "A reason is required to reverse a fee.".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    let before = sqlx::query_as!(
+        FeeCharge,
+        r#"
+        SELECT id, bank_id, account_id, fee_schedule_id, fee_type as "fee_type: _", amount, status as "status: _", source_transaction_id, fee_transaction_id, reversal_transaction_id, fee_waiver_id, period_start, reversal_reason, inserted_at, updated_at
+        FROM fee_charges
+        WHERE id = $1 AND bank_id = $2
+        FOR UPDATE
+        "#,
+        fee_id,
+        bank_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    if before.status != FeeStatus::Charged {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Only charged fees can be reversed.".to_string(),
+        ));
+    }
+
+    let account = sqlx::query!(
+        r#"
+        UPDATE accounts
+        SET balance = balance + $1, last_updated_date = CURRENT_DATE, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $2
+        RETURNING account_number, branch_id, status as "status: AccountStatus"
+        "#,
+        before.amount,
+        before.account_id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+    accounts::ensure_can_credit(account.status)?;
+
+    let calendar = calendar::load(&mut transaction, bank_id).await?;
+    let reversal = transactions::insert_transaction(
+        &mut transaction,
+        &calendar,
+        account.branch_id,
+        bank_id,
+        &account.account_number,
+        None,
+        TransactionType::FeeReversal,
+        EntryDirection::Credit,
+        before.amount,
+        Status::Approved,
+    )
+    .await?;
+
+    let charge = sqlx::query_as!(
+        FeeCharge,
+        r#"
+        UPDATE fee_charges
+        SET status = 'reversed', reversal_transaction_id = $2, reversal_reason = $3, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1
+        RETURNING id, bank_id, account_id, fee_schedule_id, fee_type as "fee_type: _", amount, status as "status: _", source_transaction_id, fee_transaction_id, reversal_transaction_id, fee_waiver_id, period_start, reversal_reason, inserted_at, updated_at
+        "#,
+        fee_id,
+        reversal.id,
+        reason.trim()
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("fee.reverse", "fee_charge", charge.id, Some(bank_id))
+            .before(&before)
+            .after(&charge),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(charge)
+}
+
+pub async fn get_waivers(
+    pool: &PgPool,
+    bank_id: Uuid,
+    account_id: Uuid,
+) -> Result<Vec<FeeWaiver>, sqlx::Error> {
+    let waivers = sqlx::query_as!(
+        FeeWaiver,
+        r#"
+        SELECT id, bank_id, account_id, fee_type as "fee_type: _", reason, valid_until, revoked_at, inserted_at, updated_at
+        FROM fee_waivers
+        WHERE bank_id = $1 AND account_id = $2
+        ORDER BY inserted_at DESC
+        "#,
+        bank_id,
+        account_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(waivers)
+}
+
+// Waives fees of `fee_type` (every fee when `None`) on the account until `valid_until`
+// (inclusive) or until revoked
+pub async fn grant_waiver(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    account_id: Uuid,
+    fee_type: Option<FeeType>,
+    reason: String,
+    valid_until: Option<NaiveDate>,
+) -> Result<FeeWaiver, CustomerErrorReps> {
+    if reason.trim().is_empty() {
+        return Err(CustomerErrorReps::InvalidInput(
+            "A reason is required to waive fees.".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    let account_bank_id =
+        sqlx::query_scalar!("SELECT bank_id FROM accounts WHERE id = $1", account_id)
+            .fetch_optional(&mut transaction)
+            .await?;
+    if account_bank_id != Some(bank_id) {
+        return Err(CustomerErrorReps::NotFound);
+    }
+
+    let waiver = sqlx::query_as!(
+        FeeWaiver,
+        r#"
+        INSERT INTO fee_waivers (id, bank_id, account_id, fee_type, reason, valid_until, inserted_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        RETURNING id, bank_id, account_id, fee_type as "fee_type: _", reason, valid_until, revoked_at, inserted_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        bank_id,
+        account_id,
+        fee_type as Option<FeeType>,
+        reason.trim(),
+        valid_until
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("fee_waiver.grant", "fee_waiver", waiver.id, Some(bank_id)).after(&waiver),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(waiver)
+}
+
+pub async fn revoke_waiver(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    waiver_id: Uuid,
+) -> Result<FeeWaiver, CustomerErrorReps> {
+    let mut transaction = pool.begin().await?;
+
+    let waiver = sqlx::query_as!(
+        FeeWaiver,
+        r#"
+        UPDATE fee_waivers
+        SET revoked_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND bank_id = $2 AND revoked_at IS NULL
+        RETURNING id, bank_id, account_id, fee_type as "fee_type: _", reason, valid_until, revoked_at, inserted_at, updated_at
+        "#,
+        waiver_id,
+        bank_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("fee_waiver.revoke", "fee_waiver", waiver.id, Some(bank_id)).after(&waiver),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(waiver)
+}
@@ -0,0 +1,835 @@
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{postgres::PgExecutor, types::Json, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::bank::helper::validation::CustomerErrorReps;
+
+use super::{
+    audit::{self, AuditContext, Change},
+    credit,
+    mandates,
+    teller,
+    transfer,
+    types::{FraudDecision, Status},
+};
+
+// The parameters of a rule, stored as JSON in `fraud_rules.condition`, e.g.
+// `{"rule_type": "velocity", "max_count": 5, "window_minutes": 10}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "rule_type", rename_all = "snake_case")]
+pub enum RuleCondition {
+    // More than `max_count` operations on the same card (or account) within the window
+    Velocity { max_count: i64, window_minutes: i64 },
+    // A single operation of at least `amount`
+    AmountThreshold { amount: i32 },
+    // At least `amount` to a beneficiary added less than `max_age_hours` ago
+    NewBeneficiary { max_age_hours: i64, amount: i32 },
+    // A country outside `allowed_countries` (when non-empty), or a different country
+    // from the card's previous use within `window_minutes`. This and MerchantMismatch
+    // apply to card operations at a merchant, and fire when the merchant reports nothing.
+    GeoMismatch {
+        allowed_countries: Vec<String>,
+        window_minutes: Option<i64>,
+    },
+    // A merchant category code the bank considers out of pattern for its cardholders
+    MerchantMismatch { merchant_categories: Vec<String> },
+    // `min_count` or more operations on the account within the window that each fall
+    // within `margin` below `reporting_limit`
+    Structuring {
+        reporting_limit: i32,
+        margin: i32,
+        min_count: i64,
+        window_hours: i64,
+    },
+}
+
+impl RuleCondition {
+    pub fn validate(&self) -> Result<(), CustomerErrorReps> {
+        let valid = match self {
+            RuleCondition::Velocity {
+                max_count,
+                window_minutes,
+            } => *max_count > 0 && *window_minutes > 0,
+            RuleCondition::AmountThreshold { amount } => *amount > 0,
+            RuleCondition::NewBeneficiary {
+                max_age_hours,
+                amount,
+            } => *max_age_hours > 0 && *amount > 0,
+            RuleCondition::GeoMismatch {
+                allowed_countries,
+                window_minutes,
+            } => {
+                (!allowed_countries.is_empty() || window_minutes.is_some())
+                    && allowed_countries.iter().all(|country| country.len() == 2)
+                    && window_minutes.map_or(true, |minutes| minutes > 0)
+            }
+            RuleCondition::MerchantMismatch {
+                merchant_categories,
+            } => !merchant_categories.is_empty(),
+            RuleCondition::Structuring {
+                reporting_limit,
+                margin,
+                min_count,
+                window_hours,
+            } => {
+                *reporting_limit > 0
+                    && *margin > 0
+                    && *margin < *reporting_limit
+                    && *min_count > 1
+                    && *window_hours > 0
+            }
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(CustomerErrorReps::InvalidInput(
+                "Invalid rule parameters.".to_string(),
+            ))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct FraudRule {
+    pub id: Uuid,
+    pub bank_id: Uuid,
+    pub rule_name: String,
+    pub condition: Json<RuleCondition>,
+    pub action: FraudDecision,
+    pub enabled: bool,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// The money movement being screened, kept so an approved review can carry it out
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScreenedOperation {
+    Transfer {
+        sender_account_number: String,
+        sender_card_number: Option<String>,
+        beneficiary_account_number: String,
+        amount: i32,
+    },
+    CardCharge {
+        card_number: String,
+        amount: i32,
+    },
+    // A debit card payment taken by a merchant
+    CardPayment {
+        card_number: String,
+        amount: i32,
+    },
+    CashWithdrawal {
+        branch_id: Uuid,
+        account_number: String,
+        card_number: Option<String>,
+        amount: i32,
+    },
+    DirectDebit {
+        mandate_id: Uuid,
+        debtor_account_number: String,
+        amount: i32,
+    },
+}
+
+// Where a card operation happens, from the acquiring client's merchant profile
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScreeningContext {
+    pub country: Option<String>,
+    pub merchant_category: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct FraudEvaluation {
+    pub id: Uuid,
+    pub bank_id: Uuid,
+    pub account_number: String,
+    pub card_number: Option<String>,
+    pub beneficiary_account_number: Option<String>,
+    pub amount: i32,
+    pub country: Option<String>,
+    pub merchant_category: Option<String>,
+    pub operation: Json<ScreenedOperation>,
+    pub decision: FraudDecision,
+    pub reasons: Vec<String>,
+    pub review_status: Option<Status>,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<NaiveDateTime>,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// What the rules look at, gathered once per screening
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreeningFacts {
+    pub amount: i32,
+    pub country: Option<String>,
+    pub merchant_category: Option<String>,
+    // Whether the operation takes place at a merchant, where the geo and merchant rules apply
+    pub at_merchant: bool,
+    pub recent_operations: Vec<PastOperation>,
+    pub beneficiary_added_at: Option<NaiveDateTime>,
+    pub now: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PastOperation {
+    pub card_number: Option<String>,
+    pub amount: i32,
+    pub country: Option<String>,
+    pub inserted_at: NaiveDateTime,
+}
+
+impl ScreenedOperation {
+    fn account_number(&self) -> Option<&str> {
+        match self {
+            ScreenedOperation::Transfer {
+                sender_account_number,
+                ..
+            } => Some(sender_account_number),
+            ScreenedOperation::CashWithdrawal { account_number, .. } => Some(account_number),
+            ScreenedOperation::DirectDebit {
+                debtor_account_number,
+                ..
+            } => Some(debtor_account_number),
+            ScreenedOperation::CardCharge { .. } | ScreenedOperation::CardPayment { .. } => None,
+        }
+    }
+
+    fn card_number(&self) -> Option<&str> {
+        match self {
+            ScreenedOperation::Transfer {
+                sender_card_number, ..
+            } => sender_card_number.as_deref(),
+            ScreenedOperation::CashWithdrawal { card_number, .. } => card_number.as_deref(),
+            ScreenedOperation::CardCharge { card_number, .. }
+            | ScreenedOperation::CardPayment { card_number, .. } => Some(card_number),
+            ScreenedOperation::DirectDebit { .. } => None,
+        }
+    }
+
+    fn beneficiary_account_number(&self) -> Option<&str> {
+        match self {
+            ScreenedOperation::Transfer {
+                beneficiary_account_number,
+                ..
+            } => Some(beneficiary_account_number),
+            _ => None,
+        }
+    }
+
+    fn amount(&self) -> i32 {
+        match self {
+            ScreenedOperation::Transfer { amount, .. }
+            | ScreenedOperation::CardCharge { amount, .. }
+            | ScreenedOperation::CardPayment { amount, .. }
+            | ScreenedOperation::CashWithdrawal { amount, .. }
+            | ScreenedOperation::DirectDebit { amount, .. } => *amount,
+        }
+    }
+
+    fn at_merchant(&self) -> bool {
+        matches!(
+            self,
+            ScreenedOperation::CardCharge { .. } | ScreenedOperation::CardPayment { .. }
+        )
+    }
+}
+
+// Returns the reason a rule fires for these facts, if it does
+fn check_rule(condition: &RuleCondition, card_number: Option<&str>, facts: &ScreeningFacts) -> Option<String> {
+    match condition {
+        RuleCondition::Velocity {
+            max_count,
+            window_minutes,
+        } => {
+            let since = facts.now - Duration::minutes(*window_minutes);
+            let count = facts
+                .recent_operations
+                .iter()
+                .filter(|past| past.inserted_at >= since)
+                .filter(|past| card_number.is_none() || past.card_number.as_deref() == card_number)
+                .count() as i64
+                + 1;
+            (count > *max_count).then(|| {
+                format!("{} operations within {} minutes", count, window_minutes)
+            })
+        }
+        RuleCondition::AmountThreshold { amount } => (facts.amount >= *amount)
+            .then(|| format!("Amount {} at or above {}", facts.amount, amount)),
+        RuleCondition::NewBeneficiary {
+            max_age_hours,
+            amount,
+        } => {
+            let added_recently = facts
+                .beneficiary_added_at
+                .map_or(false, |added_at| added_at >= facts.now - Duration::hours(*max_age_hours));
+            (added_recently && facts.amount >= *amount).then(|| {
+                format!(
+                    "Amount {} to a beneficiary added within {} hours",
+                    facts.amount, max_age_hours
+                )
+            })
+        }
+        RuleCondition::GeoMismatch {
+            allowed_countries,
+            window_minutes,
+        } => {
+            if !facts.at_merchant {
+                return None;
+            }
+            // A merchant that does not report where it is cannot be cleared by the rule
+            let country = match facts.country.as_deref() {
+                Some(country) => country,
+                None => return Some("Merchant country is not reported".to_string()),
+            };
+            if !allowed_countries.is_empty()
+                && !allowed_countries
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(country))
+            {
+                return Some(format!("Country {} is not allowed", country));
+            }
+            let since = facts.now - Duration::minutes((*window_minutes)?);
+            facts
+                .recent_operations
+                .iter()
+                .filter(|past| past.inserted_at >= since)
+                .filter(|past| card_number.is_some() && past.card_number.as_deref() == card_number)
+                .filter_map(|past| past.country.as_deref())
+                .find(|previous| !previous.eq_ignore_ascii_case(country))
+                .map(|previous| {
+                    format!(
+                        "Used in {} and {} within {} minutes",
+                        previous,
+                        country,
+                        window_minutes.unwrap_or_default()
+                    )
+                })
+        }
+        RuleCondition::MerchantMismatch {
+            merchant_categories,
+        } => {
+            if !facts.at_merchant {
+                return None;
+            }
+            let category = match facts.merchant_category.as_deref() {
+                Some(category) => category,
+                None => return Some("Merchant category is not reported".to_string()),
+            };
+            merchant_categories
+                .iter()
+                .any(|flagged| flagged == category)
+                .then(|| format!("Merchant category {} is flagged", category))
+        }
+        RuleCondition::Structuring {
+            reporting_limit,
+            margin,
+            min_count,
+            window_hours,
+        } => {
+            let in_band = |amount: i32| amount < *reporting_limit && amount >= reporting_limit - margin;
+            if !in_band(facts.amount) {
+                return None;
+            }
+            let since = facts.now - Duration::hours(*window_hours);
+            let count = facts
+                .recent_operations
+                .iter()
+                .filter(|past| past.inserted_at >= since && in_band(past.amount))
+                .count() as i64
+                + 1;
+            (count >= *min_count).then(|| {
+                format!(
+                    "{} operations just under the {} reporting limit within {} hours",
+                    count, reporting_limit, window_hours
+                )
+            })
+        }
+    }
+}
+
+// Applies every enabled rule; the most severe action among the rules that fire wins.
+pub fn evaluate(rules: &[FraudRule], card_number: Option<&str>, facts: &ScreeningFacts) -> (FraudDecision, Vec<String>) {
+    let mut decision = FraudDecision::Allow;
+    let mut reasons = Vec::new();
+
+    for rule in rules.iter().filter(|rule| rule.enabled) {
+        if let Some(reason) = check_rule(&rule.condition.0, card_number, facts) {
+            decision = decision.max(rule.action);
+            reasons.push(format!("{}: {}", rule.rule_name, reason));
+        }
+    }
+
+    (decision, reasons)
+}
+
+pub async fn get_rules(
+    executor: impl PgExecutor<'_>,
+    bank_id: Uuid,
+) -> Result<Vec<FraudRule>, sqlx::Error> {
+    let rules = sqlx::query_as!(
+        FraudRule,
+        r#"
+        SELECT id, bank_id, rule_name, condition as "condition: Json<RuleCondition>", action as "action: FraudDecision", enabled, inserted_at, updated_at
+        FROM fraud_rules
+        WHERE bank_id = $1
+        ORDER BY inserted_at
+        "#,
+        bank_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rules)
+}
+
+fn validate_rule(rule_name: &str, condition: &RuleCondition, action: FraudDecision) -> Result<(), CustomerErrorReps> {
+    if rule_name.trim().is_empty() {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Rule name is required.".to_string(),
+        ));
+    }
+    if action == FraudDecision::Allow {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Rule action must be review or deny.".to_string(),
+        ));
+    }
+    condition.validate()
+}
+
+pub async fn create_rule(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    rule_name: String,
+    condition: RuleCondition,
+    action: FraudDecision,
+) -> Result<FraudRule, CustomerErrorReps> {
+    validate_rule(&rule_name, &condition, action)?;
+
+    let mut transaction = pool.begin().await?;
+
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO fraud_rules (id, bank_id, rule_name, condition, action, enabled, inserted_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, TRUE, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        RETURNING id, enabled, inserted_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        bank_id,
+        rule_name,
+        serde_json::to_value(&condition).unwrap_or(Value::Null),
+        action as FraudDecision
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    let rule = FraudRule {
+        id: row.id,
+        bank_id,
+        rule_name,
+        condition: Json(condition),
+        action,
+        enabled: row.enabled,
+        inserted_at: row.inserted_at,
+        updated_at: row.updated_at,
+    };
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("fraud_rule.create", "fraud_rule", rule.id, Some(bank_id)).after(&rule),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(rule)
+}
+
+pub async fn update_rule(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    rule_id: Uuid,
+    rule_name: String,
+    condition: RuleCondition,
+    action: FraudDecision,
+    enabled: bool,
+) -> Result<FraudRule, CustomerErrorReps> {
+    validate_rule(&rule_name, &condition, action)?;
+
+    let mut transaction = pool.begin().await?;
+
+    let before = sqlx::query!(
+        r#"
+        SELECT rule_name, condition, action as "action: FraudDecision", enabled
+        FROM fraud_rules
+        WHERE id = $1 AND bank_id = $2
+        FOR UPDATE
+        "#,
+        rule_id,
+        bank_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    let row = sqlx::query!(
+        r#"
+        UPDATE fraud_rules
+        SET rule_name = $1, condition = $2, action = $3, enabled = $4, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $5
+        RETURNING inserted_at, updated_at
+        "#,
+        rule_name,
+        serde_json::to_value(&condition).unwrap_or(Value::Null),
+        action as FraudDecision,
+        enabled,
+        rule_id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    let rule = FraudRule {
+        id: rule_id,
+        bank_id,
+        rule_name,
+        condition: Json(condition),
+        action,
+        enabled,
+        inserted_at: row.inserted_at,
+        updated_at: row.updated_at,
+    };
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("fraud_rule.update", "fraud_rule", rule.id, Some(bank_id))
+            .before(&serde_json::json!({
+                "rule_name": before.rule_name,
+                "condition": before.condition,
+                "action": before.action,
+                "enabled": before.enabled,
+            }))
+            .after(&rule),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(rule)
+}
+
+// Evaluates the bank's rules against an operation before any money moves and records
+// the outcome. The account is locked first, so concurrent operations on it are counted
+// one after the other. Allowed operations get the open transaction back to post on,
+// keeping the lock until they commit; flagged ones are queued for review and denied
+// ones rejected, both committed and returned as errors the caller passes straight back.
+pub async fn screen<'a>(
+    pool: &'a PgPool,
+    audit: &AuditContext,
+    operation: ScreenedOperation,
+    context: ScreeningContext,
+) -> Result<Transaction<'a, Postgres>, CustomerErrorReps> {
+    let card_number = operation.card_number().map(str::to_string);
+
+    let mut transaction = pool.begin().await?;
+
+    let account = match (operation.account_number(), card_number.as_deref()) {
+        (Some(account_number), _) => sqlx::query!(
+            r#"
+            SELECT id, account_number, bank_id FROM accounts WHERE account_number = $1 FOR UPDATE
+            "#,
+            account_number
+        )
+        .fetch_optional(&mut transaction)
+        .await?
+        .map(|row| (row.id, row.account_number, row.bank_id)),
+        (None, Some(card_number)) => sqlx::query!(
+            r#"
+            SELECT a.id, a.account_number, a.bank_id
+            FROM cards c
+            JOIN accounts a ON a.account_number = c.account_number
+            WHERE c.card_number = $1
+            FOR UPDATE OF a
+            "#,
+            card_number
+        )
+        .fetch_optional(&mut transaction)
+        .await?
+        .map(|row| (row.id, row.account_number, row.bank_id)),
+        (None, None) => None,
+    };
+    let (account_id, account_number, bank_id) = account.ok_or(CustomerErrorReps::NotFound)?;
+
+    // On the screening transaction: it already holds a connection, and waiting on a second
+    // one while holding the account lock would starve the pool under load
+    let rules = get_rules(&mut transaction, bank_id).await?;
+    let now = chrono::Utc::now().naive_utc();
+
+    // The longest look-back any rule needs bounds the history we load
+    let lookback = rules
+        .iter()
+        .filter(|rule| rule.enabled)
+        .map(|rule| match rule.condition.0 {
+            RuleCondition::Velocity { window_minutes, .. } => Duration::minutes(window_minutes),
+            RuleCondition::GeoMismatch {
+                window_minutes: Some(window_minutes),
+                ..
+            } => Duration::minutes(window_minutes),
+            RuleCondition::Structuring { window_hours, .. } => Duration::hours(window_hours),
+            _ => Duration::zero(),
+        })
+        .max()
+        .unwrap_or_else(Duration::zero);
+
+    let recent_operations = if lookback > Duration::zero() {
+        sqlx::query!(
+            r#"
+            SELECT card_number, amount, country, inserted_at
+            FROM fraud_evaluations
+            WHERE account_number = $1 AND inserted_at >= $2 AND decision <> 'deny'
+            "#,
+            account_number,
+            now - lookback
+        )
+        .fetch_all(&mut transaction)
+        .await?
+        .into_iter()
+        .map(|row| PastOperation {
+            card_number: row.card_number,
+            amount: row.amount,
+            country: row.country,
+            inserted_at: row.inserted_at,
+        })
+        .collect()
+    } else {
+        Vec::new()
+    };
+
+    let beneficiary_added_at = match operation.beneficiary_account_number() {
+        Some(beneficiary_account_number) => sqlx::query_scalar!(
+            r#"
+            SELECT MIN(b.inserted_at)
+            FROM beneficiaries AS b
+            INNER JOIN account_holders AS h ON h.customer_id = b.customer_id
+            WHERE h.account_id = $1 AND h.role IN ('primary', 'joint')
+                AND b.beneficiary_account_number = $2
+            "#,
+            account_id,
+            beneficiary_account_number
+        )
+        .fetch_one(&mut transaction)
+        .await?,
+        None => None,
+    };
+
+    let facts = ScreeningFacts {
+        amount: operation.amount(),
+        country: context.country.clone(),
+        merchant_category: context.merchant_category.clone(),
+        at_merchant: operation.at_merchant(),
+        recent_operations,
+        beneficiary_added_at,
+        now,
+    };
+    let (decision, reasons) = evaluate(&rules, card_number.as_deref(), &facts);
+
+    let review_status = (decision == FraudDecision::Review).then_some(Status::Pending);
+
+    let evaluation_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO fraud_evaluations (id, bank_id, account_number, card_number, beneficiary_account_number, amount, country, merchant_category, operation, decision, reasons, review_status, inserted_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        RETURNING id
+        "#,
+        Uuid::new_v4(),
+        bank_id,
+        account_number,
+        card_number,
+        operation.beneficiary_account_number(),
+        operation.amount(),
+        context.country,
+        context.merchant_category,
+        serde_json::to_value(&operation).unwrap_or(Value::Null),
+        decision as FraudDecision,
+        &reasons,
+        review_status as Option<Status>
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    if decision == FraudDecision::Allow {
+        return Ok(transaction);
+    }
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("fraud_evaluation.flag", "fraud_evaluation", evaluation_id, Some(bank_id))
+            .after(&serde_json::json!({
+                "decision": decision,
+                "reasons": reasons,
+                "operation": operation,
+            })),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    match decision {
+        FraudDecision::Review => Err(CustomerErrorReps::HeldForReview(evaluation_id)),
+        _ => Err(CustomerErrorReps::TransactionDeclined(reasons.join("; "))),
+    }
+}
+
+pub async fn get_pending_reviews(pool: &PgPool, bank_id: Uuid) -> Result<Vec<FraudEvaluation>, sqlx::Error> {
+    let reviews = sqlx::query_as!(
+        FraudEvaluation,
+        r#"
+        SELECT id, bank_id, account_number, card_number, beneficiary_account_number, amount, country, merchant_category, operation as "operation: Json<ScreenedOperation>", decision as "decision: FraudDecision", reasons, review_status as "review_status: Status", reviewed_by, reviewed_at, inserted_at, updated_at
+        FROM fraud_evaluations
+        WHERE bank_id = $1 AND review_status = 'pending'
+        ORDER BY inserted_at
+        "#,
+        bank_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(reviews)
+}
+
+// Settles a held operation. Approving carries it out in the same transaction, skipping
+// screening; if it can no longer go through (e.g. the funds are gone) the review stays
+// pending.
+pub async fn resolve_review(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    evaluation_id: Uuid,
+    approve: bool,
+) -> Result<FraudEvaluation, CustomerErrorReps> {
+    let mut transaction = pool.begin().await?;
+
+    let evaluation = sqlx::query_as!(
+        FraudEvaluation,
+        r#"
+        SELECT id, bank_id, account_number, card_number, beneficiary_account_number, amount, country, merchant_category, operation as "operation: Json<ScreenedOperation>", decision as "decision: FraudDecision", reasons, review_status as "review_status: Status", reviewed_by, reviewed_at, inserted_at, updated_at
+        FROM fraud_evaluations
+        WHERE id = $1 AND bank_id = $2
+        FOR UPDATE
+        "#,
+        evaluation_id,
+        bank_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    if evaluation.review_status != Some(Status::Pending) {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Review is not pending.".to_string(),
+        ));
+    }
+
+    if approve {
+        match &*evaluation.operation {
+            ScreenedOperation::Transfer {
+                sender_account_number,
+                sender_card_number,
+                beneficiary_account_number,
+                amount,
+            } => {
+                transfer::transfer_funds(
+                    &mut transaction,
+                    audit,
+                    sender_account_number,
+                    sender_card_number.as_deref(),
+                    beneficiary_account_number,
+                    *amount,
+                )
+                .await?;
+            }
+            ScreenedOperation::CardCharge {
+                card_number,
+                amount,
+            } => {
+                credit::post_charge(&mut transaction, audit, card_number, *amount).await?;
+            }
+            ScreenedOperation::CashWithdrawal {
+                branch_id,
+                account_number,
+                card_number,
+                amount,
+            } => {
+                teller::post_withdrawal(
+                    &mut transaction,
+                    audit,
+                    *branch_id,
+                    account_number,
+                    card_number.as_deref(),
+                    *amount,
+                )
+                .await?;
+            }
+            ScreenedOperation::DirectDebit {
+                mandate_id,
+                amount,
+                ..
+            } => {
+                mandates::post_collection(&mut transaction, audit, *mandate_id, *amount).await?;
+            }
+            // The merchant was declined at the point of sale; approving only clears the flag
+            ScreenedOperation::CardPayment { .. } => {}
+        }
+    }
+
+    let review_status = if approve { Status::Approved } else { Status::Rejected };
+
+    let row = sqlx::query!(
+        r#"
+        UPDATE fraud_evaluations
+        SET review_status = $1, reviewed_by = $2, reviewed_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $3
+        RETURNING reviewed_at, updated_at
+        "#,
+        review_status.clone() as Status,
+        audit.actor_id,
+        evaluation.id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    let resolved = FraudEvaluation {
+        review_status: Some(review_status),
+        reviewed_by: audit.actor_id,
+        reviewed_at: row.reviewed_at,
+        updated_at: row.updated_at,
+        ..evaluation.clone()
+    };
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new(
+            if approve { "fraud_review.approve" } else { "fraud_review.reject" },
+            "fraud_evaluation",
+            evaluation.id,
+            Some(bank_id),
+        )
+        .before(&evaluation)
+        .after(&resolved),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(resolved)
+}
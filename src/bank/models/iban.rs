@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::bank::helper::validation::{mod97, CustomerErrorReps, ACCOUNT_NUMBER_LENGTH};
+
+use super::accounts::Account;
+
+// Used when IBAN_COUNTRY_CODE is not set
+pub const DEFAULT_COUNTRY_CODE: &str = "DE";
+
+// Our IBANs: country code (2) + check digits (2) + bank code (4) + account number (14)
+pub const IBAN_LENGTH: usize = 4 + 4 + ACCOUNT_NUMBER_LENGTH;
+
+// The longest IBAN any country issues
+const MAX_IBAN_LENGTH: usize = 34;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AccountIban {
+    pub account_id: Uuid,
+    pub account_number: String,
+    pub iban: String,
+    pub iban_pretty: String,
+}
+
+pub fn country_code() -> String {
+    std::env::var("IBAN_COUNTRY_CODE")
+        .ok()
+        .map(|code| code.trim().to_uppercase())
+        .filter(|code| code.len() == 2 && code.chars().all(|c| c.is_ascii_uppercase()))
+        .unwrap_or_else(|| DEFAULT_COUNTRY_CODE.to_string())
+}
+
+// Electronic format: no spaces, upper case
+pub fn normalize(input: &str) -> String {
+    input
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+// Whether the input is shaped like an IBAN rather than a plain account number
+pub fn looks_like_iban(input: &str) -> bool {
+    let iban = normalize(input);
+    let bytes = iban.as_bytes();
+    bytes.len() > 4
+        && bytes[..2].iter().all(u8::is_ascii_alphabetic)
+        && bytes[2..4].iter().all(u8::is_ascii_digit)
+}
+
+// Moves the first four characters to the end and replaces letters with 10..35, the form
+// the mod-97 check runs on
+fn numeric_form(iban: &str) -> Option<String> {
+    let (head, tail) = iban.split_at(4);
+    tail.chars()
+        .chain(head.chars())
+        .map(|c| c.to_digit(36).map(|value| value.to_string()))
+        .collect()
+}
+
+pub fn is_valid(input: &str) -> bool {
+    let iban = normalize(input);
+    looks_like_iban(&iban)
+        && iban.len() <= MAX_IBAN_LENGTH
+        && iban.chars().all(|c| c.is_ascii_alphanumeric())
+        && numeric_form(&iban).and_then(|digits| mod97(&digits)) == Some(1)
+}
+
+pub fn build(country_code: &str, bank_code: i32, account_number: &str) -> String {
+    let bban = format!(
+        "{:04}{:0>width$}",
+        bank_code,
+        account_number,
+        width = ACCOUNT_NUMBER_LENGTH
+    );
+    let remainder = numeric_form(&format!("{}00{}", country_code, bban))
+        .and_then(|digits| mod97(&digits))
+        .unwrap_or_default();
+    format!("{}{:02}{}", country_code, 98 - remainder, bban)
+}
+
+// Print format: groups of four separated by spaces
+pub fn pretty(input: &str) -> String {
+    normalize(input)
+        .chars()
+        .collect::<Vec<_>>()
+        .chunks(4)
+        .map(|group| group.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Splits one of our IBANs into bank code and zero-padded account number. `None` for
+// IBANs issued in another country or with another layout.
+pub fn parse(input: &str) -> Option<(i32, String)> {
+    let iban = normalize(input);
+    if iban.len() != IBAN_LENGTH || !iban.starts_with(&country_code()) || !is_valid(&iban) {
+        return None;
+    }
+    let bank_code = iban[4..8].parse().ok()?;
+    Some((bank_code, iban[8..].to_string()))
+}
+
+pub async fn get_account_iban(
+    pool: &PgPool,
+    account_id: Uuid,
+) -> Result<Option<AccountIban>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT a.account_number, k.bank_code
+        FROM accounts AS a
+        INNER JOIN banks AS k ON k.id = a.bank_id
+        WHERE a.id = $1
+        "#,
+        account_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|row| {
+        let iban = build(&country_code(), row.bank_code, &row.account_number);
+        AccountIban {
+            account_id,
+            account_number: row.account_number,
+            iban_pretty: pretty(&iban),
+            iban,
+        }
+    }))
+}
+
+// The account held with us that the IBAN designates. Account numbers issued before
+// check digits were introduced are shorter and zero-padded inside the IBAN; a current
+// number that matches exactly takes precedence.
+pub async fn find_account_by_iban(
+    pool: &PgPool,
+    iban: &str,
+) -> Result<Option<Account>, CustomerErrorReps> {
+    if !is_valid(iban) {
+        return Err(CustomerErrorReps::InvalidInput("Invalid IBAN.".to_string()));
+    }
+    let (bank_code, account_number) = match parse(iban) {
+        Some(parts) => parts,
+        None => return Ok(None),
+    };
+
+    let account = sqlx::query_as!(
+        Account,
+        r#"
+        SELECT a.account_type as "account_type: _", a.branch_id, a.bank_id, a.id, a.account_number, a.balance, a.opened_date, a.last_updated_date, a.inserted_at, a.updated_at, a.status as "status: _", a.status_reason, a.last_activity_at, a.closed_at, a.overdraft_limit, a.overdraft_rate_bps
+        FROM accounts AS a
+        INNER JOIN banks AS k ON k.id = a.bank_id
+        WHERE k.bank_code = $1 AND LPAD(a.account_number, 14, '0') = $2
+        ORDER BY a.account_number = $2 DESC
+        LIMIT 1
+        "#,
+        bank_code,
+        account_number
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(account)
+}
+
+// Accepts an account number or an IBAN wherever a counterparty account is entered.
+// IBANs of accounts held with us resolve to their account number, other valid IBANs are
+// kept in electronic format, and anything else is returned unchanged.
+pub async fn resolve_account_number(
+    pool: &PgPool,
+    input: &str,
+) -> Result<String, CustomerErrorReps> {
+    if !looks_like_iban(input) {
+        return Ok(input.trim().to_string());
+    }
+
+    match find_account_by_iban(pool, input).await? {
+        Some(account) => Ok(account.account_number),
+        None => Ok(normalize(input)),
+    }
+}
@@ -0,0 +1,782 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::bank::helper::validation::CustomerErrorReps;
+
+use super::{
+    accounts::{self, Account},
+    iban,
+    payment_batches::{
+        NewPaymentBatch, NewPaymentItem, PaymentBatch, PaymentBatchItem, ReasonCode,
+    },
+    transactions::Transaction,
+    types::{BatchExecutionMode, EntryDirection, PaymentBatchStatus, PaymentItemStatus},
+};
+
+// Used when CURRENCY is not set; every account is held in this currency
+pub const DEFAULT_CURRENCY: &str = "EUR";
+
+// Amounts are stored in minor units and exchanged with two decimals
+const MINOR_UNITS: i64 = 100;
+
+const XML_DECLARATION: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+const PAIN_001_MESSAGE_NAME: &str = "pain.001.001.03";
+const PAIN_002_NAMESPACE: &str = "urn:iso:std:iso:20022:tech:xsd:pain.002.001.03";
+const CAMT_053_NAMESPACE: &str = "urn:iso:std:iso:20022:tech:xsd:camt.053.001.02";
+
+pub fn currency() -> String {
+    std::env::var("CURRENCY")
+        .ok()
+        .map(|code| code.trim().to_uppercase())
+        .filter(|code| code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase()))
+        .unwrap_or_else(|| DEFAULT_CURRENCY.to_string())
+}
+
+// "1234.5" -> 123450. At most two decimals; `None` when malformed or out of range.
+pub fn parse_amount(text: &str) -> Option<i32> {
+    let (units, decimals) = match text.trim().split_once('.') {
+        Some((units, decimals)) => (units, decimals),
+        None => (text.trim(), ""),
+    };
+    if units.is_empty()
+        || decimals.len() > 2
+        || !units
+            .chars()
+            .chain(decimals.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let units: i64 = units.parse().ok()?;
+    let decimals: i64 = format!("{:0<2}", decimals).parse().ok()?;
+    i32::try_from(units.checked_mul(MINOR_UNITS)?.checked_add(decimals)?).ok()
+}
+
+pub fn format_amount(amount: i64) -> String {
+    let amount = amount.abs();
+    format!("{}.{:02}", amount / MINOR_UNITS, amount % MINOR_UNITS)
+}
+
+fn format_date_time(date_time: NaiveDateTime) -> String {
+    date_time.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+fn direction_code(direction: EntryDirection) -> &'static str {
+    match direction {
+        EntryDirection::Credit => "CRDT",
+        EntryDirection::Debit => "DBIT",
+    }
+}
+
+// pain.001 customer credit transfer initiation. Only the elements we act on are read;
+// everything else in the message is ignored.
+#[derive(Debug, Deserialize)]
+struct Pain001Document {
+    #[serde(rename = "CstmrCdtTrfInitn")]
+    initiation: CreditTransferInitiation,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreditTransferInitiation {
+    #[serde(rename = "GrpHdr")]
+    group_header: InitiationGroupHeader,
+    #[serde(rename = "PmtInf", default)]
+    payment_information: Vec<PaymentInstruction>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InitiationGroupHeader {
+    #[serde(rename = "MsgId")]
+    message_id: String,
+    #[serde(rename = "NbOfTxs")]
+    number_of_transactions: String,
+    #[serde(rename = "CtrlSum")]
+    control_sum: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaymentInstruction {
+    #[serde(rename = "PmtInfId")]
+    payment_information_id: String,
+    #[serde(rename = "ReqdExctnDt")]
+    requested_execution_date: DateChoice,
+    #[serde(rename = "DbtrAcct")]
+    debtor_account: CashAccount,
+    #[serde(rename = "CdtTrfTxInf", default)]
+    transactions: Vec<CreditTransferTransaction>,
+}
+
+// A bare date up to version 3 of the message, wrapped in <Dt> from version 8
+#[derive(Debug, Deserialize)]
+struct DateChoice {
+    #[serde(rename = "Dt")]
+    date: Option<String>,
+    #[serde(rename = "$text")]
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CashAccount {
+    #[serde(rename = "Id")]
+    id: AccountIdentification,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountIdentification {
+    #[serde(rename = "IBAN")]
+    iban: Option<String>,
+    #[serde(rename = "Othr")]
+    other: Option<GenericIdentification>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GenericIdentification {
+    #[serde(rename = "Id")]
+    id: String,
+}
+
+impl CashAccount {
+    fn account_number(&self) -> Option<String> {
+        self.id
+            .iban
+            .clone()
+            .or_else(|| self.id.other.as_ref().map(|other| other.id.clone()))
+            .map(|number| number.trim().to_string())
+            .filter(|number| !number.is_empty())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CreditTransferTransaction {
+    #[serde(rename = "PmtId")]
+    payment_id: PaymentIdentification,
+    #[serde(rename = "Amt")]
+    amount: AmountChoice,
+    #[serde(rename = "Cdtr")]
+    creditor: Option<PartyIdentification>,
+    #[serde(rename = "CdtrAcct")]
+    creditor_account: Option<CashAccount>,
+    #[serde(rename = "RmtInf")]
+    remittance_information: Option<RemittanceInformation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaymentIdentification {
+    #[serde(rename = "EndToEndId")]
+    end_to_end_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AmountChoice {
+    #[serde(rename = "InstdAmt")]
+    instructed_amount: InstructedAmount,
+}
+
+#[derive(Debug, Deserialize)]
+struct InstructedAmount {
+    #[serde(rename = "@Ccy")]
+    currency: String,
+    #[serde(rename = "$text")]
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PartyIdentification {
+    #[serde(rename = "Nm")]
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RemittanceInformation {
+    #[serde(rename = "Ustrd", default)]
+    unstructured: Vec<String>,
+}
+
+impl CreditTransferTransaction {
+    fn into_item(self, currency: &str) -> NewPaymentItem {
+        let amount = parse_amount(&self.amount.instructed_amount.value);
+        let creditor_account_number = self
+            .creditor_account
+            .as_ref()
+            .and_then(CashAccount::account_number);
+
+        let rejection = if self.amount.instructed_amount.currency != currency {
+            Some((
+                ReasonCode::AM03,
+                format!("Only {} payments are accepted.", currency),
+            ))
+        } else if amount.is_none() {
+            Some((ReasonCode::AM12, "Invalid amount.".to_string()))
+        } else if creditor_account_number.is_none() {
+            Some((
+                ReasonCode::AC01,
+                "Creditor account is required.".to_string(),
+            ))
+        } else {
+            None
+        };
+
+        // Unstructured remittance information is limited to 140 characters
+        let remittance_information = self
+            .remittance_information
+            .map(|information| information.unstructured.join(" "))
+            .map(|text| text.chars().take(140).collect::<String>())
+            .filter(|text| !text.trim().is_empty());
+
+        NewPaymentItem {
+            end_to_end_id: self.payment_id.end_to_end_id.trim().to_string(),
+            creditor_name: self
+                .creditor
+                .and_then(|creditor| creditor.name)
+                .unwrap_or_default(),
+            creditor_account_number: creditor_account_number.unwrap_or_default(),
+            amount: amount.unwrap_or_default(),
+            remittance_information,
+            rejection,
+        }
+    }
+}
+
+// Reads a pain.001 message into one batch per payment information block. The group
+// header's transaction count and control sum have to agree with the payments listed.
+pub fn parse_pain001(xml: &str) -> Result<Vec<NewPaymentBatch>, CustomerErrorReps> {
+    let document: Pain001Document = quick_xml::de::from_str(xml).map_err(|err| {
+        CustomerErrorReps::InvalidInput(format!("Malformed pain.001 message: {}", err))
+    })?;
+    let initiation = document.initiation;
+    let group_header = initiation.group_header;
+
+    let transaction_count: usize = initiation
+        .payment_information
+        .iter()
+        .map(|instruction| instruction.transactions.len())
+        .sum();
+    if group_header
+        .number_of_transactions
+        .trim()
+        .parse::<usize>()
+        .ok()
+        != Some(transaction_count)
+    {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Number of transactions does not match the group header.".to_string(),
+        ));
+    }
+
+    if let Some(control_sum) = group_header.control_sum {
+        let expected = parse_amount(&control_sum).map(i64::from);
+        let actual = initiation
+            .payment_information
+            .iter()
+            .flat_map(|instruction| instruction.transactions.iter())
+            .map(|transaction| parse_amount(&transaction.amount.instructed_amount.value))
+            .try_fold(0i64, |sum, amount| amount.map(|amount| sum + amount as i64));
+        if expected.is_none() || expected != actual {
+            return Err(CustomerErrorReps::InvalidInput(
+                "Control sum does not match the group header.".to_string(),
+            ));
+        }
+    }
+
+    let currency = currency();
+    let message_id = group_header.message_id.trim().to_string();
+
+    initiation
+        .payment_information
+        .into_iter()
+        .map(|instruction| {
+            let date = instruction
+                .requested_execution_date
+                .date
+                .or(instruction.requested_execution_date.text)
+                .unwrap_or_default();
+            let requested_execution_date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+                .map_err(|_| {
+                    CustomerErrorReps::InvalidInput(format!(
+                        "Invalid requested execution date: {}",
+                        date.trim()
+                    ))
+                })?;
+            let debtor_account_number =
+                instruction.debtor_account.account_number().ok_or_else(|| {
+                    CustomerErrorReps::InvalidInput("Debtor account is required.".to_string())
+                })?;
+
+            Ok(NewPaymentBatch {
+                debtor_account_number,
+                execution_mode: BatchExecutionMode::BestEffort,
+                message_id: message_id.clone(),
+                payment_information_id: instruction.payment_information_id.trim().to_string(),
+                requested_execution_date,
+                items: instruction
+                    .transactions
+                    .into_iter()
+                    .map(|transaction| transaction.into_item(&currency))
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+// pain.002 customer payment status report
+#[derive(Debug, Serialize)]
+struct Pain002Document {
+    #[serde(rename = "@xmlns")]
+    namespace: &'static str,
+    #[serde(rename = "CstmrPmtStsRpt")]
+    report: PaymentStatusReport,
+}
+
+#[derive(Debug, Serialize)]
+struct PaymentStatusReport {
+    #[serde(rename = "GrpHdr")]
+    group_header: GroupHeader,
+    #[serde(rename = "OrgnlGrpInfAndSts")]
+    original_group: OriginalGroupStatus,
+    #[serde(rename = "OrgnlPmtInfAndSts")]
+    original_payment_information: OriginalPaymentInformationStatus,
+}
+
+#[derive(Debug, Serialize)]
+struct GroupHeader {
+    #[serde(rename = "MsgId")]
+    message_id: String,
+    #[serde(rename = "CreDtTm")]
+    created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+struct OriginalGroupStatus {
+    #[serde(rename = "OrgnlMsgId")]
+    original_message_id: String,
+    #[serde(rename = "OrgnlMsgNmId")]
+    original_message_name: &'static str,
+    #[serde(rename = "OrgnlNbOfTxs")]
+    original_number_of_transactions: i32,
+    #[serde(rename = "OrgnlCtrlSum")]
+    original_control_sum: String,
+    #[serde(rename = "GrpSts")]
+    group_status: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct OriginalPaymentInformationStatus {
+    #[serde(rename = "OrgnlPmtInfId")]
+    original_payment_information_id: String,
+    #[serde(rename = "PmtInfSts")]
+    payment_information_status: &'static str,
+    #[serde(rename = "TxInfAndSts")]
+    transactions: Vec<TransactionStatus>,
+}
+
+#[derive(Debug, Serialize)]
+struct TransactionStatus {
+    #[serde(rename = "OrgnlEndToEndId")]
+    original_end_to_end_id: String,
+    #[serde(rename = "TxSts")]
+    status: &'static str,
+    #[serde(rename = "StsRsnInf", skip_serializing_if = "Option::is_none")]
+    reason: Option<StatusReason>,
+}
+
+#[derive(Debug, Serialize)]
+struct StatusReason {
+    #[serde(rename = "Rsn", skip_serializing_if = "Option::is_none")]
+    code: Option<ReasonCodeElement>,
+    #[serde(rename = "AddtlInf", skip_serializing_if = "Option::is_none")]
+    additional_information: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ReasonCodeElement {
+    #[serde(rename = "Cd")]
+    code: String,
+}
+
+fn batch_status_code(batch: &PaymentBatch, items: &[PaymentBatchItem]) -> &'static str {
+    let rejected = items
+        .iter()
+        .filter(|item| item.status == PaymentItemStatus::Rejected)
+        .count();
+    match batch.status {
+        PaymentBatchStatus::Completed => "ACSC",
+        PaymentBatchStatus::PartiallyCompleted => "PART",
+        PaymentBatchStatus::Rejected => "RJCT",
+        PaymentBatchStatus::Accepted | PaymentBatchStatus::Processing => {
+            if rejected == items.len() {
+                "RJCT"
+            } else if rejected > 0 {
+                "PART"
+            } else {
+                "ACTC"
+            }
+        }
+    }
+}
+
+fn item_status_code(status: PaymentItemStatus) -> &'static str {
+    match status {
+        PaymentItemStatus::Pending => "ACCP",
+        PaymentItemStatus::Held => "PDNG",
+        PaymentItemStatus::Completed => "ACSC",
+        PaymentItemStatus::Rejected | PaymentItemStatus::Failed => "RJCT",
+    }
+}
+
+// Reports the state of the batch as a whole and of every item in it
+pub fn pain002(
+    batch: &PaymentBatch,
+    items: &[PaymentBatchItem],
+    now: NaiveDateTime,
+) -> Result<String, CustomerErrorReps> {
+    let status = batch_status_code(batch, items);
+    let document = Pain002Document {
+        namespace: PAIN_002_NAMESPACE,
+        report: PaymentStatusReport {
+            group_header: GroupHeader {
+                message_id: Uuid::new_v4().simple().to_string(),
+                created_at: format_date_time(now),
+            },
+            original_group: OriginalGroupStatus {
+                original_message_id: batch.message_id.clone(),
+                original_message_name: PAIN_001_MESSAGE_NAME,
+                original_number_of_transactions: batch.item_count,
+                original_control_sum: format_amount(batch.control_sum),
+                group_status: status,
+            },
+            original_payment_information: OriginalPaymentInformationStatus {
+                original_payment_information_id: batch.payment_information_id.clone(),
+                payment_information_status: status,
+                transactions: items
+                    .iter()
+                    .map(|item| TransactionStatus {
+                        original_end_to_end_id: item.end_to_end_id.clone(),
+                        status: item_status_code(item.status),
+                        reason: item.reason.as_ref().map(|reason| StatusReason {
+                            code: item
+                                .reason_code
+                                .clone()
+                                .map(|code| ReasonCodeElement { code }),
+                            additional_information: Some(reason.chars().take(105).collect()),
+                        }),
+                    })
+                    .collect(),
+            },
+        },
+    };
+
+    to_xml(&document)
+}
+
+// camt.053 bank-to-customer statement
+#[derive(Debug, Serialize)]
+struct Camt053Document {
+    #[serde(rename = "@xmlns")]
+    namespace: &'static str,
+    #[serde(rename = "BkToCstmrStmt")]
+    statement_message: BankToCustomerStatement,
+}
+
+#[derive(Debug, Serialize)]
+struct BankToCustomerStatement {
+    #[serde(rename = "GrpHdr")]
+    group_header: GroupHeader,
+    #[serde(rename = "Stmt")]
+    statement: StatementElement,
+}
+
+#[derive(Debug, Serialize)]
+struct StatementElement {
+    #[serde(rename = "Id")]
+    id: String,
+    #[serde(rename = "CreDtTm")]
+    created_at: String,
+    #[serde(rename = "Acct")]
+    account: StatementAccount,
+    #[serde(rename = "Bal")]
+    balances: Vec<Balance>,
+    #[serde(rename = "TxsSummry")]
+    summary: TransactionsSummary,
+    #[serde(rename = "Ntry")]
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Serialize)]
+struct StatementAccount {
+    #[serde(rename = "Id")]
+    id: IbanIdentification,
+    #[serde(rename = "Ccy")]
+    currency: String,
+}
+
+#[derive(Debug, Serialize)]
+struct IbanIdentification {
+    #[serde(rename = "IBAN")]
+    iban: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Balance {
+    #[serde(rename = "Tp")]
+    balance_type: BalanceType,
+    #[serde(rename = "Amt")]
+    amount: Amount,
+    #[serde(rename = "CdtDbtInd")]
+    direction: &'static str,
+    #[serde(rename = "Dt")]
+    date: DateElement,
+}
+
+#[derive(Debug, Serialize)]
+struct BalanceType {
+    #[serde(rename = "CdOrPrtry")]
+    code_or_proprietary: CodeElement,
+}
+
+#[derive(Debug, Serialize)]
+struct CodeElement {
+    #[serde(rename = "Cd")]
+    code: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct Amount {
+    #[serde(rename = "@Ccy")]
+    currency: String,
+    #[serde(rename = "$text")]
+    value: String,
+}
+
+#[derive(Debug, Serialize)]
+struct DateElement {
+    #[serde(rename = "Dt")]
+    date: String,
+}
+
+#[derive(Debug, Serialize)]
+struct DateTimeElement {
+    #[serde(rename = "DtTm")]
+    date_time: String,
+}
+
+#[derive(Debug, Serialize)]
+struct TransactionsSummary {
+    #[serde(rename = "TtlCdtNtries")]
+    total_credit_entries: EntryTotals,
+    #[serde(rename = "TtlDbtNtries")]
+    total_debit_entries: EntryTotals,
+}
+
+#[derive(Debug, Serialize)]
+struct EntryTotals {
+    #[serde(rename = "NbOfNtries")]
+    number_of_entries: usize,
+    #[serde(rename = "Sum")]
+    sum: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Entry {
+    #[serde(rename = "NtryRef")]
+    reference: String,
+    #[serde(rename = "Amt")]
+    amount: Amount,
+    #[serde(rename = "CdtDbtInd")]
+    direction: &'static str,
+    #[serde(rename = "Sts")]
+    status: &'static str,
+    #[serde(rename = "BookgDt")]
+    booking_date: DateTimeElement,
+    #[serde(rename = "ValDt")]
+    value_date: DateElement,
+    #[serde(rename = "AcctSvcrRef")]
+    account_servicer_reference: String,
+    #[serde(rename = "BkTxCd")]
+    bank_transaction_code: BankTransactionCode,
+}
+
+#[derive(Debug, Serialize)]
+struct BankTransactionCode {
+    #[serde(rename = "Prtry")]
+    proprietary: ProprietaryCode,
+}
+
+#[derive(Debug, Serialize)]
+struct ProprietaryCode {
+    #[serde(rename = "Cd")]
+    code: &'static str,
+}
+
+// An account's booked entries for one day together with the balances either side
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountStatement {
+    pub account: Account,
+    pub iban: String,
+    pub date: NaiveDate,
+    pub opening_balance: i64,
+    pub closing_balance: i64,
+    pub entries: Vec<Transaction>,
+}
+
+fn signed(transaction: &Transaction) -> i64 {
+    match transaction.direction {
+        EntryDirection::Credit => transaction.amount as i64,
+        EntryDirection::Debit => -(transaction.amount as i64),
+    }
+}
+
+// Card charges, interest and late fees are posted against the deposit account's number
+// but move the credit account's balance, so they belong on the card statement instead.
+// The closing balance is worked back from the current balance through everything booked
+// after the statement date.
+pub async fn get_account_statement(
+    pool: &PgPool,
+    account_id: Uuid,
+    date: NaiveDate,
+) -> Result<Option<AccountStatement>, sqlx::Error> {
+    let account = match accounts::get_by_id(pool, account_id).await? {
+        Some(account) => account,
+        None => return Ok(None),
+    };
+    let iban = match iban::get_account_iban(pool, account_id).await? {
+        Some(account_iban) => account_iban.iban,
+        None => return Ok(None),
+    };
+
+    let entries = sqlx::query_as!(
+        Transaction,
+        r#"
+        SELECT branch_id, bank_id, id, account_number, transaction_type as "transaction_type: _", direction as "direction: _", card_number, amount, transaction_date, value_date, status as "status: _", inserted_at, updated_at
+        FROM transactions
+        WHERE account_number = $1 AND transaction_date = $2 AND status = 'approved'
+            AND transaction_type NOT IN ('credit_card_charge', 'interest_charge', 'late_fee')
+        ORDER BY inserted_at
+        "#,
+        account.account_number,
+        date
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let booked_since = sqlx::query_scalar!(
+        r#"
+        SELECT COALESCE(SUM(CASE WHEN direction = 'credit' THEN amount ELSE -amount END), 0)::BIGINT AS "total!"
+        FROM transactions
+        WHERE account_number = $1 AND transaction_date > $2 AND status = 'approved'
+            AND transaction_type NOT IN ('credit_card_charge', 'interest_charge', 'late_fee')
+        "#,
+        account.account_number,
+        date
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let closing_balance = account.balance as i64 - booked_since;
+    let opening_balance = closing_balance - entries.iter().map(signed).sum::<i64>();
+
+    Ok(Some(AccountStatement {
+        account,
+        iban,
+        date,
+        opening_balance,
+        closing_balance,
+        entries,
+    }))
+}
+
+fn balance(code: &'static str, amount: i64, currency: &str, date: NaiveDate) -> Balance {
+    Balance {
+        balance_type: BalanceType {
+            code_or_proprietary: CodeElement { code },
+        },
+        amount: Amount {
+            currency: currency.to_string(),
+            value: format_amount(amount),
+        },
+        direction: if amount < 0 { "DBIT" } else { "CRDT" },
+        date: DateElement {
+            date: date.to_string(),
+        },
+    }
+}
+
+fn entry_totals(entries: &[Transaction], direction: EntryDirection) -> EntryTotals {
+    let matching: Vec<&Transaction> = entries
+        .iter()
+        .filter(|entry| entry.direction == direction)
+        .collect();
+    EntryTotals {
+        number_of_entries: matching.len(),
+        sum: format_amount(matching.iter().map(|entry| entry.amount as i64).sum()),
+    }
+}
+
+pub fn camt053(
+    statement: &AccountStatement,
+    now: NaiveDateTime,
+) -> Result<String, CustomerErrorReps> {
+    let currency = currency();
+    let document = Camt053Document {
+        namespace: CAMT_053_NAMESPACE,
+        statement_message: BankToCustomerStatement {
+            group_header: GroupHeader {
+                message_id: Uuid::new_v4().simple().to_string(),
+                created_at: format_date_time(now),
+            },
+            statement: StatementElement {
+                id: format!("{}-{}", statement.account.account_number, statement.date),
+                created_at: format_date_time(now),
+                account: StatementAccount {
+                    id: IbanIdentification {
+                        iban: statement.iban.clone(),
+                    },
+                    currency: currency.clone(),
+                },
+                balances: vec![
+                    balance("OPBD", statement.opening_balance, &currency, statement.date),
+                    balance("CLBD", statement.closing_balance, &currency, statement.date),
+                ],
+                summary: TransactionsSummary {
+                    total_credit_entries: entry_totals(&statement.entries, EntryDirection::Credit),
+                    total_debit_entries: entry_totals(&statement.entries, EntryDirection::Debit),
+                },
+                entries: statement
+                    .entries
+                    .iter()
+                    .map(|entry| Entry {
+                        reference: entry.id.simple().to_string(),
+                        amount: Amount {
+                            currency: currency.clone(),
+                            value: format_amount(entry.amount as i64),
+                        },
+                        direction: direction_code(entry.direction),
+                        status: "BOOK",
+                        booking_date: DateTimeElement {
+                            date_time: format_date_time(entry.inserted_at),
+                        },
+                        value_date: DateElement {
+                            date: entry.value_date.to_string(),
+                        },
+                        account_servicer_reference: entry.id.to_string(),
+                        bank_transaction_code: BankTransactionCode {
+                            proprietary: ProprietaryCode {
+                                code: entry.transaction_type.as_str(),
+                            },
+                        },
+                    })
+                    .collect(),
+            },
+        },
+    };
+
+    to_xml(&document)
+}
+
+fn to_xml<T: Serialize>(document: &T) -> Result<String, CustomerErrorReps> {
+    let body = quick_xml::se::to_string_with_root("Document", document).map_err(|err| {
+        CustomerErrorReps::InvalidInput(format!("Could not generate message: {}", err))
+    })?;
+    Ok(format!("{}\n{}", XML_DECLARATION, body))
+}
@@ -0,0 +1,624 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::bank::helper::validation::CustomerErrorReps;
+
+use super::{
+    accounts,
+    audit::{self, AuditContext, Change},
+    calendar::Calendars,
+    fraud::{self, ScreenedOperation, ScreeningContext},
+    iban,
+    outbox::{self, DomainEvent},
+    overdraft,
+    refunds::{self, Refund},
+    transactions,
+    types::{AccountStatus, EntryDirection, MandateStatus, Status, TransactionType},
+};
+
+// Debtors can ask for a collection to be refunded for eight weeks
+pub const REFUND_WINDOW_DAYS: i64 = 56;
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct Mandate {
+    pub id: Uuid,
+    pub debtor_account_id: Uuid,
+    pub creditor_account_number: String,
+    pub creditor_name: String,
+    pub reference: String,
+    pub max_amount: i32,
+    pub status: MandateStatus,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub bank_id: Uuid,
+    pub branch_id: Uuid,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct Collection {
+    pub id: Uuid,
+    pub mandate_id: Uuid,
+    pub amount: i32,
+    pub status: Status,
+    pub rejection_code: Option<String>,
+    pub transaction_id: Option<Uuid>,
+    pub refund_id: Option<Uuid>,
+    pub refundable_until: Option<NaiveDate>,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// ISO 20022 reason codes used when a collection is rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RejectionCode {
+    // Insufficient funds
+    AM04,
+    // Amount exceeds the mandate's maximum
+    AM02,
+    // Invalid amount
+    AM12,
+    // Mandate revoked by the debtor
+    MD01,
+    // Debtor account is closed
+    AC04,
+    // Debtor account is blocked (frozen or dormant)
+    AC06,
+}
+
+impl RejectionCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RejectionCode::AM04 => "AM04",
+            RejectionCode::AM02 => "AM02",
+            RejectionCode::AM12 => "AM12",
+            RejectionCode::MD01 => "MD01",
+            RejectionCode::AC04 => "AC04",
+            RejectionCode::AC06 => "AC06",
+        }
+    }
+}
+
+// `debtor_available` is the debtor's balance plus any arranged overdraft
+pub fn check_collection(
+    mandate: &Mandate,
+    debtor_status: AccountStatus,
+    debtor_available: i32,
+    amount: i32,
+) -> Result<(), RejectionCode> {
+    if mandate.status != MandateStatus::Active {
+        return Err(RejectionCode::MD01);
+    }
+    match debtor_status {
+        AccountStatus::Active => {}
+        AccountStatus::Closed => return Err(RejectionCode::AC04),
+        AccountStatus::Frozen | AccountStatus::Dormant => return Err(RejectionCode::AC06),
+    }
+    if amount <= 0 {
+        return Err(RejectionCode::AM12);
+    }
+    if amount > mandate.max_amount {
+        return Err(RejectionCode::AM02);
+    }
+    if debtor_available < amount {
+        return Err(RejectionCode::AM04);
+    }
+    Ok(())
+}
+
+pub async fn create_mandate(
+    pool: &PgPool,
+    audit: &AuditContext,
+    debtor_account_id: Uuid,
+    creditor_account_number: String,
+    creditor_name: String,
+    reference: String,
+    max_amount: i32,
+) -> Result<Mandate, CustomerErrorReps> {
+    if max_amount <= 0 {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Maximum amount must be greater than 0.".to_string(),
+        ));
+    }
+    if reference.trim().is_empty() || creditor_name.trim().is_empty() {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Mandate reference and creditor name are required.".to_string(),
+        ));
+    }
+
+    let creditor_account_number =
+        iban::resolve_account_number(pool, &creditor_account_number).await?;
+
+    let debtor_account = accounts::get_by_id(pool, debtor_account_id)
+        .await?
+        .ok_or(CustomerErrorReps::NotFound)?;
+
+    if debtor_account.account_number == creditor_account_number {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Debtor and creditor accounts must differ.".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    let mandate = sqlx::query_as!(
+        Mandate,
+        r#"
+        INSERT INTO mandates (id, debtor_account_id, creditor_account_number, creditor_name, reference, max_amount, status, revoked_at, bank_id, branch_id, inserted_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, 'active', NULL, $7, $8, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        RETURNING id, debtor_account_id, creditor_account_number, creditor_name, reference, max_amount, status as "status: _", revoked_at, bank_id, branch_id, inserted_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        debtor_account.id,
+        creditor_account_number,
+        creditor_name,
+        reference,
+        max_amount,
+        debtor_account.bank_id,
+        debtor_account.branch_id,
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("mandate.create", "mandate", mandate.id, Some(mandate.bank_id)).after(&mandate),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(mandate)
+}
+
+pub async fn get_mandate(pool: &PgPool, mandate_id: Uuid) -> Result<Option<Mandate>, sqlx::Error> {
+    let mandate = sqlx::query_as!(
+        Mandate,
+        r#"
+        SELECT id, debtor_account_id, creditor_account_number, creditor_name, reference, max_amount, status as "status: _", revoked_at, bank_id, branch_id, inserted_at, updated_at
+        FROM mandates
+        WHERE id = $1
+        "#,
+        mandate_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(mandate)
+}
+
+// The mandate, provided it belongs to `bank_id` and pays into the account `creditor_account_id`
+pub async fn get_creditor_mandate(
+    pool: &PgPool,
+    bank_id: Uuid,
+    creditor_account_id: Uuid,
+    mandate_id: Uuid,
+) -> Result<Option<Mandate>, sqlx::Error> {
+    let mandate = sqlx::query_as!(
+        Mandate,
+        r#"
+        SELECT m.id, m.debtor_account_id, m.creditor_account_number, m.creditor_name, m.reference, m.max_amount, m.status as "status: _", m.revoked_at, m.bank_id, m.branch_id, m.inserted_at, m.updated_at
+        FROM mandates AS m
+        INNER JOIN accounts AS a ON a.account_number = m.creditor_account_number
+        WHERE m.id = $1 AND m.bank_id = $2 AND a.id = $3
+        "#,
+        mandate_id,
+        bank_id,
+        creditor_account_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(mandate)
+}
+
+pub async fn revoke_mandate(
+    pool: &PgPool,
+    audit: &AuditContext,
+    mandate_id: Uuid,
+) -> Result<Mandate, CustomerErrorReps> {
+    let mut transaction = pool.begin().await?;
+
+    let mandate = sqlx::query_as!(
+        Mandate,
+        r#"
+        UPDATE mandates
+        SET status = 'revoked', revoked_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND status = 'active'
+        RETURNING id, debtor_account_id, creditor_account_number, creditor_name, reference, max_amount, status as "status: _", revoked_at, bank_id, branch_id, inserted_at, updated_at
+        "#,
+        mandate_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("mandate.revoke", "mandate", mandate.id, Some(mandate.bank_id)).after(&mandate),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(mandate)
+}
+
+pub async fn get_collections(
+    pool: &PgPool,
+    mandate_id: Uuid,
+) -> Result<Vec<Collection>, sqlx::Error> {
+    let collections = sqlx::query_as!(
+        Collection,
+        r#"
+        SELECT id, mandate_id, amount, status as "status: _", rejection_code, transaction_id, refund_id, refundable_until, inserted_at, updated_at
+        FROM direct_debit_collections
+        WHERE mandate_id = $1
+        ORDER BY inserted_at DESC
+        "#,
+        mandate_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(collections)
+}
+
+// Collects `amount` against a mandate on behalf of the creditor after screening it
+// against the debtor's account. Rejected collections are recorded with their reason
+// code and returned rather than treated as errors.
+pub async fn collect(
+    pool: &PgPool,
+    audit: &AuditContext,
+    mandate_id: Uuid,
+    amount: i32,
+) -> Result<Collection, CustomerErrorReps> {
+    let debtor_account_number = sqlx::query_scalar!(
+        r#"
+        SELECT a.account_number
+        FROM mandates AS m
+        INNER JOIN accounts AS a ON a.id = m.debtor_account_id
+        WHERE m.id = $1
+        "#,
+        mandate_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    let mut transaction = fraud::screen(
+        pool,
+        audit,
+        ScreenedOperation::DirectDebit {
+            mandate_id,
+            debtor_account_number,
+            amount,
+        },
+        ScreeningContext::default(),
+    )
+    .await?;
+
+    let collection = post_collection(&mut transaction, audit, mandate_id, amount).await?;
+
+    transaction.commit().await?;
+
+    Ok(collection)
+}
+
+// Carries out a collection that has already been screened, on the caller's transaction
+pub async fn post_collection(
+    conn: &mut PgConnection,
+    audit: &AuditContext,
+    mandate_id: Uuid,
+    amount: i32,
+) -> Result<Collection, CustomerErrorReps> {
+    let mandate = sqlx::query_as!(
+        Mandate,
+        r#"
+        SELECT id, debtor_account_id, creditor_account_number, creditor_name, reference, max_amount, status as "status: _", revoked_at, bank_id, branch_id, inserted_at, updated_at
+        FROM mandates
+        WHERE id = $1
+        FOR SHARE
+        "#,
+        mandate_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    let debtor = sqlx::query!(
+        r#"
+        SELECT account_number, balance, overdraft_limit, bank_id, branch_id, status as "status: AccountStatus" FROM accounts WHERE id = $1 FOR UPDATE
+        "#,
+        mandate.debtor_account_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let available = debtor.balance + debtor.overdraft_limit;
+    if let Err(code) = check_collection(&mandate, debtor.status, available, amount) {
+        let collection = sqlx::query_as!(
+            Collection,
+            r#"
+            INSERT INTO direct_debit_collections (id, mandate_id, amount, status, rejection_code, transaction_id, refund_id, refundable_until, inserted_at, updated_at)
+            VALUES ($1, $2, $3, 'rejected', $4, NULL, NULL, NULL, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            RETURNING id, mandate_id, amount, status as "status: _", rejection_code, transaction_id, refund_id, refundable_until, inserted_at, updated_at
+            "#,
+            Uuid::new_v4(),
+            mandate.id,
+            amount,
+            code.as_str(),
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+
+        audit::record(
+            &mut *conn,
+            audit,
+            Change::new("mandate.collection_rejected", "direct_debit_collection", collection.id, Some(mandate.bank_id))
+                .after(&collection),
+        )
+        .await?;
+
+        return Ok(collection);
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE accounts
+        SET balance = balance - $1, last_updated_date = CURRENT_DATE, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $2
+        "#,
+        amount,
+        mandate.debtor_account_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let creditor = sqlx::query!(
+        r#"
+        UPDATE accounts
+        SET balance = balance + $1, last_updated_date = CURRENT_DATE, updated_at = CURRENT_TIMESTAMP
+        WHERE account_number = $2
+        RETURNING bank_id, branch_id, status as "status: AccountStatus"
+        "#,
+        amount,
+        mandate.creditor_account_number
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    accounts::ensure_can_credit(creditor.status)?;
+
+    // The creditor may be held at another bank
+    let mut calendars = Calendars::default();
+    let calendar = calendars.get(&mut *conn, debtor.bank_id).await?;
+    let debit = transactions::insert_transaction(
+        &mut *conn,
+        calendar,
+        debtor.branch_id,
+        debtor.bank_id,
+        &debtor.account_number,
+        None,
+        TransactionType::DirectDebit,
+        EntryDirection::Debit,
+        amount,
+        Status::Approved,
+    )
+    .await?;
+    overdraft::notify_if_overdrawn(&mut *conn, mandate.debtor_account_id, debtor.balance).await?;
+
+    let calendar = calendars.get(&mut *conn, creditor.bank_id).await?;
+    transactions::insert_transaction(
+        &mut *conn,
+        calendar,
+        creditor.branch_id,
+        creditor.bank_id,
+        &mandate.creditor_account_number,
+        None,
+        TransactionType::DirectDebit,
+        EntryDirection::Credit,
+        amount,
+        Status::Approved,
+    )
+    .await?;
+
+    let refundable_until = debit.transaction_date + Duration::days(REFUND_WINDOW_DAYS);
+
+    let collection = sqlx::query_as!(
+        Collection,
+        r#"
+        INSERT INTO direct_debit_collections (id, mandate_id, amount, status, rejection_code, transaction_id, refund_id, refundable_until, inserted_at, updated_at)
+        VALUES ($1, $2, $3, 'approved', NULL, $4, NULL, $5, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        RETURNING id, mandate_id, amount, status as "status: _", rejection_code, transaction_id, refund_id, refundable_until, inserted_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        mandate.id,
+        amount,
+        debit.id,
+        refundable_until,
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    audit::record(
+        &mut *conn,
+        audit,
+        Change::new("mandate.collect", "direct_debit_collection", collection.id, Some(mandate.bank_id))
+            .after(&collection),
+    )
+    .await?;
+
+    outbox::publish(
+        &mut *conn,
+        Some(mandate.bank_id),
+        &DomainEvent::DirectDebitCollected(collection.clone()),
+    )
+    .await?;
+
+    Ok(collection)
+}
+
+// Debtor-initiated refund of a collection within the refund window. The creditor is
+// debited even if that takes their account negative, as the debtor's right prevails.
+pub async fn refund_collection(
+    pool: &PgPool,
+    audit: &AuditContext,
+    mandate_id: Uuid,
+    collection_id: Uuid,
+) -> Result<Refund, CustomerErrorReps> {
+    let mut transaction = pool.begin().await?;
+
+    let collection = sqlx::query_as!(
+        Collection,
+        r#"
+        SELECT id, mandate_id, amount, status as "status: _", rejection_code, transaction_id, refund_id, refundable_until, inserted_at, updated_at
+        FROM direct_debit_collections
+        WHERE id = $1 AND mandate_id = $2
+        FOR UPDATE
+        "#,
+        collection_id,
+        mandate_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    let (debit_transaction_id, refundable_until) =
+        match (collection.status, collection.transaction_id, collection.refundable_until) {
+            (Status::Approved, Some(transaction_id), Some(refundable_until)) => {
+                (transaction_id, refundable_until)
+            }
+            _ => {
+                return Err(CustomerErrorReps::InvalidInput(
+                    "Only completed collections can be refunded.".to_string(),
+                ))
+            }
+        };
+
+    if collection.refund_id.is_some() {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Collection was already refunded.".to_string(),
+        ));
+    }
+    if chrono::Utc::now().naive_utc().date() > refundable_until {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Refund window has closed.".to_string(),
+        ));
+    }
+
+    let mandate = sqlx::query!(
+        r#"
+        SELECT debtor_account_id, creditor_account_number FROM mandates WHERE id = $1
+        "#,
+        mandate_id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    let debtor = sqlx::query!(
+        r#"
+        UPDATE accounts
+        SET balance = balance + $1, last_updated_date = CURRENT_DATE, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $2
+        RETURNING account_number, bank_id, branch_id, status as "status: AccountStatus"
+        "#,
+        collection.amount,
+        mandate.debtor_account_id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+    accounts::ensure_can_credit(debtor.status)?;
+
+    let creditor = sqlx::query!(
+        r#"
+        UPDATE accounts
+        SET balance = balance - $1, last_updated_date = CURRENT_DATE, updated_at = CURRENT_TIMESTAMP
+        WHERE account_number = $2
+        RETURNING bank_id, branch_id, status as "status: AccountStatus"
+        "#,
+        collection.amount,
+        mandate.creditor_account_number
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+    // The debtor is owed the refund even if the creditor is frozen or dormant; only a
+    // closed creditor account can no longer be debited
+    accounts::ensure_can_credit(creditor.status)?;
+
+    let mut calendars = Calendars::default();
+    let calendar = calendars.get(&mut transaction, debtor.bank_id).await?;
+    transactions::insert_transaction(
+        &mut transaction,
+        calendar,
+        debtor.branch_id,
+        debtor.bank_id,
+        &debtor.account_number,
+        None,
+        TransactionType::DirectDebitRefund,
+        EntryDirection::Credit,
+        collection.amount,
+        Status::Approved,
+    )
+    .await?;
+
+    let calendar = calendars.get(&mut transaction, creditor.bank_id).await?;
+    transactions::insert_transaction(
+        &mut transaction,
+        calendar,
+        creditor.branch_id,
+        creditor.bank_id,
+        &mandate.creditor_account_number,
+        None,
+        TransactionType::DirectDebitRefund,
+        EntryDirection::Debit,
+        collection.amount,
+        Status::Approved,
+    )
+    .await?;
+
+    let refund = refunds::insert_refund(
+        &mut transaction,
+        debtor.branch_id,
+        debtor.bank_id,
+        debit_transaction_id,
+        collection.amount,
+        Status::Approved,
+    )
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE direct_debit_collections
+        SET refund_id = $1, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $2
+        "#,
+        refund.id,
+        collection.id
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("refund.approve", "refund", refund.id, Some(refund.bank_id))
+            .before(&collection)
+            .after(&refund),
+    )
+    .await?;
+
+    outbox::publish(
+        &mut transaction,
+        Some(refund.bank_id),
+        &DomainEvent::RefundApproved(refund.clone()),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(refund)
+}
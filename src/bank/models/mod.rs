@@ -1,11 +1,33 @@
 pub mod customer;
 pub mod types;
+pub mod calendar;
 pub mod accounts;
+pub mod iban;
 pub mod cards;
 pub mod loans;
 pub mod refunds;
 pub mod beneficiary;
 pub mod transactions;
 pub mod transfer;
+pub mod clearing;
 pub mod bank;
 pub mod branchs;
+pub mod credit;
+pub mod fees;
+pub mod overdraft;
+pub mod teller;
+pub mod treasury;
+pub mod ctr;
+pub mod eod;
+pub mod standing_orders;
+pub mod mandates;
+pub mod payment_batches;
+pub mod iso20022;
+pub mod auth;
+pub mod api_keys;
+pub mod audit;
+pub mod fraud;
+pub mod sanctions;
+pub mod event_sinks;
+pub mod outbox;
+pub mod webhooks;
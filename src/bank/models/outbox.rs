@@ -0,0 +1,227 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use super::{
+    accounts::Account,
+    clearing::ClearingItem,
+    customer::Customer,
+    event_sinks::EventSink,
+    mandates::Collection,
+    refunds::Refund,
+    transfer::Transfer,
+    types::CardStatus,
+};
+
+pub const RELAY_BATCH_SIZE: i64 = 100;
+
+// Failed deliveries back off exponentially, capped at an hour
+const MAX_BACKOFF_SECONDS: i64 = 60 * 60;
+
+// How long a relay keeps a claimed batch to itself. If it dies mid-batch, the messages
+// it did not settle become due again after this.
+const CLAIM_LEASE_SECONDS: f64 = 15.0 * 60.0;
+
+pub const EVENT_TYPES: [&str; 8] = [
+    "CustomerCreated",
+    "AccountOpened",
+    "TransferCompleted",
+    "CardStatusChanged",
+    "RefundApproved",
+    "DirectDebitCollected",
+    "OverdraftEntered",
+    "ClearingItemReturned",
+];
+
+// Facts other systems care about. Serialized as `{"type": ..., "data": ...}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum DomainEvent {
+    CustomerCreated(Customer),
+    AccountOpened(Account),
+    TransferCompleted(Transfer),
+    CardStatusChanged {
+        card_id: Uuid,
+        account_number: String,
+        previous_status: CardStatus,
+        card_status: CardStatus,
+    },
+    RefundApproved(Refund),
+    DirectDebitCollected(Collection),
+    // A debit took the account from credit into overdraft
+    OverdraftEntered {
+        account_id: Uuid,
+        account_number: String,
+        balance: i32,
+        overdraft_limit: i32,
+    },
+    // The receiving bank refused an interbank transfer and the debtor was credited back
+    ClearingItemReturned(ClearingItem),
+}
+
+impl DomainEvent {
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            DomainEvent::CustomerCreated(_) => "CustomerCreated",
+            DomainEvent::AccountOpened(_) => "AccountOpened",
+            DomainEvent::TransferCompleted(_) => "TransferCompleted",
+            DomainEvent::CardStatusChanged { .. } => "CardStatusChanged",
+            DomainEvent::RefundApproved(_) => "RefundApproved",
+            DomainEvent::DirectDebitCollected(_) => "DirectDebitCollected",
+            DomainEvent::OverdraftEntered { .. } => "OverdraftEntered",
+            DomainEvent::ClearingItemReturned(_) => "ClearingItemReturned",
+        }
+    }
+
+    pub fn aggregate(&self) -> (&'static str, Uuid) {
+        match self {
+            DomainEvent::CustomerCreated(customer) => ("customer", customer.id),
+            DomainEvent::AccountOpened(account) => ("account", account.id),
+            DomainEvent::TransferCompleted(transfer) => ("transfer", transfer.id),
+            DomainEvent::CardStatusChanged { card_id, .. } => ("card", *card_id),
+            DomainEvent::RefundApproved(refund) => ("refund", refund.id),
+            DomainEvent::DirectDebitCollected(collection) => {
+                ("direct_debit_collection", collection.id)
+            }
+            DomainEvent::OverdraftEntered { account_id, .. } => ("account", *account_id),
+            DomainEvent::ClearingItemReturned(item) => ("clearing_item", item.id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct OutboxMessage {
+    pub sequence: i64,
+    pub id: Uuid,
+    pub event_type: String,
+    pub aggregate_type: String,
+    pub aggregate_id: Uuid,
+    pub bank_id: Option<Uuid>,
+    pub payload: Value,
+    pub occurred_at: NaiveDateTime,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: NaiveDateTime,
+    pub delivered_at: Option<NaiveDateTime>,
+}
+
+// Must be called on the same connection/transaction as the change the event describes,
+// so the event exists if and only if the change was committed.
+pub async fn publish(
+    conn: &mut PgConnection,
+    bank_id: Option<Uuid>,
+    event: &DomainEvent,
+) -> Result<OutboxMessage, sqlx::Error> {
+    let (aggregate_type, aggregate_id) = event.aggregate();
+    let payload = match serde_json::to_value(event) {
+        Ok(Value::Object(mut fields)) => fields.remove("data").unwrap_or(Value::Null),
+        Ok(_) => Value::Null,
+        Err(err) => return Err(sqlx::Error::Protocol(err.to_string())),
+    };
+
+    let message = sqlx::query_as!(
+        OutboxMessage,
+        r#"
+        INSERT INTO outbox (id, event_type, aggregate_type, aggregate_id, bank_id, payload, occurred_at, next_attempt_at)
+        VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        RETURNING sequence, id, event_type, aggregate_type, aggregate_id, bank_id, payload, occurred_at, attempts, last_error, next_attempt_at, delivered_at
+        "#,
+        Uuid::new_v4(),
+        event.event_type(),
+        aggregate_type,
+        aggregate_id,
+        bank_id,
+        payload
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(message)
+}
+
+fn backoff_seconds(attempts: i32) -> i64 {
+    2_i64
+        .saturating_pow(attempts.clamp(0, 30) as u32)
+        .min(MAX_BACKOFF_SECONDS)
+}
+
+// Delivers one batch of due messages to every sink, oldest first. A message is only
+// marked delivered once all sinks accepted it; otherwise it is retried later and may
+// reach the sinks that did accept it again, so consumers must deduplicate on `id`.
+// The batch is claimed in a short transaction and delivered without holding row locks.
+pub async fn relay_pending(
+    pool: &PgPool,
+    sinks: &[Box<dyn EventSink>],
+) -> Result<usize, sqlx::Error> {
+    // SKIP LOCKED lets several relay instances claim disjoint batches
+    let mut messages = sqlx::query_as!(
+        OutboxMessage,
+        r#"
+        UPDATE outbox
+        SET next_attempt_at = CURRENT_TIMESTAMP + make_interval(secs => $2)
+        WHERE sequence IN (
+            SELECT sequence FROM outbox
+            WHERE delivered_at IS NULL AND next_attempt_at <= CURRENT_TIMESTAMP
+            ORDER BY sequence
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING sequence, id, event_type, aggregate_type, aggregate_id, bank_id, payload, occurred_at, attempts, last_error, next_attempt_at, delivered_at
+        "#,
+        RELAY_BATCH_SIZE,
+        CLAIM_LEASE_SECONDS
+    )
+    .fetch_all(pool)
+    .await?;
+
+    // RETURNING does not keep the subquery's order
+    messages.sort_by_key(|message| message.sequence);
+
+    let mut delivered = 0;
+    for message in &messages {
+        let mut failure = None;
+        for sink in sinks {
+            if let Err(err) = sink.deliver(message).await {
+                failure = Some(format!("{}: {}", sink.name(), err));
+                break;
+            }
+        }
+
+        match failure {
+            None => {
+                sqlx::query!(
+                    r#"
+                    UPDATE outbox
+                    SET delivered_at = CURRENT_TIMESTAMP, attempts = attempts + 1, last_error = NULL
+                    WHERE sequence = $1
+                    "#,
+                    message.sequence
+                )
+                .execute(pool)
+                .await?;
+                delivered += 1;
+            }
+            Some(error) => {
+                tracing::warn!("outbox message {} not delivered: {}", message.id, error);
+                sqlx::query!(
+                    r#"
+                    UPDATE outbox
+                    SET attempts = attempts + 1,
+                        last_error = $2,
+                        next_attempt_at = CURRENT_TIMESTAMP + make_interval(secs => $3)
+                    WHERE sequence = $1
+                    "#,
+                    message.sequence,
+                    error,
+                    backoff_seconds(message.attempts) as f64
+                )
+                .execute(pool)
+                .await?;
+            }
+        }
+    }
+
+    Ok(delivered)
+}
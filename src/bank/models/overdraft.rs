@@ -0,0 +1,242 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::bank::helper::validation::CustomerErrorReps;
+
+use super::{
+    audit::{self, AuditContext, Change},
+    calendar::Calendars,
+    fees,
+    outbox::{self, DomainEvent},
+    transactions,
+    types::{EntryDirection, Status, TransactionType},
+};
+
+// Overdraft rates are yearly; interest is charged per calendar day
+pub const DAYS_PER_YEAR: i64 = 365;
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct OverdraftAccrual {
+    pub id: Uuid,
+    pub bank_id: Uuid,
+    pub account_id: Uuid,
+    pub accrual_date: NaiveDate,
+    pub balance: i32,
+    pub overdraft_limit: i32,
+    pub rate_bps: i32,
+    pub interest: i32,
+    pub transaction_id: Option<Uuid>,
+    pub inserted_at: NaiveDateTime,
+}
+
+// One day of interest on an overdrawn balance, rounded half up
+pub fn daily_interest(balance: i32, rate_bps: i32) -> i32 {
+    if balance >= 0 || rate_bps <= 0 {
+        return 0;
+    }
+    let divisor = 10_000 * DAYS_PER_YEAR;
+    ((-(balance as i64) * rate_bps as i64 + divisor / 2) / divisor) as i32
+}
+
+// Publishes `OverdraftEntered` when the account was in credit at `balance_before` and is
+// overdrawn now. Called on the transaction that made the debits.
+pub async fn notify_if_overdrawn(
+    conn: &mut PgConnection,
+    account_id: Uuid,
+    balance_before: i32,
+) -> Result<(), sqlx::Error> {
+    if balance_before < 0 {
+        return Ok(());
+    }
+
+    let account = sqlx::query!(
+        r#"
+        SELECT bank_id, account_number, balance, overdraft_limit FROM accounts WHERE id = $1
+        "#,
+        account_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if account.balance < 0 {
+        outbox::publish(
+            conn,
+            Some(account.bank_id),
+            &DomainEvent::OverdraftEntered {
+                account_id,
+                account_number: account.account_number,
+                balance: account.balance,
+                overdraft_limit: account.overdraft_limit,
+            },
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+// Charges a day of interest to every account overdrawn at the end of `as_of`, and the
+// unarranged overdraft fee to those past their limit. The balance at the end of `as_of` is
+// the current one less what has been booked with a later value date, so a run for a past
+// date sees that date's balance. Credit card postings do not move it. Accounts already
+// accrued for the date are skipped, so the run can be repeated.
+pub async fn accrue_interest(
+    pool: &PgPool,
+    bank_id: Option<Uuid>,
+    as_of: NaiveDate,
+) -> Result<u64, CustomerErrorReps> {
+    let account_ids = sqlx::query_scalar!(
+        r#"
+        SELECT a.id FROM accounts AS a
+        WHERE a.status <> 'closed' AND ($1::uuid IS NULL OR a.bank_id = $1)
+            AND NOT EXISTS (
+                SELECT 1 FROM overdraft_accruals AS o WHERE o.account_id = a.id AND o.accrual_date = $2
+            )
+            AND a.balance - (
+                SELECT COALESCE(SUM(CASE WHEN t.direction = 'credit' THEN t.amount ELSE -t.amount END), 0)
+                FROM transactions AS t
+                WHERE t.account_number = a.account_number AND t.value_date > $2 AND t.status = 'approved'
+                    AND t.transaction_type NOT IN ('credit_card_charge', 'interest_charge', 'late_fee')
+            ) < 0
+        "#,
+        bank_id,
+        as_of
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let audit = AuditContext::system("overdraft_accrual");
+    let mut calendars = Calendars::default();
+    let mut accrued = 0;
+    for account_id in account_ids {
+        let mut transaction = pool.begin().await?;
+
+        let account = sqlx::query!(
+            r#"
+            SELECT a.bank_id, a.branch_id, a.account_number, a.overdraft_limit, a.overdraft_rate_bps,
+                (a.balance - (
+                    SELECT COALESCE(SUM(CASE WHEN t.direction = 'credit' THEN t.amount ELSE -t.amount END), 0)
+                    FROM transactions AS t
+                    WHERE t.account_number = a.account_number AND t.value_date > $2 AND t.status = 'approved'
+                        AND t.transaction_type NOT IN ('credit_card_charge', 'interest_charge', 'late_fee')
+                ))::INTEGER AS "balance!"
+            FROM accounts AS a
+            WHERE a.id = $1
+            FOR UPDATE
+            "#,
+            account_id,
+            as_of
+        )
+        .fetch_one(&mut transaction)
+        .await?;
+        if account.balance >= 0 {
+            continue;
+        }
+        let calendar = calendars.get(&mut transaction, account.bank_id).await?;
+
+        let interest = daily_interest(account.balance, account.overdraft_rate_bps);
+        let transaction_id = if interest > 0 {
+            sqlx::query!(
+                r#"
+                UPDATE accounts
+                SET balance = balance - $1, last_updated_date = CURRENT_DATE, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $2
+                "#,
+                interest,
+                account_id
+            )
+            .execute(&mut transaction)
+            .await?;
+
+            let posted = transactions::insert_transaction(
+                &mut transaction,
+                calendar,
+                account.branch_id,
+                account.bank_id,
+                &account.account_number,
+                None,
+                TransactionType::OverdraftInterest,
+                EntryDirection::Debit,
+                interest,
+                Status::Approved,
+            )
+            .await?;
+            Some(posted.id)
+        } else {
+            None
+        };
+
+        let accrual = sqlx::query_as!(
+            OverdraftAccrual,
+            r#"
+            INSERT INTO overdraft_accruals (id, bank_id, account_id, accrual_date, balance, overdraft_limit, rate_bps, interest, transaction_id, inserted_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, CURRENT_TIMESTAMP)
+            RETURNING id, bank_id, account_id, accrual_date, balance, overdraft_limit, rate_bps, interest, transaction_id, inserted_at
+            "#,
+            Uuid::new_v4(),
+            account.bank_id,
+            account_id,
+            as_of,
+            account.balance,
+            account.overdraft_limit,
+            account.overdraft_rate_bps,
+            interest,
+            transaction_id
+        )
+        .fetch_one(&mut transaction)
+        .await?;
+
+        if interest > 0 {
+            audit::record(
+                &mut transaction,
+                &audit,
+                Change::new(
+                    "overdraft.accrue",
+                    "overdraft_accrual",
+                    accrual.id,
+                    Some(accrual.bank_id),
+                )
+                .after(&accrual),
+            )
+            .await?;
+        }
+
+        // On the balance the day ended with, not the one the interest just left
+        fees::charge_unarranged_overdraft_fee(
+            &mut transaction,
+            &audit,
+            calendar,
+            account_id,
+            account.balance,
+            as_of,
+        )
+        .await?;
+
+        transaction.commit().await?;
+        accrued += 1;
+    }
+
+    Ok(accrued)
+}
+
+pub async fn get_accruals(
+    pool: &PgPool,
+    account_id: Uuid,
+) -> Result<Vec<OverdraftAccrual>, sqlx::Error> {
+    let accruals = sqlx::query_as!(
+        OverdraftAccrual,
+        r#"
+        SELECT id, bank_id, account_id, accrual_date, balance, overdraft_limit, rate_bps, interest, transaction_id, inserted_at
+        FROM overdraft_accruals
+        WHERE account_id = $1
+        ORDER BY accrual_date DESC
+        "#,
+        account_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(accruals)
+}
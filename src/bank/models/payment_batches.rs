@@ -0,0 +1,950 @@
+use std::collections::HashSet;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgExecutor, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::bank::helper::validation::{validate_account_number, CustomerErrorReps};
+
+use super::{
+    audit::{self, AuditContext, Change},
+    auth,
+    fraud::{self, ScreenedOperation, ScreeningContext},
+    iban, transfer,
+    types::{AccountPermission, BatchExecutionMode, PaymentBatchStatus, PaymentItemStatus},
+};
+
+// How long a run keeps a claimed batch to itself. If it dies mid-batch, the batch is
+// claimed again by a later run once this has passed.
+const PROCESSING_LEASE_SECONDS: f64 = 60.0 * 60.0;
+
+// A group of transfers out of one account submitted together by a customer
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct PaymentBatch {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub debtor_account_id: Uuid,
+    pub source_format: String,
+    pub execution_mode: BatchExecutionMode,
+    pub message_id: String,
+    pub payment_information_id: String,
+    pub requested_execution_date: NaiveDate,
+    pub item_count: i32,
+    pub control_sum: i64,
+    pub status: PaymentBatchStatus,
+    pub processed_at: Option<NaiveDateTime>,
+    pub bank_id: Uuid,
+    pub branch_id: Uuid,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct PaymentBatchItem {
+    pub id: Uuid,
+    pub batch_id: Uuid,
+    pub line_number: i32,
+    pub end_to_end_id: String,
+    pub creditor_name: String,
+    pub creditor_account_number: String,
+    pub amount: i32,
+    pub remittance_information: Option<String>,
+    pub status: PaymentItemStatus,
+    pub reason_code: Option<String>,
+    pub reason: Option<String>,
+    pub transfer_id: Option<Uuid>,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewPaymentBatch {
+    pub debtor_account_number: String,
+    pub execution_mode: BatchExecutionMode,
+    pub message_id: String,
+    pub payment_information_id: String,
+    pub requested_execution_date: NaiveDate,
+    pub items: Vec<NewPaymentItem>,
+}
+
+// A line as read from the submitted file. Lines the parser could not make sense of
+// carry their rejection so they are still reported back item by item.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewPaymentItem {
+    pub end_to_end_id: String,
+    pub creditor_name: String,
+    pub creditor_account_number: String,
+    pub amount: i32,
+    pub remittance_information: Option<String>,
+    pub rejection: Option<Rejection>,
+}
+
+// One transfer of a CSV or JSON batch. Amounts are in minor units as everywhere else in
+// the API; the reference is reported back as the line's end-to-end id.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BulkTransfer {
+    pub reference: Option<String>,
+    pub beneficiary_name: String,
+    pub beneficiary_account_number: String,
+    pub amount: i32,
+    pub remittance_information: Option<String>,
+}
+
+impl BulkTransfer {
+    pub fn into_item(self, line_number: usize) -> NewPaymentItem {
+        NewPaymentItem {
+            end_to_end_id: self
+                .reference
+                .map(|reference| reference.trim().to_string())
+                .filter(|reference| !reference.is_empty())
+                .unwrap_or_else(|| line_number.to_string()),
+            creditor_name: self.beneficiary_name,
+            creditor_account_number: self.beneficiary_account_number,
+            amount: self.amount,
+            remittance_information: self.remittance_information,
+            rejection: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BulkTransferCsvRow {
+    reference: Option<String>,
+    beneficiary_name: String,
+    beneficiary_account_number: String,
+    amount: String,
+    remittance_information: Option<String>,
+}
+
+// The header row names the same columns as `BulkTransfer`. A line that cannot be read
+// is rejected on its own instead of failing the whole file.
+pub fn parse_csv(content: &[u8]) -> Result<Vec<NewPaymentItem>, CustomerErrorReps> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(content);
+    let headers = reader
+        .headers()
+        .map_err(|err| CustomerErrorReps::InvalidInput(format!("Invalid CSV: {}", err)))?
+        .clone();
+    for column in ["beneficiary_name", "beneficiary_account_number", "amount"] {
+        if !headers.iter().any(|header| header == column) {
+            return Err(CustomerErrorReps::InvalidInput(format!(
+                "CSV is missing the {} column.",
+                column
+            )));
+        }
+    }
+
+    let items = reader
+        .records()
+        .enumerate()
+        .map(|(index, record)| {
+            let line_number = index + 1;
+            let row =
+                record.and_then(|record| record.deserialize::<BulkTransferCsvRow>(Some(&headers)));
+            let row = match row {
+                Ok(row) => row,
+                Err(err) => {
+                    return NewPaymentItem {
+                        end_to_end_id: line_number.to_string(),
+                        creditor_name: String::new(),
+                        creditor_account_number: String::new(),
+                        amount: 0,
+                        remittance_information: None,
+                        rejection: Some((ReasonCode::MS03, format!("Malformed line: {}", err))),
+                    }
+                }
+            };
+
+            let amount = row.amount.parse::<i32>().ok();
+            let mut item = BulkTransfer {
+                reference: row.reference,
+                beneficiary_name: row.beneficiary_name,
+                beneficiary_account_number: row.beneficiary_account_number,
+                amount: amount.unwrap_or_default(),
+                remittance_information: row.remittance_information,
+            }
+            .into_item(line_number);
+            if amount.is_none() {
+                item.rejection =
+                    Some((ReasonCode::AM12, format!("Invalid amount: {}", row.amount)));
+            }
+            item
+        })
+        .collect();
+
+    Ok(items)
+}
+
+// Counts and totals of a batch's lines by outcome
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchSummary {
+    pub batch_id: Uuid,
+    pub status: PaymentBatchStatus,
+    pub execution_mode: BatchExecutionMode,
+    pub item_count: i32,
+    pub total_amount: i64,
+    pub completed_count: usize,
+    pub completed_amount: i64,
+    pub pending_count: usize,
+    pub held_count: usize,
+    pub rejected_count: usize,
+    pub failed_count: usize,
+    pub processed_at: Option<NaiveDateTime>,
+}
+
+pub fn summarize(batch: &PaymentBatch, items: &[PaymentBatchItem]) -> BatchSummary {
+    let count =
+        |status: PaymentItemStatus| items.iter().filter(|item| item.status == status).count();
+
+    BatchSummary {
+        batch_id: batch.id,
+        status: batch.status,
+        execution_mode: batch.execution_mode,
+        item_count: batch.item_count,
+        total_amount: batch.control_sum,
+        completed_count: count(PaymentItemStatus::Completed),
+        completed_amount: items
+            .iter()
+            .filter(|item| item.status == PaymentItemStatus::Completed)
+            .map(|item| item.amount as i64)
+            .sum(),
+        pending_count: count(PaymentItemStatus::Pending),
+        held_count: count(PaymentItemStatus::Held),
+        rejected_count: count(PaymentItemStatus::Rejected),
+        failed_count: count(PaymentItemStatus::Failed),
+        processed_at: batch.processed_at,
+    }
+}
+
+// ISO 20022 status reason codes reported for rejected and failed items
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReasonCode {
+    // Creditor account number is invalid or missing
+    AC01,
+    // Account is closed
+    AC04,
+    // Account is blocked (frozen or dormant)
+    AC06,
+    // Currency is not the one accounts are held in
+    AM03,
+    // Insufficient funds
+    AM04,
+    // End-to-end id repeated within the batch
+    AM05,
+    // Invalid amount
+    AM12,
+    // Declined by fraud screening
+    FR01,
+    // Reason not covered by a more specific code
+    MS03,
+    // Creditor name matches a watchlist entry
+    RR04,
+}
+
+impl ReasonCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReasonCode::AC01 => "AC01",
+            ReasonCode::AC04 => "AC04",
+            ReasonCode::AC06 => "AC06",
+            ReasonCode::AM03 => "AM03",
+            ReasonCode::AM04 => "AM04",
+            ReasonCode::AM05 => "AM05",
+            ReasonCode::AM12 => "AM12",
+            ReasonCode::FR01 => "FR01",
+            ReasonCode::MS03 => "MS03",
+            ReasonCode::RR04 => "RR04",
+        }
+    }
+}
+
+pub type Rejection = (ReasonCode, String);
+
+// Outcome of carrying out a single item
+enum ItemOutcome {
+    Completed(Uuid),
+    Held(String),
+    Failed(ReasonCode, String),
+}
+
+// Item errors become per-item rejections; only database failures stop the batch
+fn item_outcome(err: CustomerErrorReps) -> Result<ItemOutcome, CustomerErrorReps> {
+    let reason = err.to_string();
+    let code = match err {
+        CustomerErrorReps::DatabaseError(_) => return Err(err),
+        CustomerErrorReps::HeldForReview(_) => return Ok(ItemOutcome::Held(reason)),
+        CustomerErrorReps::NotFound => ReasonCode::AC01,
+        CustomerErrorReps::AccountUnavailable("closed") => ReasonCode::AC04,
+        CustomerErrorReps::AccountUnavailable(_) => ReasonCode::AC06,
+        CustomerErrorReps::InsufficientFunds => ReasonCode::AM04,
+        CustomerErrorReps::TransactionDeclined(_) => ReasonCode::FR01,
+        CustomerErrorReps::SanctionsMatch(_) => ReasonCode::RR04,
+        _ => ReasonCode::MS03,
+    };
+    Ok(ItemOutcome::Failed(code, reason))
+}
+
+// Upfront checks that need no funds or screening, so a bad line is reported before
+// anything is paid. Returns the creditor account number to pay.
+async fn validate_item(
+    pool: &PgPool,
+    debtor_account_number: &str,
+    item: &NewPaymentItem,
+) -> Result<Result<String, Rejection>, CustomerErrorReps> {
+    if let Some(rejection) = &item.rejection {
+        return Ok(Err(rejection.clone()));
+    }
+    if item.amount <= 0 {
+        let reason = "Amount must be greater than 0.".to_string();
+        return Ok(Err((ReasonCode::AM12, reason)));
+    }
+    if item.creditor_name.trim().is_empty() {
+        let reason = "Creditor name is required.".to_string();
+        return Ok(Err((ReasonCode::MS03, reason)));
+    }
+
+    let creditor_account_number =
+        match iban::resolve_account_number(pool, &item.creditor_account_number).await {
+            Ok(number) => number,
+            Err(CustomerErrorReps::InvalidInput(reason)) => {
+                return Ok(Err((ReasonCode::AC01, reason)))
+            }
+            Err(err) => return Err(err),
+        };
+
+    if creditor_account_number == debtor_account_number {
+        let reason = "Creditor and debtor accounts must differ.".to_string();
+        return Ok(Err((ReasonCode::AC01, reason)));
+    }
+
+    // Lines only pay the owners' registered beneficiaries, which were screened when they
+    // were added; a batch never registers new ones
+    let registered = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1
+            FROM beneficiaries AS b
+            INNER JOIN account_holders AS h ON h.customer_id = b.customer_id
+            INNER JOIN accounts AS a ON a.id = h.account_id
+            WHERE a.account_number = $1 AND h.role IN ('primary', 'joint')
+                AND b.beneficiary_account_number = $2
+        ) AS "exists!"
+        "#,
+        debtor_account_number,
+        creditor_account_number
+    )
+    .fetch_one(pool)
+    .await?;
+    if !registered {
+        let reason = "Creditor is not a registered beneficiary of the account.".to_string();
+        return Ok(Err((ReasonCode::MS03, reason)));
+    }
+
+    // Accounts opened before check digits were introduced keep their old numbers
+    if !iban::looks_like_iban(&creditor_account_number) {
+        let held_with_us = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM accounts WHERE account_number = $1
+            ) AS "exists!"
+            "#,
+            creditor_account_number
+        )
+        .fetch_one(pool)
+        .await?;
+
+        let validation = validate_account_number(&creditor_account_number);
+        if !held_with_us && !validation.is_valid {
+            let reason = validation.error_message.unwrap_or_default();
+            return Ok(Err((ReasonCode::AC01, reason)));
+        }
+    }
+
+    Ok(Ok(creditor_account_number))
+}
+
+// Stores the batches of one submitted message after checking every line, then carries
+// out those that are due. Problems with the message as a whole (unknown debtor account,
+// missing permission, a message id that was already used) reject everything; problems
+// with a line only reject that line.
+pub async fn submit_batches(
+    pool: &PgPool,
+    audit: &AuditContext,
+    customer_id: Uuid,
+    source_format: &str,
+    batches: Vec<NewPaymentBatch>,
+    today: NaiveDate,
+) -> Result<Vec<PaymentBatch>, CustomerErrorReps> {
+    if batches.is_empty() {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Message contains no payments.".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+    let mut created = Vec::with_capacity(batches.len());
+
+    for new_batch in batches {
+        if new_batch.items.is_empty() {
+            return Err(CustomerErrorReps::InvalidInput(format!(
+                "Payment information {} contains no transactions.",
+                new_batch.payment_information_id
+            )));
+        }
+
+        let debtor_account_number =
+            iban::resolve_account_number(pool, &new_batch.debtor_account_number).await?;
+        let debtor = sqlx::query!(
+            r#"
+            SELECT id, bank_id, branch_id FROM accounts WHERE account_number = $1
+            "#,
+            debtor_account_number
+        )
+        .fetch_optional(&mut transaction)
+        .await?
+        .ok_or(CustomerErrorReps::NotFound)?;
+
+        match auth::customer_account_role(pool, customer_id, debtor.id).await? {
+            Some(role) if role.permits(AccountPermission::Transact) => {}
+            _ => return Err(CustomerErrorReps::Forbidden),
+        }
+
+        let duplicate = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM payment_batches
+                WHERE customer_id = $1 AND message_id = $2 AND payment_information_id = $3
+            ) AS "exists!"
+            "#,
+            customer_id,
+            new_batch.message_id,
+            new_batch.payment_information_id
+        )
+        .fetch_one(&mut transaction)
+        .await?;
+
+        if duplicate {
+            return Err(CustomerErrorReps::InvalidInput(format!(
+                "Message {} has already been submitted.",
+                new_batch.message_id
+            )));
+        }
+
+        let control_sum: i64 = new_batch.items.iter().map(|item| item.amount as i64).sum();
+        let batch = sqlx::query_as!(
+            PaymentBatch,
+            r#"
+            INSERT INTO payment_batches (id, customer_id, debtor_account_id, source_format, execution_mode, message_id, payment_information_id, requested_execution_date, item_count, control_sum, status, processed_at, bank_id, branch_id, inserted_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, NULL, $12, $13, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            RETURNING id, customer_id, debtor_account_id, source_format, execution_mode as "execution_mode: _", message_id, payment_information_id, requested_execution_date, item_count, control_sum, status as "status: _", processed_at, bank_id, branch_id, inserted_at, updated_at
+            "#,
+            Uuid::new_v4(),
+            customer_id,
+            debtor.id,
+            source_format,
+            new_batch.execution_mode as BatchExecutionMode,
+            new_batch.message_id,
+            new_batch.payment_information_id,
+            new_batch.requested_execution_date.max(today),
+            new_batch.items.len() as i32,
+            control_sum,
+            PaymentBatchStatus::Accepted as PaymentBatchStatus,
+            debtor.bank_id,
+            debtor.branch_id,
+        )
+        .fetch_one(&mut transaction)
+        .await?;
+
+        let mut end_to_end_ids = HashSet::new();
+        let mut invalid_lines = 0;
+        for (index, item) in new_batch.items.iter().enumerate() {
+            let (creditor_account_number, mut rejection) =
+                match validate_item(pool, &debtor_account_number, item).await? {
+                    Ok(number) => (number, None),
+                    Err(rejection) => (item.creditor_account_number.clone(), Some(rejection)),
+                };
+            if rejection.is_none() && !end_to_end_ids.insert(item.end_to_end_id.as_str()) {
+                rejection = Some((
+                    ReasonCode::AM05,
+                    format!("End-to-end id {} is repeated.", item.end_to_end_id),
+                ));
+            }
+
+            let status = if rejection.is_some() {
+                invalid_lines += 1;
+                PaymentItemStatus::Rejected
+            } else {
+                PaymentItemStatus::Pending
+            };
+            let (reason_code, reason) = match rejection {
+                Some((code, reason)) => (Some(code.as_str()), Some(reason)),
+                None => (None, None),
+            };
+
+            sqlx::query!(
+                r#"
+                INSERT INTO payment_batch_items (id, batch_id, line_number, end_to_end_id, creditor_name, creditor_account_number, amount, remittance_information, status, reason_code, reason, transfer_id, inserted_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, NULL, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+                "#,
+                Uuid::new_v4(),
+                batch.id,
+                index as i32 + 1,
+                item.end_to_end_id,
+                item.creditor_name,
+                creditor_account_number,
+                item.amount,
+                item.remittance_information,
+                status as PaymentItemStatus,
+                reason_code,
+                reason,
+            )
+            .execute(&mut transaction)
+            .await?;
+        }
+
+        // All-or-nothing batches with an invalid line are turned down before anything is paid
+        let batch =
+            if new_batch.execution_mode == BatchExecutionMode::AllOrNothing && invalid_lines > 0 {
+                fail_pending_items(
+                    &mut transaction,
+                    batch.id,
+                    "Not carried out: the batch has invalid lines.",
+                )
+                .await?;
+                finish_batch(
+                    &mut transaction,
+                    audit,
+                    &batch,
+                    Some(PaymentBatchStatus::Rejected),
+                )
+                .await?
+            } else {
+                batch
+            };
+
+        audit::record(
+            &mut transaction,
+            audit,
+            Change::new(
+                "payment_batch.submit",
+                "payment_batch",
+                batch.id,
+                Some(batch.bank_id),
+            )
+            .after(&batch),
+        )
+        .await?;
+
+        created.push(batch);
+    }
+
+    transaction.commit().await?;
+
+    let mut batches = Vec::with_capacity(created.len());
+    for batch in created {
+        if batch.requested_execution_date <= today {
+            batches.push(process_batch(pool, audit, batch.id).await?.unwrap_or(batch));
+        } else {
+            batches.push(batch);
+        }
+    }
+
+    Ok(batches)
+}
+
+// Carries out the batch's pending items. Returns `None` when the batch is not waiting to
+// be processed (e.g. another run already claimed it and its lease has not run out).
+pub async fn process_batch(
+    pool: &PgPool,
+    audit: &AuditContext,
+    batch_id: Uuid,
+) -> Result<Option<PaymentBatch>, CustomerErrorReps> {
+    let claimed = sqlx::query_as!(
+        PaymentBatch,
+        r#"
+        UPDATE payment_batches
+        SET status = 'processing', processing_started_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1
+            AND (status = 'accepted'
+                OR (status = 'processing' AND processing_started_at < CURRENT_TIMESTAMP - make_interval(secs => $2)))
+        RETURNING id, customer_id, debtor_account_id, source_format, execution_mode as "execution_mode: _", message_id, payment_information_id, requested_execution_date, item_count, control_sum, status as "status: _", processed_at, bank_id, branch_id, inserted_at, updated_at
+        "#,
+        batch_id,
+        PROCESSING_LEASE_SECONDS
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let before = match claimed {
+        Some(batch) => batch,
+        None => return Ok(None),
+    };
+
+    let processed = match before.execution_mode {
+        BatchExecutionMode::BestEffort => process_items(pool, audit, &before).await,
+        BatchExecutionMode::AllOrNothing => process_all_or_nothing(pool, audit, &before).await,
+    };
+
+    match processed {
+        Ok(batch) => Ok(Some(batch)),
+        Err(err) => {
+            // Hand the batch back so the next run picks up the items still pending
+            sqlx::query!(
+                r#"
+                UPDATE payment_batches
+                SET status = 'accepted', processing_started_at = NULL, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $1
+                "#,
+                batch_id
+            )
+            .execute(pool)
+            .await?;
+            Err(err)
+        }
+    }
+}
+
+async fn debtor_account_number(pool: &PgPool, batch: &PaymentBatch) -> Result<String, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT account_number FROM accounts WHERE id = $1
+        "#,
+        batch.debtor_account_id
+    )
+    .fetch_one(pool)
+    .await
+}
+
+async fn pending_items(
+    pool: &PgPool,
+    batch: &PaymentBatch,
+) -> Result<Vec<PaymentBatchItem>, sqlx::Error> {
+    Ok(get_items(pool, batch.id)
+        .await?
+        .into_iter()
+        .filter(|item| item.status == PaymentItemStatus::Pending)
+        .collect())
+}
+
+// Best effort: every line is paid on its own and goes through the same screening as a
+// transfer made by itself, so one failing line does not stop the others
+async fn process_items(
+    pool: &PgPool,
+    audit: &AuditContext,
+    batch: &PaymentBatch,
+) -> Result<PaymentBatch, CustomerErrorReps> {
+    let debtor_account_number = debtor_account_number(pool, batch).await?;
+
+    for item in pending_items(pool, batch).await? {
+        let outcome = match pay_item(pool, audit, &debtor_account_number, &item).await {
+            Ok(transfer_id) => ItemOutcome::Completed(transfer_id),
+            Err(err) => item_outcome(err)?,
+        };
+        set_item_outcome(pool, item.id, outcome).await?;
+    }
+
+    let mut transaction = pool.begin().await?;
+    let after = finish_batch(&mut transaction, audit, batch, None).await?;
+    transaction.commit().await?;
+
+    Ok(after)
+}
+
+// All or nothing: every line is screened first and the transfers are then made in one
+// database transaction, so either all lines are paid or none is. A line held for fraud
+// review fails the batch; the review itself stays queued like any other.
+async fn process_all_or_nothing(
+    pool: &PgPool,
+    audit: &AuditContext,
+    batch: &PaymentBatch,
+) -> Result<PaymentBatch, CustomerErrorReps> {
+    let debtor_account_number = debtor_account_number(pool, batch).await?;
+    let items = pending_items(pool, batch).await?;
+
+    for item in &items {
+        // Each line's evaluation is committed so the next line counts it
+        let screened = match fraud::screen(
+            pool,
+            audit,
+            ScreenedOperation::Transfer {
+                sender_account_number: debtor_account_number.clone(),
+                sender_card_number: None,
+                beneficiary_account_number: item.creditor_account_number.clone(),
+                amount: item.amount,
+            },
+            ScreeningContext::default(),
+        )
+        .await
+        {
+            Ok(screening) => screening.commit().await.map_err(Into::into),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = screened {
+            return fail_batch(pool, audit, batch, item, err).await;
+        }
+    }
+
+    let mut transaction = pool.begin().await?;
+    let mut transfers = Vec::with_capacity(items.len());
+    for item in &items {
+        match transfer::transfer_funds(
+            &mut transaction,
+            audit,
+            &debtor_account_number,
+            None,
+            &item.creditor_account_number,
+            item.amount,
+        )
+        .await
+        {
+            Ok(transfer) => transfers.push((item.id, transfer.id)),
+            Err(err) => {
+                transaction.rollback().await?;
+                return fail_batch(pool, audit, batch, item, err).await;
+            }
+        }
+    }
+
+    for (item_id, transfer_id) in transfers {
+        set_item_outcome(
+            &mut transaction,
+            item_id,
+            ItemOutcome::Completed(transfer_id),
+        )
+        .await?;
+    }
+    let after = finish_batch(&mut transaction, audit, batch, None).await?;
+    transaction.commit().await?;
+
+    Ok(after)
+}
+
+// Records why `item` stopped an all-or-nothing batch and turns the whole batch down
+async fn fail_batch(
+    pool: &PgPool,
+    audit: &AuditContext,
+    batch: &PaymentBatch,
+    item: &PaymentBatchItem,
+    err: CustomerErrorReps,
+) -> Result<PaymentBatch, CustomerErrorReps> {
+    let outcome = item_outcome(err)?;
+
+    let mut transaction = pool.begin().await?;
+    set_item_outcome(&mut transaction, item.id, outcome).await?;
+    fail_pending_items(
+        &mut transaction,
+        batch.id,
+        &format!("Not carried out: line {} failed.", item.line_number),
+    )
+    .await?;
+    let after = finish_batch(
+        &mut transaction,
+        audit,
+        batch,
+        Some(PaymentBatchStatus::Rejected),
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(after)
+}
+
+async fn set_item_outcome(
+    executor: impl PgExecutor<'_>,
+    item_id: Uuid,
+    outcome: ItemOutcome,
+) -> Result<(), sqlx::Error> {
+    let (status, reason_code, reason, transfer_id) = match outcome {
+        ItemOutcome::Completed(transfer_id) => {
+            (PaymentItemStatus::Completed, None, None, Some(transfer_id))
+        }
+        ItemOutcome::Held(reason) => (PaymentItemStatus::Held, None, Some(reason), None),
+        ItemOutcome::Failed(code, reason) => (
+            PaymentItemStatus::Failed,
+            Some(code.as_str()),
+            Some(reason),
+            None,
+        ),
+    };
+
+    sqlx::query!(
+        r#"
+        UPDATE payment_batch_items
+        SET status = $1, reason_code = $2, reason = $3, transfer_id = $4, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $5
+        "#,
+        status as PaymentItemStatus,
+        reason_code,
+        reason,
+        transfer_id,
+        item_id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+async fn fail_pending_items(
+    conn: &mut PgConnection,
+    batch_id: Uuid,
+    reason: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE payment_batch_items
+        SET status = 'failed', reason = $2, updated_at = CURRENT_TIMESTAMP
+        WHERE batch_id = $1 AND status = 'pending'
+        "#,
+        batch_id,
+        reason
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+// Settles the batch status from how its items ended, unless `status` is given
+async fn finish_batch(
+    conn: &mut PgConnection,
+    audit: &AuditContext,
+    before: &PaymentBatch,
+    status: Option<PaymentBatchStatus>,
+) -> Result<PaymentBatch, CustomerErrorReps> {
+    let after = sqlx::query_as!(
+        PaymentBatch,
+        r#"
+        UPDATE payment_batches AS b
+        SET status = COALESCE($2, CASE
+                WHEN s.completed = b.item_count THEN 'completed'::paymentbatchstatus
+                WHEN s.completed + s.held = 0 THEN 'rejected'::paymentbatchstatus
+                ELSE 'partially_completed'::paymentbatchstatus
+            END),
+            processed_at = CURRENT_TIMESTAMP,
+            updated_at = CURRENT_TIMESTAMP
+        FROM (
+            SELECT
+                COUNT(*) FILTER (WHERE status = 'completed')::INTEGER AS completed,
+                COUNT(*) FILTER (WHERE status = 'held')::INTEGER AS held
+            FROM payment_batch_items
+            WHERE batch_id = $1
+        ) AS s
+        WHERE b.id = $1
+        RETURNING b.id, b.customer_id, b.debtor_account_id, b.source_format, b.execution_mode as "execution_mode: _", b.message_id, b.payment_information_id, b.requested_execution_date, b.item_count, b.control_sum, b.status as "status: _", b.processed_at, b.bank_id, b.branch_id, b.inserted_at, b.updated_at
+        "#,
+        before.id,
+        status as Option<PaymentBatchStatus>
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    audit::record(
+        conn,
+        audit,
+        Change::new(
+            "payment_batch.process",
+            "payment_batch",
+            after.id,
+            Some(after.bank_id),
+        )
+        .before(before)
+        .after(&after),
+    )
+    .await?;
+
+    Ok(after)
+}
+
+async fn pay_item(
+    pool: &PgPool,
+    audit: &AuditContext,
+    debtor_account_number: &str,
+    item: &PaymentBatchItem,
+) -> Result<Uuid, CustomerErrorReps> {
+    let transfer = transfer::execute_transfer(
+        pool,
+        audit,
+        debtor_account_number,
+        None,
+        &item.creditor_account_number,
+        item.amount,
+    )
+    .await?;
+
+    Ok(transfer.id)
+}
+
+// Processes batches whose requested execution date has arrived, and resumes those a
+// run died while processing
+pub async fn process_due_batches(
+    pool: &PgPool,
+    bank_id: Option<Uuid>,
+    today: NaiveDate,
+) -> Result<usize, CustomerErrorReps> {
+    let audit = AuditContext::system("payment_batches");
+
+    let due = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM payment_batches
+        WHERE (status = 'accepted'
+                OR (status = 'processing' AND processing_started_at < CURRENT_TIMESTAMP - make_interval(secs => $3)))
+            AND requested_execution_date <= $1
+            AND ($2::uuid IS NULL OR bank_id = $2)
+        ORDER BY requested_execution_date, inserted_at
+        "#,
+        today,
+        bank_id,
+        PROCESSING_LEASE_SECONDS
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut processed = 0;
+    for batch_id in due {
+        if process_batch(pool, &audit, batch_id).await?.is_some() {
+            processed += 1;
+        }
+    }
+
+    Ok(processed)
+}
+
+pub async fn get_batch(pool: &PgPool, batch_id: Uuid) -> Result<Option<PaymentBatch>, sqlx::Error> {
+    let batch = sqlx::query_as!(
+        PaymentBatch,
+        r#"
+        SELECT id, customer_id, debtor_account_id, source_format, execution_mode as "execution_mode: _", message_id, payment_information_id, requested_execution_date, item_count, control_sum, status as "status: _", processed_at, bank_id, branch_id, inserted_at, updated_at
+        FROM payment_batches
+        WHERE id = $1
+        "#,
+        batch_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(batch)
+}
+
+pub async fn get_items(
+    pool: &PgPool,
+    batch_id: Uuid,
+) -> Result<Vec<PaymentBatchItem>, sqlx::Error> {
+    let items = sqlx::query_as!(
+        PaymentBatchItem,
+        r#"
+        SELECT id, batch_id, line_number, end_to_end_id, creditor_name, creditor_account_number, amount, remittance_information, status as "status: _", reason_code, reason, transfer_id, inserted_at, updated_at
+        FROM payment_batch_items
+        WHERE batch_id = $1
+        ORDER BY line_number
+        "#,
+        batch_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(items)
+}
@@ -1,8 +1,9 @@
 use chrono::{NaiveDate, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{postgres::PgExecutor, PgPool};
 use uuid::Uuid;
 
+use super::audit::{self, AuditContext, Change};
 use super::types::Status;
 
 #[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
@@ -39,13 +40,15 @@ pub async fn get_by_transaction_id(
 
 pub async fn create_refund(
     pool: &PgPool,
+    audit: &AuditContext,
     transaction_id: Uuid,
     refund_amount: i32,
 ) -> Result<Uuid, sqlx::Error> {
     let id = Uuid::new_v4();
     let current_date = Utc::now().naive_utc().date();
+    let mut transaction = pool.begin().await?;
 
-    sqlx::query!(
+    let refund_id = sqlx::query!(
         r#"
         INSERT INTO refunds (id, transaction_id, refund_amount, refund_date, status, inserted_at, updated_at)
         VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
@@ -57,7 +60,49 @@ pub async fn create_refund(
         current_date,
         Status::Pending as i32, // Assuming you have an enum `Status` with appropriate values
     )
-    .fetch_one(pool)
-    .await
-    .map(|record| record.id)
+    .fetch_one(&mut transaction)
+    .await?
+    .id;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("refund.request", "refund", refund_id, None).after(&serde_json::json!({
+            "transaction_id": transaction_id,
+            "refund_amount": refund_amount,
+        })),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(refund_id)
+}
+
+pub async fn insert_refund(
+    executor: impl PgExecutor<'_>,
+    branch_id: Uuid,
+    bank_id: Uuid,
+    transaction_id: Uuid,
+    refund_amount: i32,
+    status: Status,
+) -> Result<Refund, sqlx::Error> {
+    let refund = sqlx::query_as!(
+        Refund,
+        r#"
+        INSERT INTO refunds (id, transaction_id, refund_amount, refund_date, status, inserted_at, updated_at, bank_id, branch_id)
+        VALUES ($1, $2, $3, CURRENT_DATE, $4, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, $5, $6)
+        RETURNING branch_id, bank_id, id, transaction_id, refund_amount, refund_date, status as "status: _", inserted_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        transaction_id,
+        refund_amount,
+        status as Status,
+        bank_id,
+        branch_id,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(refund)
 }
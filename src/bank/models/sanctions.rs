@@ -0,0 +1,489 @@
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{postgres::PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::bank::helper::validation::CustomerErrorReps;
+
+use super::{
+    audit::{self, AuditContext, Change},
+    types::SanctionsHitStatus,
+};
+
+pub const SUBJECT_CUSTOMER: &str = "customer";
+pub const SUBJECT_BENEFICIARY: &str = "beneficiary";
+
+// Jaro-Winkler score at or above which a name is treated as a potential match
+pub const DEFAULT_MATCH_THRESHOLD: f64 = 0.92;
+
+pub fn match_threshold() -> f64 {
+    std::env::var("SANCTIONS_MATCH_THRESHOLD")
+        .ok()
+        .and_then(|threshold| threshold.parse().ok())
+        .filter(|threshold: &f64| *threshold > 0.0 && *threshold <= 1.0)
+        .unwrap_or(DEFAULT_MATCH_THRESHOLD)
+}
+
+// A listed party as it appears in the watchlist file. In CSV the columns are
+// `id,name,aliases,program`, with aliases separated by semicolons.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WatchlistRecord {
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub program: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct WatchlistCsvRow {
+    id: Option<String>,
+    name: String,
+    aliases: Option<String>,
+    program: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct WatchlistVersion {
+    pub id: Uuid,
+    pub source: String,
+    pub content_hash: String,
+    pub entry_count: i32,
+    pub loaded_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct WatchlistEntry {
+    pub external_id: Option<String>,
+    pub entry_name: String,
+    pub normalized_name: String,
+    pub program: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct SanctionsHit {
+    pub id: Uuid,
+    pub bank_id: Uuid,
+    pub subject_type: String,
+    pub subject_id: Option<Uuid>,
+    pub subject_reference: Option<String>,
+    pub subject_name: String,
+    pub normalized_subject_name: String,
+    pub external_id: Option<String>,
+    pub entry_name: String,
+    pub program: Option<String>,
+    pub score: f64,
+    pub status: SanctionsHitStatus,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<NaiveDateTime>,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// Transliterates to ASCII (so "Müller", "Mueller" and "Мюллер" land close together),
+// lowercases and reduces punctuation to single spaces.
+pub fn normalize_name(name: &str) -> String {
+    deunicode::deunicode(name)
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Scores both the names as given and with their tokens sorted, so a reordered
+// "family name, given name" still matches
+pub fn name_similarity(a: &str, b: &str) -> f64 {
+    let sorted = |name: &str| {
+        let mut tokens: Vec<&str> = name.split(' ').collect();
+        tokens.sort_unstable();
+        tokens.join(" ")
+    };
+
+    strsim::jaro_winkler(a, b).max(strsim::jaro_winkler(&sorted(a), &sorted(b)))
+}
+
+pub fn find_matches<'a>(
+    entries: &'a [WatchlistEntry],
+    normalized_name: &str,
+    threshold: f64,
+) -> Vec<(&'a WatchlistEntry, f64)> {
+    entries
+        .iter()
+        .map(|entry| (entry, name_similarity(normalized_name, &entry.normalized_name)))
+        .filter(|(_, score)| *score >= threshold)
+        .collect()
+}
+
+pub fn parse_watchlist(path: &Path, content: &[u8]) -> Result<Vec<WatchlistRecord>, CustomerErrorReps> {
+    let invalid = |err: String| CustomerErrorReps::InvalidInput(format!("Invalid watchlist: {}", err));
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("json") => serde_json::from_slice(content).map_err(|err| invalid(err.to_string())),
+        Some("csv") => csv::Reader::from_reader(content)
+            .deserialize::<WatchlistCsvRow>()
+            .map(|row| {
+                let row = row.map_err(|err| invalid(err.to_string()))?;
+                Ok(WatchlistRecord {
+                    id: row.id.filter(|id| !id.is_empty()),
+                    name: row.name,
+                    aliases: row
+                        .aliases
+                        .unwrap_or_default()
+                        .split(';')
+                        .map(str::trim)
+                        .filter(|alias| !alias.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                    program: row.program.filter(|program| !program.is_empty()),
+                })
+            })
+            .collect(),
+        _ => Err(invalid("expected a .csv or .json file".to_string())),
+    }
+}
+
+pub async fn current_entries(executor: impl PgExecutor<'_>) -> Result<Vec<WatchlistEntry>, sqlx::Error> {
+    let entries = sqlx::query_as!(
+        WatchlistEntry,
+        r#"
+        SELECT external_id, entry_name, normalized_name, program
+        FROM watchlist_entries
+        WHERE version_id = (SELECT id FROM watchlist_versions ORDER BY loaded_at DESC LIMIT 1)
+        "#
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(entries)
+}
+
+// Replaces the list in force with the file's contents. Returns None when the file is
+// unchanged since the last load.
+pub async fn load_watchlist_file(
+    pool: &PgPool,
+    audit: &AuditContext,
+    path: &Path,
+) -> Result<Option<WatchlistVersion>, CustomerErrorReps> {
+    let content = std::fs::read(path).map_err(|err| {
+        CustomerErrorReps::InvalidInput(format!("Cannot read watchlist {}: {}", path.display(), err))
+    })?;
+    let content_hash = hex::encode(Sha256::digest(&content));
+
+    let current_hash = sqlx::query_scalar!(
+        r#"
+        SELECT content_hash FROM watchlist_versions ORDER BY loaded_at DESC LIMIT 1
+        "#
+    )
+    .fetch_optional(pool)
+    .await?;
+    if current_hash.as_deref() == Some(content_hash.as_str()) {
+        return Ok(None);
+    }
+
+    let records = parse_watchlist(path, &content)?;
+    let entries: Vec<WatchlistEntry> = records
+        .iter()
+        .flat_map(|record| {
+            std::iter::once(&record.name)
+                .chain(record.aliases.iter())
+                .map(move |name| WatchlistEntry {
+                    external_id: record.id.clone(),
+                    entry_name: name.clone(),
+                    normalized_name: normalize_name(name),
+                    program: record.program.clone(),
+                })
+        })
+        .filter(|entry| !entry.normalized_name.is_empty())
+        .collect();
+
+    let mut transaction = pool.begin().await?;
+
+    let version = sqlx::query_as!(
+        WatchlistVersion,
+        r#"
+        INSERT INTO watchlist_versions (id, source, content_hash, entry_count, loaded_at)
+        VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP)
+        RETURNING id, source, content_hash, entry_count, loaded_at
+        "#,
+        Uuid::new_v4(),
+        path.display().to_string(),
+        content_hash,
+        entries.len() as i32
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    for entry in &entries {
+        sqlx::query!(
+            r#"
+            INSERT INTO watchlist_entries (id, version_id, external_id, entry_name, normalized_name, program)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            Uuid::new_v4(),
+            version.id,
+            entry.external_id,
+            entry.entry_name,
+            entry.normalized_name,
+            entry.program
+        )
+        .execute(&mut transaction)
+        .await?;
+    }
+
+    // Older versions are kept for the record; only their entries go
+    sqlx::query!(
+        r#"
+        DELETE FROM watchlist_entries WHERE version_id <> $1
+        "#,
+        version.id
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("watchlist.load", "watchlist_version", version.id, None).after(&version),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(Some(version))
+}
+
+// Screens a name against the list in force and records new hits for review. Matches a
+// reviewer already cleared for the same subject are ignored; `subject_reference`
+// identifies it (a customer's CIC number, a beneficiary's account number) even before
+// it exists. Returns the hits that are still outstanding (pending or confirmed).
+pub async fn screen_name(
+    pool: &PgPool,
+    bank_id: Uuid,
+    subject_type: &str,
+    subject_id: Option<Uuid>,
+    subject_reference: &str,
+    name: &str,
+) -> Result<Vec<SanctionsHit>, sqlx::Error> {
+    let entries = current_entries(pool).await?;
+    screen_against(
+        pool,
+        &entries,
+        match_threshold(),
+        bank_id,
+        subject_type,
+        subject_id,
+        subject_reference,
+        name,
+    )
+    .await
+}
+
+async fn screen_against(
+    pool: &PgPool,
+    entries: &[WatchlistEntry],
+    threshold: f64,
+    bank_id: Uuid,
+    subject_type: &str,
+    subject_id: Option<Uuid>,
+    subject_reference: &str,
+    name: &str,
+) -> Result<Vec<SanctionsHit>, sqlx::Error> {
+    let normalized_name = normalize_name(name);
+    let matches = find_matches(entries, &normalized_name, threshold);
+    if matches.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut transaction = pool.begin().await?;
+    let mut outstanding = Vec::new();
+
+    for (entry, score) in matches {
+        let previous = sqlx::query_as!(
+            SanctionsHit,
+            r#"
+            SELECT id, bank_id, subject_type, subject_id, subject_reference, subject_name, normalized_subject_name, external_id, entry_name, program, score, status as "status: _", reviewed_by, reviewed_at, inserted_at, updated_at
+            FROM sanctions_hits
+            WHERE bank_id = $1 AND subject_type = $2 AND subject_reference = $3
+              AND normalized_subject_name = $4 AND entry_name = $5
+            ORDER BY (status = 'cleared') DESC, inserted_at DESC
+            LIMIT 1
+            "#,
+            bank_id,
+            subject_type,
+            subject_reference,
+            normalized_name,
+            entry.entry_name
+        )
+        .fetch_optional(&mut transaction)
+        .await?;
+
+        match previous {
+            Some(hit) if hit.status == SanctionsHitStatus::Cleared => {}
+            Some(hit) => outstanding.push(hit),
+            None => {
+                let hit = sqlx::query_as!(
+                    SanctionsHit,
+                    r#"
+                    INSERT INTO sanctions_hits (id, bank_id, subject_type, subject_id, subject_reference, subject_name, normalized_subject_name, external_id, entry_name, program, score, status, inserted_at, updated_at)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, 'pending', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+                    RETURNING id, bank_id, subject_type, subject_id, subject_reference, subject_name, normalized_subject_name, external_id, entry_name, program, score, status as "status: _", reviewed_by, reviewed_at, inserted_at, updated_at
+                    "#,
+                    Uuid::new_v4(),
+                    bank_id,
+                    subject_type,
+                    subject_id,
+                    subject_reference,
+                    name,
+                    normalized_name,
+                    entry.external_id,
+                    entry.entry_name,
+                    entry.program,
+                    score
+                )
+                .fetch_one(&mut transaction)
+                .await?;
+                outstanding.push(hit);
+            }
+        }
+    }
+
+    transaction.commit().await?;
+
+    Ok(outstanding)
+}
+
+// Blocks a new customer or beneficiary whose name has an outstanding match. The hit
+// is kept for compliance even though the caller's own change is abandoned.
+pub async fn ensure_not_listed(
+    pool: &PgPool,
+    bank_id: Uuid,
+    subject_type: &str,
+    subject_reference: &str,
+    name: &str,
+) -> Result<(), CustomerErrorReps> {
+    let hits = screen_name(pool, bank_id, subject_type, None, subject_reference, name).await?;
+    match hits.first() {
+        Some(hit) => Err(CustomerErrorReps::SanctionsMatch(hit.id)),
+        None => Ok(()),
+    }
+}
+
+// Re-screens every existing customer and beneficiary against the list in force.
+// Returns how many of them have an outstanding hit.
+pub async fn rescreen_all(pool: &PgPool) -> Result<usize, sqlx::Error> {
+    let entries = current_entries(pool).await?;
+    let threshold = match_threshold();
+
+    let subjects = sqlx::query!(
+        r#"
+        SELECT id, bank_id, customer_name AS "subject_name", 'customer' AS "subject_type!", cic_number AS "subject_reference!"
+        FROM customers
+        UNION ALL
+        SELECT id, bank_id, beneficiary_name AS "subject_name", 'beneficiary' AS "subject_type!", beneficiary_account_number AS "subject_reference!"
+        FROM beneficiaries
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut flagged = 0;
+    for subject in subjects {
+        let hits = screen_against(
+            pool,
+            &entries,
+            threshold,
+            subject.bank_id,
+            &subject.subject_type,
+            Some(subject.id),
+            &subject.subject_reference,
+            &subject.subject_name,
+        )
+        .await?;
+        if !hits.is_empty() {
+            flagged += 1;
+        }
+    }
+
+    Ok(flagged)
+}
+
+// Loads the watchlist if the file changed and, if so, re-screens everyone against it.
+pub async fn refresh_watchlist(pool: &PgPool, path: &Path) -> Result<Option<usize>, CustomerErrorReps> {
+    let audit = AuditContext::system("sanctions");
+    match load_watchlist_file(pool, &audit, path).await? {
+        Some(_) => Ok(Some(rescreen_all(pool).await?)),
+        None => Ok(None),
+    }
+}
+
+pub async fn get_hits(
+    pool: &PgPool,
+    bank_id: Uuid,
+    status: Option<SanctionsHitStatus>,
+) -> Result<Vec<SanctionsHit>, sqlx::Error> {
+    let hits = sqlx::query_as!(
+        SanctionsHit,
+        r#"
+        SELECT id, bank_id, subject_type, subject_id, subject_reference, subject_name, normalized_subject_name, external_id, entry_name, program, score, status as "status: _", reviewed_by, reviewed_at, inserted_at, updated_at
+        FROM sanctions_hits
+        WHERE bank_id = $1 AND ($2::sanctionshitstatus IS NULL OR status = $2)
+        ORDER BY inserted_at DESC
+        "#,
+        bank_id,
+        status as Option<SanctionsHitStatus>
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(hits)
+}
+
+// Confirms a hit as a true match or clears it as a false positive. Cleared matches
+// stop blocking the same subject under that name.
+pub async fn resolve_hit(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    hit_id: Uuid,
+    status: SanctionsHitStatus,
+) -> Result<SanctionsHit, CustomerErrorReps> {
+    if status == SanctionsHitStatus::Pending {
+        return Err(CustomerErrorReps::InvalidInput(
+            "A hit can only be confirmed or cleared.".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    let hit = sqlx::query_as!(
+        SanctionsHit,
+        r#"
+        UPDATE sanctions_hits
+        SET status = $1, reviewed_by = $2, reviewed_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $3 AND bank_id = $4 AND status = 'pending'
+        RETURNING id, bank_id, subject_type, subject_id, subject_reference, subject_name, normalized_subject_name, external_id, entry_name, program, score, status as "status: _", reviewed_by, reviewed_at, inserted_at, updated_at
+        "#,
+        status as SanctionsHitStatus,
+        audit.actor_id,
+        hit_id,
+        bank_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("sanctions_hit.resolve", "sanctions_hit", hit.id, Some(bank_id)).after(&hit),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(hit)
+}
@@ -0,0 +1,569 @@
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::bank::helper::validation::CustomerErrorReps;
+
+use super::{
+    accounts,
+    audit::{self, AuditContext, Change},
+    calendar, transfer,
+    types::{ExecutionStatus, StandingOrderFrequency, StandingOrderStatus},
+};
+
+// Insufficient-funds failures are retried this many times before the occurrence is skipped
+pub const MAX_RETRIES: i32 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct StandingOrder {
+    pub id: Uuid,
+    pub source_account_id: Uuid,
+    pub beneficiary_id: Uuid,
+    pub amount: i32,
+    pub frequency: StandingOrderFrequency,
+    pub cron_expression: Option<String>,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub next_run_date: NaiveDate,
+    pub retry_count: i32,
+    pub next_retry_at: Option<NaiveDateTime>,
+    pub status: StandingOrderStatus,
+    pub bank_id: Uuid,
+    pub branch_id: Uuid,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct StandingOrderExecution {
+    pub id: Uuid,
+    pub standing_order_id: Uuid,
+    pub run_date: NaiveDate,
+    pub attempt: i32,
+    pub status: ExecutionStatus,
+    pub transfer_id: Option<Uuid>,
+    pub failure_reason: Option<String>,
+    pub inserted_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewStandingOrder {
+    pub source_account_id: Uuid,
+    pub beneficiary_id: Uuid,
+    pub amount: i32,
+    pub frequency: StandingOrderFrequency,
+    pub cron_expression: Option<String>,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+}
+
+// Date-level cron schedule: "day-of-month month day-of-week". Each field accepts
+// `*`, `N`, `N-M`, lists separated by `,` and steps like `*/2` or `1-15/7`.
+// Day of week is 0-6 starting on Sunday (7 is also Sunday).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateCron {
+    days_of_month: Option<Vec<u32>>,
+    months: Option<Vec<u32>>,
+    days_of_week: Option<Vec<u32>>,
+}
+
+impl DateCron {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 3 {
+            return Err("Cron expression must have 3 fields: day-of-month month day-of-week.".to_string());
+        }
+
+        let days_of_week = parse_cron_field(fields[2], 0, 7)?
+            .map(|days| days.into_iter().map(|d| d % 7).collect());
+
+        Ok(Self {
+            days_of_month: parse_cron_field(fields[0], 1, 31)?,
+            months: parse_cron_field(fields[1], 1, 12)?,
+            days_of_week,
+        })
+    }
+
+    pub fn matches(&self, date: NaiveDate) -> bool {
+        if let Some(months) = &self.months {
+            if !months.contains(&date.month()) {
+                return false;
+            }
+        }
+
+        let dom = self
+            .days_of_month
+            .as_ref()
+            .map(|days| days.contains(&date.day()));
+        let dow = self
+            .days_of_week
+            .as_ref()
+            .map(|days| days.contains(&date.weekday().num_days_from_sunday()));
+
+        // Like cron, a restricted day-of-month and day-of-week match if either does
+        match (dom, dow) {
+            (Some(dom), Some(dow)) => dom || dow,
+            (Some(dom), None) => dom,
+            (None, Some(dow)) => dow,
+            (None, None) => true,
+        }
+    }
+
+    // First matching date on or after `from`, looking at most four years ahead.
+    pub fn next_on_or_after(&self, from: NaiveDate) -> Option<NaiveDate> {
+        (0..366 * 4)
+            .map(|offset| from + Duration::days(offset))
+            .find(|date| self.matches(*date))
+    }
+}
+
+fn parse_cron_field(field: &str, min: u32, max: u32) -> Result<Option<Vec<u32>>, String> {
+    if field == "*" {
+        return Ok(None);
+    }
+
+    let mut values = Vec::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("Invalid step in cron field '{}'.", field))?;
+                if step == 0 {
+                    return Err(format!("Invalid step in cron field '{}'.", field));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                start
+                    .parse()
+                    .map_err(|_| format!("Invalid value in cron field '{}'.", field))?,
+                end.parse()
+                    .map_err(|_| format!("Invalid value in cron field '{}'.", field))?,
+            )
+        } else {
+            let value: u32 = range
+                .parse()
+                .map_err(|_| format!("Invalid value in cron field '{}'.", field))?;
+            (value, value)
+        };
+
+        if start < min || end > max || start > end {
+            return Err(format!(
+                "Cron field '{}' must be within {}-{}.",
+                field, min, max
+            ));
+        }
+
+        values.extend((start..=end).step_by(step as usize));
+    }
+
+    values.sort_unstable();
+    values.dedup();
+    Ok(Some(values))
+}
+
+pub fn first_run_date(
+    frequency: &StandingOrderFrequency,
+    cron_expression: Option<&str>,
+    start_date: NaiveDate,
+) -> Option<NaiveDate> {
+    match frequency {
+        StandingOrderFrequency::Cron => DateCron::parse(cron_expression?)
+            .ok()?
+            .next_on_or_after(start_date),
+        _ => Some(start_date),
+    }
+}
+
+// Next occurrence strictly after `after`. Monthly orders keep the start date's day of
+// month, falling back to the last day of shorter months.
+pub fn next_run_date(
+    frequency: &StandingOrderFrequency,
+    cron_expression: Option<&str>,
+    start_date: NaiveDate,
+    after: NaiveDate,
+) -> Option<NaiveDate> {
+    match frequency {
+        StandingOrderFrequency::Daily => Some(after + Duration::days(1)),
+        StandingOrderFrequency::Weekly => Some(after + Duration::days(7)),
+        StandingOrderFrequency::Monthly => {
+            let elapsed = (after.year() - start_date.year()) * 12 + after.month() as i32
+                - start_date.month() as i32;
+            (elapsed.max(0) as u32..)
+                .map(|months| start_date.checked_add_months(Months::new(months)))
+                .find(|date| date.map_or(true, |date| date > after))
+                .flatten()
+        }
+        StandingOrderFrequency::Cron => DateCron::parse(cron_expression?)
+            .ok()?
+            .next_on_or_after(after + Duration::days(1)),
+    }
+}
+
+// Wait before retrying a failed run: 30 minutes, then 1 hour, then 2 hours...
+pub fn retry_backoff(attempt: i32) -> Duration {
+    Duration::minutes(30 * 2i64.pow(attempt.clamp(1, 10) as u32 - 1))
+}
+
+pub async fn create_standing_order(
+    pool: &PgPool,
+    audit: &AuditContext,
+    new_order: NewStandingOrder,
+) -> Result<StandingOrder, CustomerErrorReps> {
+    if new_order.amount <= 0 {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Amount must be greater than 0.".to_string(),
+        ));
+    }
+    if let Some(end_date) = new_order.end_date {
+        if end_date < new_order.start_date {
+            return Err(CustomerErrorReps::InvalidInput(
+                "End date cannot be before the start date.".to_string(),
+            ));
+        }
+    }
+    match (&new_order.frequency, &new_order.cron_expression) {
+        (StandingOrderFrequency::Cron, Some(expression)) => {
+            DateCron::parse(expression).map_err(CustomerErrorReps::InvalidInput)?;
+        }
+        (StandingOrderFrequency::Cron, None) => {
+            return Err(CustomerErrorReps::InvalidInput(
+                "A cron expression is required for cron standing orders.".to_string(),
+            ));
+        }
+        (_, Some(_)) => {
+            return Err(CustomerErrorReps::InvalidInput(
+                "A cron expression is only allowed for cron standing orders.".to_string(),
+            ));
+        }
+        (_, None) => {}
+    }
+
+    let account = accounts::get_by_id(pool, new_order.source_account_id)
+        .await?
+        .ok_or(CustomerErrorReps::NotFound)?;
+
+    let beneficiary_belongs_to_customer = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1
+            FROM beneficiaries AS b
+            INNER JOIN account_holders AS h ON h.customer_id = b.customer_id
+            WHERE b.id = $1 AND h.account_id = $2 AND h.role IN ('primary', 'joint')
+        ) AS "exists!"
+        "#,
+        new_order.beneficiary_id,
+        account.id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if !beneficiary_belongs_to_customer {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Beneficiary is not registered for this customer.".to_string(),
+        ));
+    }
+
+    let next_run_date = first_run_date(
+        &new_order.frequency,
+        new_order.cron_expression.as_deref(),
+        new_order.start_date,
+    )
+    .filter(|date| new_order.end_date.map_or(true, |end_date| *date <= end_date))
+    .ok_or_else(|| {
+        CustomerErrorReps::InvalidInput("Schedule has no run before its end date.".to_string())
+    })?;
+
+    let mut transaction = pool.begin().await?;
+
+    let standing_order = sqlx::query_as!(
+        StandingOrder,
+        r#"
+        INSERT INTO standing_orders (id, source_account_id, beneficiary_id, amount, frequency, cron_expression, start_date, end_date, next_run_date, retry_count, next_retry_at, status, bank_id, branch_id, inserted_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, 0, NULL, 'active', $10, $11, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        RETURNING id, source_account_id, beneficiary_id, amount, frequency as "frequency: _", cron_expression, start_date, end_date, next_run_date, retry_count, next_retry_at, status as "status: _", bank_id, branch_id, inserted_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        account.id,
+        new_order.beneficiary_id,
+        new_order.amount,
+        new_order.frequency as StandingOrderFrequency,
+        new_order.cron_expression,
+        new_order.start_date,
+        new_order.end_date,
+        next_run_date,
+        account.bank_id,
+        account.branch_id,
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("standing_order.create", "standing_order", standing_order.id, Some(standing_order.bank_id))
+            .after(&standing_order),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(standing_order)
+}
+
+pub async fn get_standing_order(
+    pool: &PgPool,
+    standing_order_id: Uuid,
+) -> Result<Option<StandingOrder>, sqlx::Error> {
+    let standing_order = sqlx::query_as!(
+        StandingOrder,
+        r#"
+        SELECT id, source_account_id, beneficiary_id, amount, frequency as "frequency: _", cron_expression, start_date, end_date, next_run_date, retry_count, next_retry_at, status as "status: _", bank_id, branch_id, inserted_at, updated_at
+        FROM standing_orders
+        WHERE id = $1
+        "#,
+        standing_order_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(standing_order)
+}
+
+pub async fn cancel_standing_order(
+    pool: &PgPool,
+    audit: &AuditContext,
+    standing_order_id: Uuid,
+) -> Result<StandingOrder, CustomerErrorReps> {
+    let mut transaction = pool.begin().await?;
+
+    let standing_order = sqlx::query_as!(
+        StandingOrder,
+        r#"
+        UPDATE standing_orders
+        SET status = 'cancelled', updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND status IN ('active', 'paused')
+        RETURNING id, source_account_id, beneficiary_id, amount, frequency as "frequency: _", cron_expression, start_date, end_date, next_run_date, retry_count, next_retry_at, status as "status: _", bank_id, branch_id, inserted_at, updated_at
+        "#,
+        standing_order_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("standing_order.cancel", "standing_order", standing_order.id, Some(standing_order.bank_id))
+            .after(&standing_order),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(standing_order)
+}
+
+pub async fn get_executions(
+    pool: &PgPool,
+    standing_order_id: Uuid,
+) -> Result<Vec<StandingOrderExecution>, sqlx::Error> {
+    let executions = sqlx::query_as!(
+        StandingOrderExecution,
+        r#"
+        SELECT id, standing_order_id, run_date, attempt, status as "status: _", transfer_id, failure_reason, inserted_at
+        FROM standing_order_executions
+        WHERE standing_order_id = $1
+        ORDER BY inserted_at DESC
+        "#,
+        standing_order_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(executions)
+}
+
+// Executes every standing order that is due at `now`, one occurrence per order.
+// Orders that fell behind catch up on the following runs.
+pub async fn run_due_standing_orders(
+    pool: &PgPool,
+    bank_id: Option<Uuid>,
+    now: NaiveDateTime,
+) -> Result<u64, sqlx::Error> {
+    let due_ids: Vec<Uuid> = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM standing_orders
+        WHERE status = 'active' AND next_run_date <= $1 AND (next_retry_at IS NULL OR next_retry_at <= $2)
+            AND ($3::uuid IS NULL OR bank_id = $3)
+        ORDER BY next_run_date
+        "#,
+        now.date(),
+        now,
+        bank_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let audit = AuditContext::system("standing_orders");
+    let mut executed = 0;
+    for standing_order_id in due_ids {
+        let mut transaction = pool.begin().await?;
+
+        // Another scheduler instance may already be running this order
+        let standing_order = sqlx::query_as!(
+            StandingOrder,
+            r#"
+            SELECT id, source_account_id, beneficiary_id, amount, frequency as "frequency: _", cron_expression, start_date, end_date, next_run_date, retry_count, next_retry_at, status as "status: _", bank_id, branch_id, inserted_at, updated_at
+            FROM standing_orders
+            WHERE id = $1 AND status = 'active' AND next_run_date <= $2 AND (next_retry_at IS NULL OR next_retry_at <= $3)
+            FOR UPDATE SKIP LOCKED
+            "#,
+            standing_order_id,
+            now.date(),
+            now
+        )
+        .fetch_optional(&mut transaction)
+        .await?;
+
+        let standing_order = match standing_order {
+            Some(standing_order) => standing_order,
+            None => continue,
+        };
+
+        // Orders falling due on a weekend or holiday run on the next business day
+        let calendar = calendar::load(&mut transaction, standing_order.bank_id).await?;
+        if !calendar.is_business_day(now.date()) {
+            continue;
+        }
+
+        let parties = sqlx::query!(
+            r#"
+            SELECT a.account_number, b.beneficiary_account_number
+            FROM accounts AS a, beneficiaries AS b
+            WHERE a.id = $1 AND b.id = $2
+            "#,
+            standing_order.source_account_id,
+            standing_order.beneficiary_id
+        )
+        .fetch_one(&mut transaction)
+        .await?;
+
+        // Run the transfer in a savepoint so a failure can still be recorded
+        let mut savepoint = transaction.begin().await?;
+        let result = transfer::transfer_funds(
+            &mut savepoint,
+            &audit,
+            &parties.account_number,
+            None,
+            &parties.beneficiary_account_number,
+            standing_order.amount,
+        )
+        .await;
+        let (transfer_id, failure) = match result {
+            Ok(transfer) => {
+                savepoint.commit().await?;
+                (Some(transfer.id), None)
+            }
+            Err(err) => {
+                savepoint.rollback().await?;
+                (None, Some(err))
+            }
+        };
+
+        let attempt = standing_order.retry_count + 1;
+        let status = if failure.is_none() {
+            ExecutionStatus::Succeeded
+        } else {
+            ExecutionStatus::Failed
+        };
+        let failure_reason = failure
+            .as_ref()
+            .map(|err| err.to_string().chars().take(255).collect::<String>());
+
+        let execution = sqlx::query_as!(
+            StandingOrderExecution,
+            r#"
+            INSERT INTO standing_order_executions (id, standing_order_id, run_date, attempt, status, transfer_id, failure_reason, inserted_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, CURRENT_TIMESTAMP)
+            RETURNING id, standing_order_id, run_date, attempt, status as "status: _", transfer_id, failure_reason, inserted_at
+            "#,
+            Uuid::new_v4(),
+            standing_order.id,
+            standing_order.next_run_date,
+            attempt,
+            status as ExecutionStatus,
+            transfer_id,
+            failure_reason,
+        )
+        .fetch_one(&mut transaction)
+        .await?;
+
+        audit::record(
+            &mut transaction,
+            &audit,
+            Change::new("standing_order.execute", "standing_order", standing_order.id, Some(standing_order.bank_id))
+                .before(&standing_order)
+                .after(&execution),
+        )
+        .await?;
+
+        let retry = matches!(failure, Some(CustomerErrorReps::InsufficientFunds))
+            && standing_order.retry_count < MAX_RETRIES;
+
+        if retry {
+            sqlx::query!(
+                r#"
+                UPDATE standing_orders
+                SET retry_count = $1, next_retry_at = $2, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $3
+                "#,
+                attempt,
+                now + retry_backoff(attempt),
+                standing_order.id
+            )
+            .execute(&mut transaction)
+            .await?;
+        } else {
+            // Succeeded, or gave up on this occurrence: move on to the next one
+            let next_run_date = next_run_date(
+                &standing_order.frequency,
+                standing_order.cron_expression.as_deref(),
+                standing_order.start_date,
+                standing_order.next_run_date,
+            )
+            .filter(|date| standing_order.end_date.map_or(true, |end_date| *date <= end_date));
+
+            let status = match next_run_date {
+                Some(_) => StandingOrderStatus::Active,
+                None => StandingOrderStatus::Completed,
+            };
+
+            sqlx::query!(
+                r#"
+                UPDATE standing_orders
+                SET next_run_date = $1, status = $2, retry_count = 0, next_retry_at = NULL, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $3
+                "#,
+                next_run_date.unwrap_or(standing_order.next_run_date),
+                status as StandingOrderStatus,
+                standing_order.id
+            )
+            .execute(&mut transaction)
+            .await?;
+        }
+
+        transaction.commit().await?;
+        executed += 1;
+    }
+
+    Ok(executed)
+}
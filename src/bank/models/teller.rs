@@ -0,0 +1,257 @@
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::bank::helper::validation::CustomerErrorReps;
+
+use super::{
+    accounts,
+    audit::{self, AuditContext, Change},
+    branchs,
+    calendar,
+    cards,
+    fees,
+    fraud::{self, ScreenedOperation, ScreeningContext},
+    overdraft,
+    treasury,
+    transactions::{self, Transaction},
+    types::{AccountStatus, CardStatus, CardType, EntryDirection, Status, TransactionType},
+};
+
+pub async fn cash_deposit(
+    pool: &PgPool,
+    audit: &AuditContext,
+    branch_id: Uuid,
+    account_number: &str,
+    amount: i32,
+) -> Result<Transaction, CustomerErrorReps> {
+    let mut transaction = pool.begin().await?;
+
+    let posted = move_cash(
+        &mut transaction,
+        audit,
+        branch_id,
+        account_number,
+        None,
+        amount,
+        TransactionType::CashDeposit,
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(posted)
+}
+
+pub async fn cash_withdrawal(
+    pool: &PgPool,
+    audit: &AuditContext,
+    branch_id: Uuid,
+    account_number: &str,
+    amount: i32,
+) -> Result<Transaction, CustomerErrorReps> {
+    screened_withdrawal(pool, audit, branch_id, account_number, None, amount).await
+}
+
+// Cash taken with a debit card from an ATM of `bank_id` at one of its branches. The ATM
+// has checked the card and PIN; cards of other banks are not found. ATM fees apply
+// rather than the transaction fee for cash withdrawals.
+pub async fn atm_withdrawal(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    branch_id: Uuid,
+    card_number: &str,
+    amount: i32,
+) -> Result<Transaction, CustomerErrorReps> {
+    let card = cards::get_by_card_number(pool, card_number)
+        .await?
+        .filter(|card| card.bank_id == bank_id)
+        .ok_or(CustomerErrorReps::NotFound)?;
+    if card.card_type != CardType::Debit || card.card_status != CardStatus::Active {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Card cannot be used for cash withdrawals.".to_string(),
+        ));
+    }
+
+    screened_withdrawal(
+        pool,
+        audit,
+        branch_id,
+        &card.account_number,
+        Some(card_number),
+        amount,
+    )
+    .await
+}
+
+async fn screened_withdrawal(
+    pool: &PgPool,
+    audit: &AuditContext,
+    branch_id: Uuid,
+    account_number: &str,
+    card_number: Option<&str>,
+    amount: i32,
+) -> Result<Transaction, CustomerErrorReps> {
+    ensure_positive(amount)?;
+
+    let mut transaction = fraud::screen(
+        pool,
+        audit,
+        ScreenedOperation::CashWithdrawal {
+            branch_id,
+            account_number: account_number.to_string(),
+            card_number: card_number.map(str::to_string),
+            amount,
+        },
+        ScreeningContext::default(),
+    )
+    .await?;
+
+    let posted =
+        post_withdrawal(&mut transaction, audit, branch_id, account_number, card_number, amount)
+            .await?;
+
+    transaction.commit().await?;
+
+    Ok(posted)
+}
+
+// Pays out a withdrawal that has already been screened, on the caller's transaction
+pub async fn post_withdrawal(
+    conn: &mut PgConnection,
+    audit: &AuditContext,
+    branch_id: Uuid,
+    account_number: &str,
+    card_number: Option<&str>,
+    amount: i32,
+) -> Result<Transaction, CustomerErrorReps> {
+    move_cash(
+        conn,
+        audit,
+        branch_id,
+        account_number,
+        card_number,
+        amount,
+        TransactionType::CashWithdrawal,
+    )
+    .await
+}
+
+fn ensure_positive(amount: i32) -> Result<(), CustomerErrorReps> {
+    if amount <= 0 {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Amount must be greater than 0.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+async fn move_cash(
+    conn: &mut PgConnection,
+    audit: &AuditContext,
+    branch_id: Uuid,
+    account_number: &str,
+    card_number: Option<&str>,
+    amount: i32,
+    transaction_type: TransactionType,
+) -> Result<Transaction, CustomerErrorReps> {
+    ensure_positive(amount)?;
+
+    // Lock the account first, as screening does, then the branch vault so tellers at the
+    // same branch serialize
+    let account = sqlx::query!(
+        r#"
+        SELECT id, bank_id, branch_id, balance, overdraft_limit, status as "status: AccountStatus" FROM accounts WHERE account_number = $1 FOR UPDATE
+        "#,
+        account_number
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    let branch = sqlx::query!(
+        r#"
+        SELECT bank_id, total_money FROM branches WHERE id = $1 FOR UPDATE
+        "#,
+        branch_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(CustomerErrorReps::BranchNotFound)?;
+
+    if account.bank_id != branch.bank_id {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Account does not belong to this bank.".to_string(),
+        ));
+    }
+
+    let delta = match transaction_type {
+        TransactionType::CashWithdrawal => {
+            accounts::ensure_can_debit(account.status)?;
+            accounts::ensure_funds(account.balance, account.overdraft_limit, amount)?;
+            if branch.total_money < amount {
+                return Err(CustomerErrorReps::InsufficientBranchCash(branch.total_money));
+            }
+            // Deposits only shrink at this branch if the account is held here
+            let deposit_reduction = if account.branch_id == branch_id { amount } else { 0 };
+            treasury::ensure_reserve(&mut *conn, branch_id, amount, deposit_reduction).await?;
+            -amount
+        }
+        _ => {
+            accounts::ensure_can_credit(account.status)?;
+            amount
+        }
+    };
+
+    sqlx::query!(
+        r#"
+        UPDATE accounts
+        SET balance = balance + $1, last_updated_date = CURRENT_DATE, last_activity_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $2
+        "#,
+        delta,
+        account.id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    branchs::update_total_money_on_deposit(&mut *conn, branch_id, delta).await?;
+
+    let direction = if delta < 0 {
+        EntryDirection::Debit
+    } else {
+        EntryDirection::Credit
+    };
+
+    let calendar = calendar::load(&mut *conn, branch.bank_id).await?;
+    let posted = transactions::insert_transaction(
+        &mut *conn,
+        &calendar,
+        branch_id,
+        branch.bank_id,
+        account_number,
+        card_number,
+        transaction_type,
+        direction,
+        amount,
+        Status::Approved,
+    )
+    .await?;
+
+    fees::charge_transaction_fee(&mut *conn, audit, &calendar, &posted).await?;
+    overdraft::notify_if_overdrawn(&mut *conn, account.id, account.balance).await?;
+
+    let action = match (delta < 0, card_number) {
+        (true, Some(_)) => "atm.cash_withdrawal",
+        (true, None) => "teller.cash_withdrawal",
+        (false, _) => "teller.cash_deposit",
+    };
+    audit::record(
+        &mut *conn,
+        audit,
+        Change::new(action, "transaction", posted.id, Some(posted.bank_id)).after(&posted),
+    )
+    .await?;
+
+    Ok(posted)
+}
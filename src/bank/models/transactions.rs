@@ -1,8 +1,12 @@
 use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgExecutor, PgConnection};
 use uuid::Uuid;
 
-use super::types::{Status, TransactionType};
+use super::{
+    calendar::BusinessCalendar,
+    types::{EntryDirection, Status, TransactionType},
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
 pub struct Transaction {
@@ -11,10 +15,77 @@ pub struct Transaction {
     pub id: Uuid,
     pub account_number: String,
     pub transaction_type: TransactionType,
-    pub card_number: String,
+    pub direction: EntryDirection,
+    pub card_number: Option<String>,
     pub amount: i32,
     pub transaction_date: NaiveDate,
+    pub value_date: NaiveDate,
     pub status: Status,
     pub inserted_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
+
+// Books the entry today. Its value date is today when that is a business day of the bank
+// and the cut-off has not passed, the next business day otherwise. `calendar` is the
+// bank's, loaded once by the operation making the posting.
+pub async fn insert_transaction(
+    conn: &mut PgConnection,
+    calendar: &BusinessCalendar,
+    branch_id: Uuid,
+    bank_id: Uuid,
+    account_number: &str,
+    card_number: Option<&str>,
+    transaction_type: TransactionType,
+    direction: EntryDirection,
+    amount: i32,
+    status: Status,
+) -> Result<Transaction, sqlx::Error> {
+    let id = Uuid::new_v4();
+    let now = chrono::Utc::now().naive_utc();
+    let transaction_date = now.date();
+    let value_date = calendar.value_date(now);
+
+    let transaction = sqlx::query_as!(
+        Transaction,
+        r#"
+        INSERT INTO transactions (branch_id, bank_id, id, account_number, transaction_type, direction, card_number, amount, transaction_date, value_date, status, inserted_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        RETURNING branch_id, bank_id, id, account_number, transaction_type as "transaction_type: _", direction as "direction: _", card_number, amount, transaction_date, value_date, status as "status: _", inserted_at, updated_at
+        "#,
+        branch_id,
+        bank_id,
+        id,
+        account_number,
+        transaction_type as TransactionType,
+        direction as EntryDirection,
+        card_number,
+        amount,
+        transaction_date,
+        value_date,
+        status as Status,
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(transaction)
+}
+
+pub async fn get_by_account_number(
+    executor: impl PgExecutor<'_>,
+    account_number: &str,
+) -> Result<Vec<Transaction>, sqlx::Error> {
+    let transactions = sqlx::query_as!(
+        Transaction,
+        r#"
+        SELECT branch_id, bank_id, id, account_number, transaction_type as "transaction_type: _", direction as "direction: _", card_number, amount, transaction_date, value_date, status as "status: _", inserted_at, updated_at
+        FROM transactions
+        WHERE account_number = $1
+        ORDER BY inserted_at DESC
+        "#,
+        account_number
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(transactions)
+}
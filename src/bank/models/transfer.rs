@@ -1,16 +1,279 @@
-use chrono::{NaiveDate, NaiveDateTime}
-;use serde::{Deserialize, Serialize};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
 use uuid::Uuid;
 
+use crate::bank::helper::validation::CustomerErrorReps;
+
+use super::{
+    accounts,
+    audit::{self, AuditContext, Change},
+    calendar,
+    clearing,
+    fees,
+    fraud::{self, ScreenedOperation, ScreeningContext},
+    iban,
+    outbox::{self, DomainEvent},
+    overdraft,
+    transactions,
+    types::{AccountStatus, EntryDirection, Status, TransactionType},
+};
+
 #[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
 pub struct Transfer {
     pub branch_id: Uuid,
     pub bank_id: Uuid,
     pub id: Uuid,
-    pub sender_card_number: String,
+    pub sender_account_number: String,
+    pub sender_card_number: Option<String>,
     pub beneficiary_account_number: String,
     pub amount: i32,
     pub transfer_date: NaiveDate,
     pub inserted_at: NaiveDateTime,
-    pub updated_at: NaiveDateTime
+    pub updated_at: NaiveDateTime,
+}
+
+pub async fn execute_transfer(
+    pool: &PgPool,
+    audit: &AuditContext,
+    sender_account_number: &str,
+    sender_card_number: Option<&str>,
+    beneficiary_account_number: &str,
+    amount: i32,
+) -> Result<Transfer, CustomerErrorReps> {
+    let sender_account_number = &iban::resolve_account_number(pool, sender_account_number).await?;
+    let beneficiary_account_number =
+        &iban::resolve_account_number(pool, beneficiary_account_number).await?;
+
+    let mut transaction = fraud::screen(
+        pool,
+        audit,
+        ScreenedOperation::Transfer {
+            sender_account_number: sender_account_number.to_string(),
+            sender_card_number: sender_card_number.map(str::to_string),
+            beneficiary_account_number: beneficiary_account_number.to_string(),
+            amount,
+        },
+        ScreeningContext::default(),
+    )
+    .await?;
+
+    let transfer = transfer_funds(
+        &mut transaction,
+        audit,
+        sender_account_number,
+        sender_card_number,
+        beneficiary_account_number,
+        amount,
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(transfer)
+}
+
+// Moves `amount` from the sender's account to one of the sender's beneficiaries.
+// Callers are responsible for fraud screening; standing orders, which the customer
+// set up in advance, are not screened.
+// When the beneficiary account is held at the sender's bank it is credited in the same
+// transaction; accounts at our other banks go through interbank clearing. Accounts held
+// at none of our banks fail with `NotFound`.
+pub async fn transfer_funds(
+    conn: &mut PgConnection,
+    audit: &AuditContext,
+    sender_account_number: &str,
+    sender_card_number: Option<&str>,
+    beneficiary_account_number: &str,
+    amount: i32,
+) -> Result<Transfer, CustomerErrorReps> {
+    if amount <= 0 {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Transfer amount must be greater than 0.".to_string(),
+        ));
+    }
+    if sender_account_number == beneficiary_account_number {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Cannot transfer to the same account.".to_string(),
+        ));
+    }
+
+    let sender = sqlx::query!(
+        r#"
+        SELECT id, bank_id, branch_id, balance, overdraft_limit, status as "status: AccountStatus"
+        FROM accounts
+        WHERE account_number = $1
+        FOR UPDATE
+        "#,
+        sender_account_number
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    accounts::ensure_can_debit(sender.status)?;
+
+    if let Some(card_number) = sender_card_number {
+        let card_on_account = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM cards WHERE card_number = $1 AND account_number = $2
+            ) AS "exists!"
+            "#,
+            card_number,
+            sender_account_number
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+
+        if !card_on_account {
+            return Err(CustomerErrorReps::InvalidInput(
+                "Card does not belong to the sending account.".to_string(),
+            ));
+        }
+    }
+
+    // The owners' registered beneficiaries can be paid from a shared account; authorized
+    // signers act for the owners and cannot pay their own
+    let is_beneficiary = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1
+            FROM beneficiaries AS b
+            INNER JOIN account_holders AS h ON h.customer_id = b.customer_id
+            WHERE h.account_id = $1 AND h.role IN ('primary', 'joint')
+                AND b.beneficiary_account_number = $2
+        ) AS "exists!"
+        "#,
+        sender.id,
+        beneficiary_account_number
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if !is_beneficiary {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Beneficiary is not registered for this customer.".to_string(),
+        ));
+    }
+
+    accounts::ensure_funds(sender.balance, sender.overdraft_limit, amount)?;
+
+    // Only accounts held at one of our banks can be paid. Anything else fails before the
+    // sender is debited, as there is nowhere to send the money.
+    let creditor_bank_id = sqlx::query_scalar!(
+        r#"
+        SELECT bank_id FROM accounts WHERE account_number = $1
+        "#,
+        beneficiary_account_number
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    sqlx::query!(
+        r#"
+        UPDATE accounts
+        SET balance = balance - $1, last_updated_date = CURRENT_DATE, last_activity_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $2
+        "#,
+        amount,
+        sender.id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // The recipient, if held here, is at the same bank and follows the same calendar
+    let calendar = calendar::load(&mut *conn, sender.bank_id).await?;
+    let debit = transactions::insert_transaction(
+        &mut *conn,
+        &calendar,
+        sender.branch_id,
+        sender.bank_id,
+        sender_account_number,
+        sender_card_number,
+        TransactionType::P2P,
+        EntryDirection::Debit,
+        amount,
+        Status::Approved,
+    )
+    .await?;
+
+    fees::charge_transaction_fee(&mut *conn, audit, &calendar, &debit).await?;
+    overdraft::notify_if_overdrawn(&mut *conn, sender.id, sender.balance).await?;
+
+    let recipient = sqlx::query!(
+        r#"
+        UPDATE accounts
+        SET balance = balance + $1, last_updated_date = CURRENT_DATE, updated_at = CURRENT_TIMESTAMP
+        WHERE account_number = $2 AND bank_id = $3
+        RETURNING bank_id, branch_id, status as "status: AccountStatus"
+        "#,
+        amount,
+        beneficiary_account_number,
+        sender.bank_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    // Accounts at another bank are credited when the clearing cycle settles
+    let clearing_bank_id = match recipient {
+        Some(recipient) => {
+            accounts::ensure_can_credit(recipient.status)?;
+            transactions::insert_transaction(
+                &mut *conn,
+                &calendar,
+                recipient.branch_id,
+                recipient.bank_id,
+                beneficiary_account_number,
+                None,
+                TransactionType::P2P,
+                EntryDirection::Credit,
+                amount,
+                Status::Approved,
+            )
+            .await?;
+            None
+        }
+        None => Some(creditor_bank_id),
+    };
+
+    let transfer = sqlx::query_as!(
+        Transfer,
+        r#"
+        INSERT INTO transfers (id, sender_account_number, sender_card_number, beneficiary_account_number, amount, transfer_date, inserted_at, updated_at, bank_id, branch_id)
+        VALUES ($1, $2, $3, $4, $5, CURRENT_DATE, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, $6, $7)
+        RETURNING branch_id, bank_id, id, sender_account_number, sender_card_number, beneficiary_account_number, amount, transfer_date, inserted_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        sender_account_number,
+        sender_card_number,
+        beneficiary_account_number,
+        amount,
+        sender.bank_id,
+        sender.branch_id,
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    audit::record(
+        &mut *conn,
+        audit,
+        Change::new("transfer.execute", "transfer", transfer.id, Some(transfer.bank_id))
+            .after(&transfer),
+    )
+    .await?;
+
+    if let Some(clearing_bank_id) = clearing_bank_id {
+        clearing::enqueue(&mut *conn, &transfer, clearing_bank_id).await?;
+    }
+
+    outbox::publish(
+        conn,
+        Some(transfer.bank_id),
+        &DomainEvent::TransferCompleted(transfer.clone()),
+    )
+    .await?;
+
+    Ok(transfer)
 }
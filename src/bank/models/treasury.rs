@@ -0,0 +1,388 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::bank::helper::validation::CustomerErrorReps;
+
+use super::{
+    audit::{self, AuditContext, Change},
+    calendar,
+    types::Status,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct BranchCashTransfer {
+    pub id: Uuid,
+    pub bank_id: Uuid,
+    pub from_branch_id: Uuid,
+    pub to_branch_id: Uuid,
+    pub amount: i32,
+    pub inserted_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct BranchCashPosition {
+    pub id: Uuid,
+    pub bank_id: Uuid,
+    pub branch_id: Uuid,
+    pub business_date: NaiveDate,
+    pub total_money: i32,
+    pub customer_deposits: i64,
+    pub required_reserve: i64,
+    pub inserted_at: NaiveDateTime,
+}
+
+// A loan paid out of the branch vault on approval
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoanApproval {
+    pub loan_id: Uuid,
+    pub bank_id: Uuid,
+    pub branch_id: Uuid,
+    pub amount: i32,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub status: Status,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReserveCoverage {
+    pub branch_id: Uuid,
+    pub branch_name: String,
+    pub total_money: i32,
+    pub customer_deposits: i64,
+    pub required_reserve: i64,
+    pub coverage_bps: Option<i64>,
+    pub meets_minimum: bool,
+}
+
+pub fn required_reserve(customer_deposits: i64, reserve_ratio_bps: i32) -> i64 {
+    customer_deposits.max(0) * reserve_ratio_bps as i64 / 10_000
+}
+
+// Vault cash as a share of customer deposits, in basis points. `None` when the
+// branch holds no deposits.
+pub fn coverage_bps(total_money: i32, customer_deposits: i64) -> Option<i64> {
+    if customer_deposits <= 0 {
+        return None;
+    }
+    Some(total_money as i64 * 10_000 / customer_deposits)
+}
+
+// Fails if paying `outflow` out of the branch vault would leave it below the bank's
+// minimum reserve ratio. `deposit_reduction` is the part of the outflow that also
+// reduces customer deposits (a withdrawal), as opposed to a loan disbursement.
+pub async fn ensure_reserve(
+    conn: &mut PgConnection,
+    branch_id: Uuid,
+    outflow: i32,
+    deposit_reduction: i32,
+) -> Result<(), CustomerErrorReps> {
+    let position = sqlx::query!(
+        r#"
+        SELECT b.total_money, k.reserve_ratio_bps,
+            (SELECT COALESCE(SUM(a.balance), 0) FROM accounts AS a WHERE a.branch_id = b.id)::BIGINT AS "customer_deposits!"
+        FROM branches AS b
+        INNER JOIN banks AS k ON k.id = b.bank_id
+        WHERE b.id = $1
+        "#,
+        branch_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(CustomerErrorReps::BranchNotFound)?;
+
+    let required = required_reserve(
+        position.customer_deposits - deposit_reduction as i64,
+        position.reserve_ratio_bps,
+    );
+
+    if (position.total_money as i64 - outflow as i64) < required {
+        return Err(CustomerErrorReps::ReserveRatioBreached(required));
+    }
+
+    Ok(())
+}
+
+pub async fn transfer_cash(
+    pool: &PgPool,
+    audit: &AuditContext,
+    from_branch_id: Uuid,
+    to_branch_id: Uuid,
+    amount: i32,
+) -> Result<BranchCashTransfer, CustomerErrorReps> {
+    if amount <= 0 {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Amount must be greater than 0.".to_string(),
+        ));
+    }
+    if from_branch_id == to_branch_id {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Cannot transfer cash to the same branch.".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    // Lock both vaults in a stable order to avoid deadlocks between opposite transfers
+    let branches = sqlx::query!(
+        r#"
+        SELECT id, bank_id, total_money FROM branches
+        WHERE id = $1 OR id = $2
+        ORDER BY id
+        FOR UPDATE
+        "#,
+        from_branch_id,
+        to_branch_id
+    )
+    .fetch_all(&mut transaction)
+    .await?;
+
+    let from_branch = branches
+        .iter()
+        .find(|b| b.id == from_branch_id)
+        .ok_or(CustomerErrorReps::BranchNotFound)?;
+    let to_branch = branches
+        .iter()
+        .find(|b| b.id == to_branch_id)
+        .ok_or(CustomerErrorReps::BranchNotFound)?;
+
+    if from_branch.bank_id != to_branch.bank_id {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Cash can only be moved between branches of the same bank.".to_string(),
+        ));
+    }
+    if from_branch.total_money < amount {
+        return Err(CustomerErrorReps::InsufficientBranchCash(from_branch.total_money));
+    }
+
+    ensure_reserve(&mut transaction, from_branch_id, amount, 0).await?;
+
+    // The bank's total is unchanged, cash only moves between vaults
+    sqlx::query!(
+        r#"
+        UPDATE branches
+        SET total_money = total_money + CASE WHEN id = $1 THEN -($3::int) ELSE $3 END, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1 OR id = $2
+        "#,
+        from_branch_id,
+        to_branch_id,
+        amount
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    let cash_transfer = sqlx::query_as!(
+        BranchCashTransfer,
+        r#"
+        INSERT INTO branch_cash_transfers (id, bank_id, from_branch_id, to_branch_id, amount, inserted_at)
+        VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+        RETURNING *
+        "#,
+        Uuid::new_v4(),
+        from_branch.bank_id,
+        from_branch_id,
+        to_branch_id,
+        amount
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("treasury.cash_transfer", "branch_cash_transfer", cash_transfer.id, Some(cash_transfer.bank_id))
+            .after(&cash_transfer),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(cash_transfer)
+}
+
+// Approves a pending loan and disburses it from the branch vault, provided the vault
+// keeps the bank's minimum reserve afterwards. A loan falling due on a weekend or holiday
+// falls due on the next business day instead.
+pub async fn approve_loan(
+    pool: &PgPool,
+    audit: &AuditContext,
+    branch_id: Uuid,
+    loan_id: Uuid,
+) -> Result<LoanApproval, CustomerErrorReps> {
+    let mut transaction = pool.begin().await?;
+
+    let loan = sqlx::query!(
+        r#"
+        SELECT id, bank_id, branch_id, amount, start_date, end_date, status as "status: Status"
+        FROM loans
+        WHERE id = $1 AND branch_id = $2
+        FOR UPDATE
+        "#,
+        loan_id,
+        branch_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    if loan.status != Status::Pending {
+        return Err(CustomerErrorReps::InvalidInput(
+            "Only pending loans can be approved.".to_string(),
+        ));
+    }
+
+    let total_money = sqlx::query_scalar!(
+        r#"
+        SELECT total_money FROM branches WHERE id = $1 FOR UPDATE
+        "#,
+        branch_id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+    if total_money < loan.amount {
+        return Err(CustomerErrorReps::InsufficientBranchCash(total_money));
+    }
+
+    ensure_reserve(&mut transaction, branch_id, loan.amount, 0).await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE branches
+        SET total_money = total_money - $1, loans_given = loans_given + $1, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $2
+        "#,
+        loan.amount,
+        branch_id
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    let end_date = calendar::load(&mut transaction, loan.bank_id)
+        .await?
+        .business_day_on_or_after(loan.end_date);
+
+    sqlx::query!(
+        r#"
+        UPDATE loans SET status = 'approved', end_date = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $1
+        "#,
+        loan_id,
+        end_date
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    let approval = LoanApproval {
+        loan_id,
+        bank_id: loan.bank_id,
+        branch_id,
+        amount: loan.amount,
+        start_date: loan.start_date,
+        end_date,
+        status: Status::Approved,
+    };
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("loan.approve", "loan", loan_id, Some(loan.bank_id))
+            .before(&serde_json::json!({ "status": loan.status, "end_date": loan.end_date }))
+            .after(&approval),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(approval)
+}
+
+pub async fn get_reserve_coverage(
+    pool: &PgPool,
+    bank_id: Uuid,
+) -> Result<Vec<ReserveCoverage>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT b.id, b.branch_name, b.total_money, k.reserve_ratio_bps,
+            (SELECT COALESCE(SUM(a.balance), 0) FROM accounts AS a WHERE a.branch_id = b.id)::BIGINT AS "customer_deposits!"
+        FROM branches AS b
+        INNER JOIN banks AS k ON k.id = b.bank_id
+        WHERE b.bank_id = $1
+        ORDER BY b.branch_name
+        "#,
+        bank_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let coverage = rows
+        .into_iter()
+        .map(|row| {
+            let required = required_reserve(row.customer_deposits, row.reserve_ratio_bps);
+            ReserveCoverage {
+                branch_id: row.id,
+                branch_name: row.branch_name,
+                total_money: row.total_money,
+                customer_deposits: row.customer_deposits,
+                required_reserve: required,
+                coverage_bps: coverage_bps(row.total_money, row.customer_deposits),
+                meets_minimum: row.total_money as i64 >= required,
+            }
+        })
+        .collect();
+
+    Ok(coverage)
+}
+
+// Records the end-of-day cash position of every branch, or of one bank's branches.
+// Re-running for the same date overwrites that day's snapshot.
+pub async fn snapshot_cash_positions(
+    pool: &PgPool,
+    bank_id: Option<Uuid>,
+    business_date: NaiveDate,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO branch_cash_positions (id, bank_id, branch_id, business_date, total_money, customer_deposits, required_reserve, inserted_at)
+        SELECT gen_random_uuid(), b.bank_id, b.id, $1, b.total_money, d.customer_deposits,
+            d.customer_deposits * k.reserve_ratio_bps / 10000, CURRENT_TIMESTAMP
+        FROM branches AS b
+        INNER JOIN banks AS k ON k.id = b.bank_id
+        CROSS JOIN LATERAL (
+            SELECT COALESCE(SUM(a.balance), 0)::BIGINT AS customer_deposits
+            FROM accounts AS a
+            WHERE a.branch_id = b.id
+        ) AS d
+        WHERE $2::uuid IS NULL OR b.bank_id = $2
+        ON CONFLICT (branch_id, business_date) DO UPDATE
+        SET total_money = EXCLUDED.total_money,
+            customer_deposits = EXCLUDED.customer_deposits,
+            required_reserve = EXCLUDED.required_reserve,
+            inserted_at = EXCLUDED.inserted_at
+        "#,
+        business_date,
+        bank_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn get_cash_positions(
+    pool: &PgPool,
+    branch_id: Uuid,
+) -> Result<Vec<BranchCashPosition>, sqlx::Error> {
+    let positions = sqlx::query_as!(
+        BranchCashPosition,
+        r#"
+        SELECT * FROM branch_cash_positions
+        WHERE branch_id = $1
+        ORDER BY business_date DESC
+        "#,
+        branch_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(positions)
+}
@@ -31,6 +31,60 @@ pub enum Status {
     Close,
 }
 
+#[derive(Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "accountstatus", rename_all = "snake_case")]
+pub enum AccountStatus {
+    Active,
+    Frozen,
+    Dormant,
+    Closed,
+}
+
+impl AccountStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountStatus::Active => "active",
+            AccountStatus::Frozen => "frozen",
+            AccountStatus::Dormant => "dormant",
+            AccountStatus::Closed => "closed",
+        }
+    }
+}
+
+#[derive(Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "accountholderrole", rename_all = "snake_case")]
+pub enum AccountHolderRole {
+    Primary,
+    Joint,
+    AuthorizedSigner,
+}
+
+// What a holder may do with an account; granted by their `AccountHolderRole`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccountPermission {
+    // Balances, transactions and statements
+    View,
+    // Payments, card charges and card controls
+    Transact,
+    // Standing orders, mandates and refunds
+    Manage,
+    // Adding and removing other holders
+    ManageHolders,
+}
+
+impl AccountHolderRole {
+    pub fn permits(&self, permission: AccountPermission) -> bool {
+        match self {
+            AccountHolderRole::Primary => true,
+            AccountHolderRole::Joint => permission != AccountPermission::ManageHolders,
+            AccountHolderRole::AuthorizedSigner => matches!(
+                permission,
+                AccountPermission::View | AccountPermission::Transact
+            ),
+        }
+    }
+}
+
 #[derive(Type, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[sqlx(type_name = "transactiontype", rename_all = "snake_case")]
 pub enum TransactionType {
@@ -40,4 +94,208 @@ pub enum TransactionType {
     CashWithdrawal,
     CashDeposit,
     DebitCardCharge,
+    CreditCardCharge,
+    CreditCardPayment,
+    InterestCharge,
+    LateFee,
+    DirectDebit,
+    DirectDebitRefund,
+    ClosureSweep,
+    ClearingReturn,
+    Fee,
+    FeeReversal,
+    OverdraftInterest,
+}
+
+impl TransactionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionType::RepayLoan => "repay_loan",
+            TransactionType::RepayInterest => "repay_interest",
+            TransactionType::P2P => "p2p",
+            TransactionType::CashWithdrawal => "cash_withdrawal",
+            TransactionType::CashDeposit => "cash_deposit",
+            TransactionType::DebitCardCharge => "debit_card_charge",
+            TransactionType::CreditCardCharge => "credit_card_charge",
+            TransactionType::CreditCardPayment => "credit_card_payment",
+            TransactionType::InterestCharge => "interest_charge",
+            TransactionType::LateFee => "late_fee",
+            TransactionType::DirectDebit => "direct_debit",
+            TransactionType::DirectDebitRefund => "direct_debit_refund",
+            TransactionType::ClosureSweep => "closure_sweep",
+            TransactionType::ClearingReturn => "clearing_return",
+            TransactionType::Fee => "fee",
+            TransactionType::FeeReversal => "fee_reversal",
+            TransactionType::OverdraftInterest => "overdraft_interest",
+        }
+    }
+}
+
+#[derive(Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "entrydirection", rename_all = "snake_case")]
+pub enum EntryDirection {
+    Credit,
+    Debit,
+}
+
+#[derive(Type, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "statementstatus", rename_all = "snake_case")]
+pub enum StatementStatus {
+    Open,
+    Paid,
+    MinimumPaid,
+    Overdue,
+}
+
+#[derive(Type, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "standingorderfrequency", rename_all = "snake_case")]
+pub enum StandingOrderFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Cron,
+}
+
+#[derive(Type, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "standingorderstatus", rename_all = "snake_case")]
+pub enum StandingOrderStatus {
+    Active,
+    Paused,
+    Completed,
+    Cancelled,
+}
+
+#[derive(Type, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "executionstatus", rename_all = "snake_case")]
+pub enum ExecutionStatus {
+    Succeeded,
+    Failed,
+}
+
+#[derive(Type, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "mandatestatus", rename_all = "snake_case")]
+pub enum MandateStatus {
+    Active,
+    Revoked,
+}
+
+#[derive(Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "role", rename_all = "snake_case")]
+pub enum Role {
+    Customer,
+    Teller,
+    BranchManager,
+    BankAdmin,
+    Auditor,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Customer => "customer",
+            Role::Teller => "teller",
+            Role::BranchManager => "branch_manager",
+            Role::BankAdmin => "bank_admin",
+            Role::Auditor => "auditor",
+        }
+    }
+}
+
+#[derive(Type, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "webhookdeliverystatus", rename_all = "snake_case")]
+pub enum WebhookDeliveryStatus {
+    Pending,
+    Delivered,
+    DeadLettered,
+}
+
+#[derive(Type, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[sqlx(type_name = "frauddecision", rename_all = "snake_case")]
+pub enum FraudDecision {
+    Allow,
+    Review,
+    Deny,
+}
+
+#[derive(Type, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "sanctionshitstatus", rename_all = "snake_case")]
+pub enum SanctionsHitStatus {
+    Pending,
+    Confirmed,
+    Cleared,
+}
+
+#[derive(Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "paymentbatchstatus", rename_all = "snake_case")]
+pub enum PaymentBatchStatus {
+    Accepted,
+    Processing,
+    Completed,
+    PartiallyCompleted,
+    Rejected,
+}
+
+#[derive(Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "paymentitemstatus", rename_all = "snake_case")]
+pub enum PaymentItemStatus {
+    Pending,
+    // Waiting on a fraud review; carried out if the review approves it
+    Held,
+    Completed,
+    // Failed validation when the batch was submitted
+    Rejected,
+    // Could not be carried out
+    Failed,
+}
+
+#[derive(Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "batchexecutionmode", rename_all = "snake_case")]
+pub enum BatchExecutionMode {
+    BestEffort,
+    AllOrNothing,
+}
+
+#[derive(Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "clearingitemstatus", rename_all = "snake_case")]
+pub enum ClearingItemStatus {
+    Queued,
+    Settled,
+    // Refused by the receiving bank and credited back to the debtor
+    Returned,
+}
+
+#[derive(Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "batchrunstatus", rename_all = "snake_case")]
+pub enum BatchRunStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "feetype", rename_all = "snake_case")]
+pub enum FeeType {
+    // Charged on a posted transaction of the schedule's transaction type
+    Transaction,
+    // Card cash withdrawals
+    AtmWithdrawal,
+    MonthlyMaintenance,
+    // Charged daily while the balance is below the arranged overdraft limit
+    UnarrangedOverdraft,
+}
+
+#[derive(Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "feecalculation", rename_all = "snake_case")]
+pub enum FeeCalculation {
+    Flat,
+    Percentage,
+}
+
+#[derive(Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "feestatus", rename_all = "snake_case")]
+pub enum FeeStatus {
+    Charged,
+    // Covered by a waiver; nothing was posted
+    Waived,
+    Reversed,
 }
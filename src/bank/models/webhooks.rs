@@ -0,0 +1,544 @@
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use hmac::{Hmac, Mac};
+use hyper::{client::HttpConnector, Body, Client, Request, Uri};
+use hyper_rustls::HttpsConnector;
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::bank::helper::validation::CustomerErrorReps;
+
+use super::{
+    audit::{self, AuditContext, Change},
+    outbox::{self, DomainEvent, OutboxMessage},
+    types::WebhookDeliveryStatus,
+};
+
+pub const SIGNATURE_HEADER: &str = "x-signature";
+// Receivers should reject signatures older than this to prevent replays
+pub const SIGNATURE_TOLERANCE_SECONDS: i64 = 5 * 60;
+
+pub const DISPATCH_BATCH_SIZE: i64 = 50;
+// Roughly a day and a half of retries before a delivery is dead-lettered
+pub const MAX_ATTEMPTS: i32 = 12;
+
+const BASE_BACKOFF_SECONDS: i64 = 30;
+const MAX_BACKOFF_SECONDS: i64 = 6 * 60 * 60;
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+const SECRET_LEN: usize = 32;
+
+pub type WebhookClient = Client<HttpsConnector<HttpConnector>>;
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct WebhookEndpoint {
+    pub id: Uuid,
+    pub client_id: Uuid,
+    pub bank_id: Uuid,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub disabled_at: Option<NaiveDateTime>,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// Returned once when an endpoint is registered; receivers need the secret to verify
+// signatures
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisteredWebhookEndpoint {
+    pub endpoint: WebhookEndpoint,
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub endpoint_id: Uuid,
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub payload: Value,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    pub last_response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: NaiveDateTime,
+    pub delivered_at: Option<NaiveDateTime>,
+    pub inserted_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+pub fn http_client() -> WebhookClient {
+    let connector = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_native_roots()
+        .https_or_http()
+        .enable_http1()
+        .build();
+    Client::builder().build(connector)
+}
+
+fn hmac_hex(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+// `t=<unix seconds>,v1=<hex HMAC-SHA256 of "<t>.<body>">`. Signing the timestamp
+// stops a captured delivery from being replayed later with a fresh one.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    format!("t={},v1={}", timestamp, hmac_hex(secret, timestamp, body))
+}
+
+pub fn verify_signature(secret: &str, header: &str, body: &[u8], now: i64) -> bool {
+    let mut timestamp = None;
+    let mut signature = None;
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signature = hex::decode(value).ok(),
+            _ => {}
+        }
+    }
+
+    let (timestamp, signature) = match (timestamp, signature) {
+        (Some(timestamp), Some(signature)) => (timestamp, signature),
+        _ => return false,
+    };
+    if (now - timestamp).abs() > SIGNATURE_TOLERANCE_SECONDS {
+        return false;
+    }
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+fn validate_url(url: &str) -> Result<(), CustomerErrorReps> {
+    let invalid = || CustomerErrorReps::InvalidInput("Webhook URL must be an absolute http(s) URL.".to_string());
+    let uri: Uri = url.parse().map_err(|_| invalid())?;
+    match (uri.scheme_str(), uri.host()) {
+        (Some("http") | Some("https"), Some(_)) => Ok(()),
+        _ => Err(invalid()),
+    }
+}
+
+fn validate_event_types(event_types: &[String]) -> Result<(), CustomerErrorReps> {
+    if event_types.is_empty() {
+        return Err(CustomerErrorReps::InvalidInput(
+            "At least one event type is required.".to_string(),
+        ));
+    }
+    if let Some(unknown) = event_types
+        .iter()
+        .find(|event_type| !outbox::EVENT_TYPES.contains(&event_type.as_str()))
+    {
+        return Err(CustomerErrorReps::InvalidInput(format!(
+            "Unknown event type: {}",
+            unknown
+        )));
+    }
+    Ok(())
+}
+
+pub async fn create_endpoint(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    client_id: Uuid,
+    url: String,
+    event_types: Vec<String>,
+) -> Result<RegisteredWebhookEndpoint, CustomerErrorReps> {
+    validate_url(&url)?;
+    validate_event_types(&event_types)?;
+
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query_scalar!(
+        r#"
+        SELECT id FROM api_clients WHERE id = $1 AND bank_id = $2
+        "#,
+        client_id,
+        bank_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    let secret: String = format!(
+        "whsec_{}",
+        rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(SECRET_LEN)
+            .map(char::from)
+            .collect::<String>()
+    );
+
+    let endpoint = sqlx::query_as!(
+        WebhookEndpoint,
+        r#"
+        INSERT INTO webhook_endpoints (id, client_id, bank_id, url, secret, event_types, inserted_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        RETURNING id, client_id, bank_id, url, event_types, disabled_at, inserted_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        client_id,
+        bank_id,
+        url,
+        secret,
+        &event_types
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("webhook_endpoint.create", "webhook_endpoint", endpoint.id, Some(bank_id))
+            .after(&endpoint),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(RegisteredWebhookEndpoint { endpoint, secret })
+}
+
+pub async fn disable_endpoint(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    endpoint_id: Uuid,
+) -> Result<WebhookEndpoint, CustomerErrorReps> {
+    let mut transaction = pool.begin().await?;
+
+    let endpoint = sqlx::query_as!(
+        WebhookEndpoint,
+        r#"
+        UPDATE webhook_endpoints
+        SET disabled_at = COALESCE(disabled_at, CURRENT_TIMESTAMP), updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND bank_id = $2
+        RETURNING id, client_id, bank_id, url, event_types, disabled_at, inserted_at, updated_at
+        "#,
+        endpoint_id,
+        bank_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("webhook_endpoint.disable", "webhook_endpoint", endpoint.id, Some(bank_id))
+            .after(&endpoint),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(endpoint)
+}
+
+pub async fn get_endpoints(
+    pool: &PgPool,
+    bank_id: Uuid,
+    client_id: Uuid,
+) -> Result<Vec<WebhookEndpoint>, sqlx::Error> {
+    let endpoints = sqlx::query_as!(
+        WebhookEndpoint,
+        r#"
+        SELECT id, client_id, bank_id, url, event_types, disabled_at, inserted_at, updated_at
+        FROM webhook_endpoints
+        WHERE client_id = $1 AND bank_id = $2
+        ORDER BY inserted_at DESC
+        "#,
+        client_id,
+        bank_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(endpoints)
+}
+
+pub async fn get_deliveries(
+    pool: &PgPool,
+    bank_id: Uuid,
+    endpoint_id: Uuid,
+    status: Option<WebhookDeliveryStatus>,
+) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+    let deliveries = sqlx::query_as!(
+        WebhookDelivery,
+        r#"
+        SELECT d.id, d.endpoint_id, d.event_id, d.event_type, d.payload, d.status as "status: _", d.attempts, d.last_response_status, d.last_error, d.next_attempt_at, d.delivered_at, d.inserted_at, d.updated_at
+        FROM webhook_deliveries d
+        JOIN webhook_endpoints e ON e.id = d.endpoint_id
+        WHERE d.endpoint_id = $1 AND e.bank_id = $2
+          AND ($3::webhookdeliverystatus IS NULL OR d.status = $3)
+        ORDER BY d.inserted_at DESC
+        "#,
+        endpoint_id,
+        bank_id,
+        status as Option<WebhookDeliveryStatus>
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(deliveries)
+}
+
+// Puts a delivery back in the queue with a fresh retry budget, whatever its state.
+pub async fn replay_delivery(
+    pool: &PgPool,
+    audit: &AuditContext,
+    bank_id: Uuid,
+    delivery_id: Uuid,
+) -> Result<WebhookDelivery, CustomerErrorReps> {
+    let mut transaction = pool.begin().await?;
+
+    let delivery = sqlx::query_as!(
+        WebhookDelivery,
+        r#"
+        UPDATE webhook_deliveries d
+        SET status = 'pending', attempts = 0, next_attempt_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+        FROM webhook_endpoints e
+        WHERE d.id = $1 AND e.id = d.endpoint_id AND e.bank_id = $2 AND e.disabled_at IS NULL
+        RETURNING d.id, d.endpoint_id, d.event_id, d.event_type, d.payload, d.status as "status: _", d.attempts, d.last_response_status, d.last_error, d.next_attempt_at, d.delivered_at, d.inserted_at, d.updated_at
+        "#,
+        delivery_id,
+        bank_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or(CustomerErrorReps::NotFound)?;
+
+    audit::record(
+        &mut transaction,
+        audit,
+        Change::new("webhook_delivery.replay", "webhook_delivery", delivery.id, Some(bank_id)),
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(delivery)
+}
+
+// The accounts an event is about. A client only hears about events concerning the
+// account it operates on; events about no account (e.g. new customers) go nowhere.
+async fn concerned_account_numbers(
+    conn: &mut PgConnection,
+    event: &DomainEvent,
+) -> Result<Vec<String>, sqlx::Error> {
+    let account_numbers = match event {
+        DomainEvent::CustomerCreated(_) => vec![],
+        DomainEvent::AccountOpened(account) => vec![account.account_number.clone()],
+        DomainEvent::TransferCompleted(transfer) => vec![
+            transfer.sender_account_number.clone(),
+            transfer.beneficiary_account_number.clone(),
+        ],
+        DomainEvent::CardStatusChanged { account_number, .. }
+        | DomainEvent::OverdraftEntered { account_number, .. } => vec![account_number.clone()],
+        DomainEvent::RefundApproved(refund) => sqlx::query_scalar!(
+            r#"
+            SELECT account_number FROM transactions WHERE id = $1
+            "#,
+            refund.transaction_id
+        )
+        .fetch_all(&mut *conn)
+        .await?,
+        // Only the creditor is told about a collection
+        DomainEvent::DirectDebitCollected(collection) => sqlx::query_scalar!(
+            r#"
+            SELECT creditor_account_number FROM mandates WHERE id = $1
+            "#,
+            collection.mandate_id
+        )
+        .fetch_all(&mut *conn)
+        .await?,
+        // Only the debtor is told; the creditor never saw the money
+        DomainEvent::ClearingItemReturned(item) => vec![item.debtor_account_number.clone()],
+    };
+
+    Ok(account_numbers)
+}
+
+// Fans an outbox message out to the active endpoints subscribed to its type whose
+// client operates an account the event concerns. Safe to repeat: each (endpoint, event)
+// pair is only queued once.
+pub async fn enqueue(conn: &mut PgConnection, message: &OutboxMessage) -> Result<u64, sqlx::Error> {
+    let bank_id = match message.bank_id {
+        Some(bank_id) => bank_id,
+        None => return Ok(0),
+    };
+
+    let event: DomainEvent = serde_json::from_value(serde_json::json!({
+        "type": message.event_type,
+        "data": message.payload,
+    }))
+    .map_err(|err| sqlx::Error::Protocol(err.to_string()))?;
+    let account_numbers = concerned_account_numbers(&mut *conn, &event).await?;
+    if account_numbers.is_empty() {
+        return Ok(0);
+    }
+
+    let body = serde_json::json!({
+        "id": message.id,
+        "type": message.event_type,
+        "occurred_at": message.occurred_at,
+        "data": message.payload,
+    });
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO webhook_deliveries (id, endpoint_id, event_id, event_type, payload, status, next_attempt_at, inserted_at, updated_at)
+        SELECT gen_random_uuid(), e.id, $1, $2::varchar, $3, 'pending', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP
+        FROM webhook_endpoints AS e
+        INNER JOIN api_clients AS c ON c.id = e.client_id
+        INNER JOIN accounts AS a ON a.id = c.account_id
+        WHERE e.bank_id = $4 AND e.disabled_at IS NULL AND $2::text = ANY(e.event_types)
+            AND a.account_number = ANY($5)
+        ON CONFLICT (endpoint_id, event_id) DO NOTHING
+        "#,
+        message.id,
+        message.event_type,
+        body,
+        bank_id,
+        &account_numbers
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+fn backoff_seconds(attempts: i32) -> i64 {
+    BASE_BACKOFF_SECONDS
+        .saturating_mul(2_i64.saturating_pow(attempts.clamp(0, 30) as u32))
+        .min(MAX_BACKOFF_SECONDS)
+}
+
+// Outcome of a single POST: the response status if one came back, and an error otherwise
+async fn post(
+    client: &WebhookClient,
+    url: &str,
+    secret: &str,
+    delivery: &WebhookDelivery,
+) -> (Option<i32>, Option<String>) {
+    let body = delivery.payload.to_string().into_bytes();
+    let signature = sign(secret, chrono::Utc::now().timestamp(), &body);
+
+    let request = match Request::post(url)
+        .header("content-type", "application/json")
+        .header(SIGNATURE_HEADER, signature)
+        .header("x-webhook-id", delivery.id.to_string())
+        .header("x-event-id", delivery.event_id.to_string())
+        .header("x-event-type", delivery.event_type.as_str())
+        .body(Body::from(body))
+    {
+        Ok(request) => request,
+        Err(err) => return (None, Some(err.to_string())),
+    };
+
+    match tokio::time::timeout(DELIVERY_TIMEOUT, client.request(request)).await {
+        Ok(Ok(response)) if response.status().is_success() => {
+            (Some(response.status().as_u16() as i32), None)
+        }
+        Ok(Ok(response)) => (
+            Some(response.status().as_u16() as i32),
+            Some(format!("Endpoint responded with {}", response.status())),
+        ),
+        Ok(Err(err)) => (None, Some(err.to_string())),
+        Err(_) => (None, Some("Timed out".to_string())),
+    }
+}
+
+// Attempts every due delivery once. Failures are rescheduled with exponential backoff
+// and dead-lettered after MAX_ATTEMPTS.
+pub async fn dispatch_due(pool: &PgPool, client: &WebhookClient) -> Result<usize, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let due = sqlx::query!(
+        r#"
+        SELECT d.id, d.endpoint_id, d.event_id, d.event_type, d.payload, d.status as "status: WebhookDeliveryStatus", d.attempts, d.last_response_status, d.last_error, d.next_attempt_at, d.delivered_at, d.inserted_at, d.updated_at, e.url, e.secret
+        FROM webhook_deliveries d
+        JOIN webhook_endpoints e ON e.id = d.endpoint_id
+        WHERE d.status = 'pending' AND d.next_attempt_at <= CURRENT_TIMESTAMP AND e.disabled_at IS NULL
+        ORDER BY d.next_attempt_at
+        LIMIT $1
+        FOR UPDATE OF d SKIP LOCKED
+        "#,
+        DISPATCH_BATCH_SIZE
+    )
+    .fetch_all(&mut transaction)
+    .await?;
+
+    let mut delivered = 0;
+    for row in due {
+        let delivery = WebhookDelivery {
+            id: row.id,
+            endpoint_id: row.endpoint_id,
+            event_id: row.event_id,
+            event_type: row.event_type,
+            payload: row.payload,
+            status: row.status,
+            attempts: row.attempts,
+            last_response_status: row.last_response_status,
+            last_error: row.last_error,
+            next_attempt_at: row.next_attempt_at,
+            delivered_at: row.delivered_at,
+            inserted_at: row.inserted_at,
+            updated_at: row.updated_at,
+        };
+
+        let (response_status, error) = post(client, &row.url, &row.secret, &delivery).await;
+        let attempts = delivery.attempts + 1;
+
+        let status = match error {
+            None => {
+                delivered += 1;
+                WebhookDeliveryStatus::Delivered
+            }
+            Some(ref error) if attempts >= MAX_ATTEMPTS => {
+                tracing::warn!("webhook delivery {} dead-lettered: {}", delivery.id, error);
+                WebhookDeliveryStatus::DeadLettered
+            }
+            Some(_) => WebhookDeliveryStatus::Pending,
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = $2,
+                attempts = $3,
+                last_response_status = $4,
+                last_error = $5,
+                next_attempt_at = CURRENT_TIMESTAMP + make_interval(secs => $6),
+                delivered_at = CASE WHEN $2 = 'delivered'::webhookdeliverystatus THEN CURRENT_TIMESTAMP ELSE NULL END,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+            delivery.id,
+            status as WebhookDeliveryStatus,
+            attempts,
+            response_status,
+            error,
+            backoff_seconds(delivery.attempts) as f64
+        )
+        .execute(&mut transaction)
+        .await?;
+    }
+
+    transaction.commit().await?;
+
+    Ok(delivered)
+}
@@ -1,33 +1,200 @@
 use axum::{
-    routing::{get, post},
-    Router,
+    http::StatusCode,
+    routing::{delete, get, post, put},
+    Json, Router,
 };
 use sqlx::PgPool;
 
 use crate::bank::accounts::AccountService;
+use crate::bank::helper::validation::CustomerErrorReps;
+use crate::bank::models::auth::JwtConfig;
 mod accounts;
+mod api_keys;
+mod audit;
+mod auth;
+mod batches;
+mod beneficiaries;
+mod calendar;
 mod cards;
+mod clearing;
+mod credit;
+mod ctr;
 mod customer;
+mod eod;
+mod fees;
+mod fraud;
+mod iso20022;
+mod mandates;
 mod payments;
 mod refunds;
+mod sanctions;
+mod standing_orders;
+mod teller;
+mod treasury;
+mod webhooks;
 #[derive(Clone)]
 pub struct BankWeb<T> {
     pool: PgPool,
     account_service: T,
+    jwt: JwtConfig,
+}
+
+fn error_response<T>(err: CustomerErrorReps) -> (StatusCode, Json<Result<T, String>>) {
+    let status_code = match err {
+        CustomerErrorReps::NotFound | CustomerErrorReps::BranchNotFound => StatusCode::NOT_FOUND,
+        CustomerErrorReps::Unauthorized => StatusCode::UNAUTHORIZED,
+        CustomerErrorReps::Forbidden => StatusCode::FORBIDDEN,
+        CustomerErrorReps::CreditLimitExceeded(_) => StatusCode::PAYMENT_REQUIRED,
+        CustomerErrorReps::HeldForReview(_) => StatusCode::ACCEPTED,
+        CustomerErrorReps::InvalidInput(_)
+        | CustomerErrorReps::InsufficientFunds
+        | CustomerErrorReps::TransactionDeclined(_)
+        | CustomerErrorReps::SanctionsMatch(_)
+        | CustomerErrorReps::AccountUnavailable(_)
+        | CustomerErrorReps::InsufficientBranchCash(_)
+        | CustomerErrorReps::ReserveRatioBreached(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        CustomerErrorReps::DatabaseError(ref err) => {
+            tracing::error!("database error: {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status_code, Json(Err(err.to_string())))
 }
 
 impl<T: AccountService> BankWeb<T> {
-    pub fn new(pool: PgPool, account_service: T) -> Self {
+    pub fn new(pool: PgPool, account_service: T, jwt: JwtConfig) -> Self {
         Self {
             pool,
             account_service,
+            jwt,
         }
     }
 
     pub fn into_router(self) -> Router {
         Router::new()
-            .route("/api/customers", post(customer::post::<T>))
+            .route("/api/auth/register", post(auth::register::<T>))
+            .route("/api/auth/login", post(auth::login::<T>))
+            .route("/api/auth/staff/login", post(auth::staff_login::<T>))
+            .route("/api/banks/:bank_id/staff", post(auth::create_staff::<T>))
+            .route(
+                "/api/banks/:bank_id/api-clients",
+                post(api_keys::create_client::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/api-clients/:client_id/keys",
+                get(api_keys::keys::<T>).post(api_keys::issue_key::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/api-keys/:key_id",
+                delete(api_keys::revoke_key::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/api-clients/:client_id/webhook-endpoints",
+                get(webhooks::endpoints::<T>).post(webhooks::create_endpoint::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/webhook-endpoints/:endpoint_id",
+                delete(webhooks::disable_endpoint::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/webhook-endpoints/:endpoint_id/deliveries",
+                get(webhooks::deliveries::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/webhook-deliveries/:delivery_id/replay",
+                post(webhooks::replay::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/fraud-rules",
+                get(fraud::rules::<T>).post(fraud::create_rule::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/fraud-rules/:rule_id",
+                put(fraud::update_rule::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/fraud-reviews",
+                get(fraud::reviews::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/fraud-reviews/:review_id/approve",
+                post(fraud::approve::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/fraud-reviews/:review_id/reject",
+                post(fraud::reject::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/sanctions-hits",
+                get(sanctions::hits::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/sanctions-hits/:hit_id/confirm",
+                post(sanctions::confirm::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/sanctions-hits/:hit_id/clear",
+                post(sanctions::clear::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/calendar",
+                get(calendar::get::<T>).put(calendar::update::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/clearing-items",
+                get(clearing::items::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/clearing-items/:item_id/return",
+                post(clearing::return_item::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/clearing-positions",
+                get(clearing::positions::<T>),
+            )
+            .route("/api/banks/:bank_id/batch-runs", get(eod::runs::<T>))
+            .route(
+                "/api/banks/:bank_id/fee-schedules",
+                get(fees::schedules::<T>).post(fees::create_schedule::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/fee-schedules/:schedule_id",
+                put(fees::update_schedule::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/fees/:fee_id/reverse",
+                post(fees::reverse::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/accounts/:account_id/fee-waivers",
+                get(fees::waivers::<T>).post(fees::grant_waiver::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/fee-waivers/:waiver_id",
+                delete(fees::revoke_waiver::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/audit-events",
+                get(audit::events::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/audit-events/verify",
+                get(audit::verify::<T>),
+            )
+            .route(
+                "/api/branches/:branch_id/customers",
+                post(customer::post::<T>),
+            )
             .route("/api/customers/:customer_id", get(customer::get::<T>))
+            .route(
+                "/api/customers/:customer_id/accounts",
+                get(accounts::customer_accounts::<T>),
+            )
+            .route(
+                "/api/customers/:customer_id/beneficiaries",
+                post(beneficiaries::post::<T>),
+            )
+            .route("/api/payments", post(payments::post::<T>))
             .route(
                 "/api/payments/:payment_id/refunds",
                 post(refunds::post::<T>),
@@ -36,9 +203,152 @@ impl<T: AccountService> BankWeb<T> {
                 "/api/payments/:payment_id/refunds/:refund_id",
                 get(refunds::get::<T>),
             )
-            .route("/api/cards", post(cards::post::<T>))
+            .route("/api/branches/:branch_id/cards", post(cards::post::<T>))
+            .route(
+                "/api/branches/:branch_id/cards/:card_number/status",
+                put(cards::update_status_by_staff::<T>),
+            )
             .route("/api/cards/:card_number", post(cards::get::<T>))
-            .route("/api/account/", post(accounts::create_account::<T>))
+            .route(
+                "/api/cards/:card_number/status",
+                put(cards::update_status::<T>),
+            )
+            .route(
+                "/api/branches/:branch_id/accounts",
+                post(accounts::create_account::<T>),
+            )
+            .route(
+                "/api/accounts/:account_id/transactions",
+                get(accounts::get_transactions::<T>),
+            )
+            .route("/api/accounts/:account_id/iban", get(accounts::get_iban::<T>))
+            .route("/api/accounts/:account_id/fees", get(fees::charges::<T>))
+            .route(
+                "/api/accounts/:account_id/overdraft-accruals",
+                get(accounts::overdraft_accruals::<T>),
+            )
+            .route(
+                "/api/accounts/:account_id/camt.053",
+                get(iso20022::statement::<T>),
+            )
+            .route("/api/payment-initiations", post(iso20022::initiate::<T>))
+            .route(
+                "/api/payment-batches/:batch_id/pain.002",
+                get(iso20022::status_report::<T>),
+            )
+            .route("/api/batches", post(batches::post::<T>))
+            .route("/api/batches/:batch_id", get(batches::get::<T>))
+            .route(
+                "/api/batches/:batch_id/summary",
+                get(batches::summary::<T>),
+            )
+            .route(
+                "/api/accounts/:account_id/holders",
+                get(accounts::holders::<T>).post(accounts::add_holder::<T>),
+            )
+            .route(
+                "/api/accounts/:account_id/holders/:customer_id",
+                delete(accounts::remove_holder::<T>),
+            )
+            .route(
+                "/api/branches/:branch_id/credit-accounts",
+                post(credit::post::<T>),
+            )
+            .route("/api/credit-accounts/:account_id", get(credit::get::<T>))
+            .route(
+                "/api/credit-accounts/:account_id/payments",
+                post(credit::pay::<T>),
+            )
+            .route(
+                "/api/credit-accounts/:account_id/statements",
+                get(credit::statements::<T>),
+            )
+            .route(
+                "/api/credit-cards/:card_number/charges",
+                post(credit::charge::<T>),
+            )
+            .route(
+                "/api/branches/:branch_id/deposits",
+                post(teller::deposit::<T>),
+            )
+            .route(
+                "/api/branches/:branch_id/withdrawals",
+                post(teller::withdraw::<T>),
+            )
+            .route(
+                "/api/branches/:branch_id/atm-withdrawals",
+                post(teller::atm_withdraw::<T>),
+            )
+            .route(
+                "/api/branches/:branch_id/accounts/by-iban/:iban",
+                get(accounts::find_by_iban::<T>),
+            )
+            .route(
+                "/api/branches/:branch_id/accounts/:account_id/freeze",
+                post(accounts::freeze::<T>),
+            )
+            .route(
+                "/api/branches/:branch_id/accounts/:account_id/unfreeze",
+                post(accounts::unfreeze::<T>),
+            )
+            .route(
+                "/api/branches/:branch_id/accounts/:account_id/reactivate",
+                post(accounts::reactivate::<T>),
+            )
+            .route(
+                "/api/branches/:branch_id/accounts/:account_id/close",
+                post(accounts::close::<T>),
+            )
+            .route(
+                "/api/branches/:branch_id/accounts/:account_id/overdraft",
+                put(accounts::set_overdraft::<T>),
+            )
+            .route(
+                "/api/branches/:branch_id/cash-transfers",
+                post(treasury::transfer::<T>),
+            )
+            .route(
+                "/api/branches/:branch_id/loans/:loan_id/approve",
+                post(treasury::approve_loan::<T>),
+            )
+            .route(
+                "/api/branches/:branch_id/cash-positions",
+                get(treasury::positions::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/reserve-coverage",
+                get(treasury::coverage::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/ctr-reports",
+                get(ctr::reports::<T>),
+            )
+            .route(
+                "/api/banks/:bank_id/ctr-reports/export",
+                post(ctr::export::<T>),
+            )
+            .route("/api/standing-orders", post(standing_orders::post::<T>))
+            .route(
+                "/api/standing-orders/:standing_order_id",
+                get(standing_orders::get::<T>).delete(standing_orders::cancel::<T>),
+            )
+            .route(
+                "/api/standing-orders/:standing_order_id/executions",
+                get(standing_orders::executions::<T>),
+            )
+            .route("/api/mandates", post(mandates::post::<T>))
+            .route(
+                "/api/mandates/:mandate_id",
+                get(mandates::get::<T>).delete(mandates::revoke::<T>),
+            )
+            .route(
+                "/api/mandates/:mandate_id/collections",
+                get(mandates::collections::<T>).post(mandates::collect::<T>),
+            )
+            .route(
+                "/api/mandates/:mandate_id/collections/:collection_id/refund",
+                post(mandates::refund::<T>),
+            )
             .layer(axum_tracing_opentelemetry::opentelemetry_tracing_layer())
             .with_state(self)
             .with_state(())
@@ -1,4 +1,4 @@
-use axum::extract::State;
+use axum::extract::{Path, State};
 use axum::Json;
 use hyper::StatusCode;
 use serde::{Deserialize, Serialize};
@@ -8,7 +8,17 @@ use crate::bank::accounts::{self, Account, AccountService, BankService, Hold};
 use crate::bank_web::payments::{InvalidData, InvalidDataResponse};
 
 use super::payments::create_invalid_data_response;
-use super::BankWeb;
+use super::auth::{AuthenticatedCustomer, Authorized, BranchManagerAccess, TellerAccess};
+use super::{error_response, BankWeb};
+use crate::bank::helper::validation::CustomerErrorReps;
+use crate::bank::models::{
+    accounts::{self as account_models, AccountHolder},
+    auth as auth_models,
+    iban::{self, AccountIban},
+    overdraft::{self, OverdraftAccrual},
+    transactions::{self, Transaction},
+    types::{AccountHolderRole, AccountPermission},
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct HoldRequestBody {
@@ -55,6 +65,8 @@ pub struct AccountResponseBody {
 /// POST ACCOUNT
 pub async fn create_account<T: AccountService>(
     State(bank_web): State<BankWeb<T>>,
+    _staff: Authorized<TellerAccess>,
+    Path(_branch_id): Path<Uuid>,
     Json(body): Json<AccountRequestBody>,
 ) -> (
     StatusCode,
@@ -121,3 +133,352 @@ pub async fn create_account<T: AccountService>(
 
     (StatusCode::CREATED, Json(Ok(response_data)))
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TransactionsResponseBody {
+    pub data: Vec<Transaction>,
+}
+
+pub async fn get_transactions<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(account_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<TransactionsResponseBody, String>>) {
+    if let Err(err) = principal
+        .authorize_account(&bank_web, account_id, AccountPermission::View)
+        .await
+    {
+        return error_response(err);
+    }
+
+    let account = match account_models::get_by_id(&bank_web.pool, account_id).await {
+        Ok(Some(account)) => account,
+        Ok(None) => return error_response(CustomerErrorReps::NotFound),
+        Err(err) => return error_response(err.into()),
+    };
+
+    match transactions::get_by_account_number(&bank_web.pool, &account.account_number).await {
+        Ok(transactions) => (
+            StatusCode::OK,
+            Json(Ok(TransactionsResponseBody { data: transactions })),
+        ),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FreezeRequestData {
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FreezeRequestBody {
+    pub freeze: FreezeRequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ClosureRequestData {
+    pub sweep_to_account_number: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ClosureRequestBody {
+    pub closure: ClosureRequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LifecycleResponseBody {
+    pub data: account_models::Account,
+}
+
+fn lifecycle_response(
+    result: Result<account_models::Account, CustomerErrorReps>,
+) -> (StatusCode, Json<Result<LifecycleResponseBody, String>>) {
+    match result {
+        Ok(account) => (StatusCode::OK, Json(Ok(LifecycleResponseBody { data: account }))),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn freeze<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    staff: Authorized<BranchManagerAccess>,
+    Path((branch_id, account_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<FreezeRequestBody>,
+) -> (StatusCode, Json<Result<LifecycleResponseBody, String>>) {
+    lifecycle_response(
+        account_models::freeze_account(
+            &bank_web.pool,
+            &staff.audit(),
+            branch_id,
+            account_id,
+            body.freeze.reason,
+        )
+        .await,
+    )
+}
+
+pub async fn unfreeze<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    staff: Authorized<BranchManagerAccess>,
+    Path((branch_id, account_id)): Path<(Uuid, Uuid)>,
+) -> (StatusCode, Json<Result<LifecycleResponseBody, String>>) {
+    lifecycle_response(
+        account_models::unfreeze_account(&bank_web.pool, &staff.audit(), branch_id, account_id)
+            .await,
+    )
+}
+
+pub async fn reactivate<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    staff: Authorized<TellerAccess>,
+    Path((branch_id, account_id)): Path<(Uuid, Uuid)>,
+) -> (StatusCode, Json<Result<LifecycleResponseBody, String>>) {
+    lifecycle_response(
+        account_models::reactivate_account(&bank_web.pool, &staff.audit(), branch_id, account_id)
+            .await,
+    )
+}
+
+pub async fn close<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    staff: Authorized<TellerAccess>,
+    Path((branch_id, account_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<ClosureRequestBody>,
+) -> (StatusCode, Json<Result<LifecycleResponseBody, String>>) {
+    lifecycle_response(
+        account_models::close_account(
+            &bank_web.pool,
+            &staff.audit(),
+            branch_id,
+            account_id,
+            body.closure.sweep_to_account_number.as_deref(),
+        )
+        .await,
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct OverdraftRequestData {
+    pub overdraft_limit: i32,
+    pub overdraft_rate_bps: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct OverdraftRequestBody {
+    pub overdraft: OverdraftRequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AccrualsResponseBody {
+    pub data: Vec<OverdraftAccrual>,
+}
+
+// Arranges, changes or (with a limit of 0) removes the account's overdraft
+pub async fn set_overdraft<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    staff: Authorized<BranchManagerAccess>,
+    Path((branch_id, account_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<OverdraftRequestBody>,
+) -> (StatusCode, Json<Result<LifecycleResponseBody, String>>) {
+    lifecycle_response(
+        account_models::set_overdraft(
+            &bank_web.pool,
+            &staff.audit(),
+            branch_id,
+            account_id,
+            body.overdraft.overdraft_limit,
+            body.overdraft.overdraft_rate_bps,
+        )
+        .await,
+    )
+}
+
+// Daily overdraft interest charged to the account, newest first
+pub async fn overdraft_accruals<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(account_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<AccrualsResponseBody, String>>) {
+    if let Err(err) = principal
+        .authorize_account(&bank_web, account_id, AccountPermission::View)
+        .await
+    {
+        return error_response(err);
+    }
+
+    match overdraft::get_accruals(&bank_web.pool, account_id).await {
+        Ok(accruals) => (
+            StatusCode::OK,
+            Json(Ok(AccrualsResponseBody { data: accruals })),
+        ),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HolderRequestData {
+    pub customer_id: Uuid,
+    pub role: AccountHolderRole,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HolderRequestBody {
+    pub holder: HolderRequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HolderResponseBody {
+    pub data: AccountHolder,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HoldersResponseBody {
+    pub data: Vec<AccountHolder>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CustomerAccountsResponseBody {
+    pub data: Vec<account_models::Account>,
+}
+
+pub async fn customer_accounts<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(customer_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<CustomerAccountsResponseBody, String>>) {
+    if principal.customer_id != customer_id {
+        return error_response(CustomerErrorReps::Forbidden);
+    }
+
+    match account_models::get_by_customer_id(&bank_web.pool, customer_id).await {
+        Ok(accounts) => (
+            StatusCode::OK,
+            Json(Ok(CustomerAccountsResponseBody { data: accounts })),
+        ),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+pub async fn holders<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(account_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<HoldersResponseBody, String>>) {
+    if let Err(err) = principal
+        .authorize_account(&bank_web, account_id, AccountPermission::View)
+        .await
+    {
+        return error_response(err);
+    }
+
+    match account_models::get_holders(&bank_web.pool, account_id).await {
+        Ok(holders) => (StatusCode::OK, Json(Ok(HoldersResponseBody { data: holders }))),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+pub async fn add_holder<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(account_id): Path<Uuid>,
+    Json(body): Json<HolderRequestBody>,
+) -> (StatusCode, Json<Result<HolderResponseBody, String>>) {
+    if let Err(err) = principal
+        .authorize_account(&bank_web, account_id, AccountPermission::ManageHolders)
+        .await
+    {
+        return error_response(err);
+    }
+
+    match account_models::add_holder(
+        &bank_web.pool,
+        &principal.audit(),
+        account_id,
+        body.holder.customer_id,
+        body.holder.role,
+    )
+    .await
+    {
+        Ok(holder) => (StatusCode::CREATED, Json(Ok(HolderResponseBody { data: holder }))),
+        Err(err) => error_response(err),
+    }
+}
+
+// The primary holder can remove anyone else; other holders can only remove themselves
+pub async fn remove_holder<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path((account_id, customer_id)): Path<(Uuid, Uuid)>,
+) -> (StatusCode, Json<Result<HolderResponseBody, String>>) {
+    let permission = if principal.customer_id == customer_id {
+        AccountPermission::View
+    } else {
+        AccountPermission::ManageHolders
+    };
+    if let Err(err) = principal
+        .authorize_account(&bank_web, account_id, permission)
+        .await
+    {
+        return error_response(err);
+    }
+
+    match account_models::remove_holder(&bank_web.pool, &principal.audit(), account_id, customer_id)
+        .await
+    {
+        Ok(holder) => (StatusCode::OK, Json(Ok(HolderResponseBody { data: holder }))),
+        Err(err) => error_response(err),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IbanResponseBody {
+    pub data: AccountIban,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IbanLookupResponseBody {
+    pub data: account_models::Account,
+}
+
+pub async fn get_iban<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(account_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<IbanResponseBody, String>>) {
+    if let Err(err) = principal
+        .authorize_account(&bank_web, account_id, AccountPermission::View)
+        .await
+    {
+        return error_response(err);
+    }
+
+    match iban::get_account_iban(&bank_web.pool, account_id).await {
+        Ok(Some(account_iban)) => {
+            (StatusCode::OK, Json(Ok(IbanResponseBody { data: account_iban })))
+        }
+        Ok(None) => error_response(CustomerErrorReps::NotFound),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+// Lets tellers confirm which account an IBAN presented at the counter belongs to. Any
+// account of the branch's bank can be looked up.
+pub async fn find_by_iban<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _staff: Authorized<TellerAccess>,
+    Path((branch_id, account_iban)): Path<(Uuid, String)>,
+) -> (StatusCode, Json<Result<IbanLookupResponseBody, String>>) {
+    let bank_id = match auth_models::get_branch_bank_id(&bank_web.pool, branch_id).await {
+        Ok(bank_id) => bank_id,
+        Err(err) => return error_response(err),
+    };
+
+    match iban::find_account_by_iban(&bank_web.pool, &account_iban).await {
+        Ok(Some(account)) if account.bank_id == bank_id => {
+            (StatusCode::OK, Json(Ok(IbanLookupResponseBody { data: account })))
+        }
+        Ok(_) => error_response(CustomerErrorReps::NotFound),
+        Err(err) => error_response(err),
+    }
+}
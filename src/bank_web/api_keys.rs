@@ -0,0 +1,144 @@
+use super::auth::{Authorized, BankAdminAccess};
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::models::api_keys::{self, ApiClient, ApiKey, IssuedApiKey};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ClientRequestData {
+    pub client_name: String,
+    pub account_id: Option<Uuid>,
+    pub merchant_country: Option<String>,
+    pub merchant_category: Option<String>,
+    pub scopes: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ClientRequestBody {
+    pub api_client: ClientRequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ClientResponseData {
+    pub client: ApiClient,
+    pub key: IssuedApiKey,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ClientResponseBody {
+    pub data: ClientResponseData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct KeyRequestData {
+    pub scopes: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct KeyRequestBody {
+    pub api_key: KeyRequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IssuedKeyResponseBody {
+    pub data: IssuedApiKey,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct KeyResponseBody {
+    pub data: ApiKey,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct KeysResponseBody {
+    pub data: Vec<ApiKey>,
+}
+
+pub async fn create_client<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    admin: Authorized<BankAdminAccess>,
+    Path(bank_id): Path<Uuid>,
+    Json(body): Json<ClientRequestBody>,
+) -> (StatusCode, Json<Result<ClientResponseBody, String>>) {
+    let data = body.api_client;
+    if let Err(err) = api_keys::validate_scopes(&data.scopes) {
+        return error_response(err);
+    }
+
+    let audit = admin.audit();
+    let client = match api_keys::create_client(
+        &bank_web.pool,
+        &audit,
+        bank_id,
+        data.client_name,
+        data.account_id,
+        data.merchant_country,
+        data.merchant_category,
+    )
+    .await
+    {
+        Ok(client) => client,
+        Err(err) => return error_response(err),
+    };
+
+    match api_keys::issue_key(&bank_web.pool, &audit, bank_id, client.id, data.scopes).await {
+        Ok(key) => (
+            StatusCode::CREATED,
+            Json(Ok(ClientResponseBody {
+                data: ClientResponseData { client, key },
+            })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn issue_key<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    admin: Authorized<BankAdminAccess>,
+    Path((bank_id, client_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<KeyRequestBody>,
+) -> (StatusCode, Json<Result<IssuedKeyResponseBody, String>>) {
+    match api_keys::issue_key(
+        &bank_web.pool,
+        &admin.audit(),
+        bank_id,
+        client_id,
+        body.api_key.scopes,
+    )
+    .await
+    {
+        Ok(key) => (
+            StatusCode::CREATED,
+            Json(Ok(IssuedKeyResponseBody { data: key })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn keys<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _admin: Authorized<BankAdminAccess>,
+    Path((bank_id, client_id)): Path<(Uuid, Uuid)>,
+) -> (StatusCode, Json<Result<KeysResponseBody, String>>) {
+    match api_keys::get_keys(&bank_web.pool, bank_id, client_id).await {
+        Ok(keys) => (StatusCode::OK, Json(Ok(KeysResponseBody { data: keys }))),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+pub async fn revoke_key<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    admin: Authorized<BankAdminAccess>,
+    Path((bank_id, key_id)): Path<(Uuid, Uuid)>,
+) -> (StatusCode, Json<Result<KeyResponseBody, String>>) {
+    match api_keys::revoke_key(&bank_web.pool, &admin.audit(), bank_id, key_id).await {
+        Ok(key) => (StatusCode::OK, Json(Ok(KeyResponseBody { data: key }))),
+        Err(err) => error_response(err),
+    }
+}
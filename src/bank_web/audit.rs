@@ -0,0 +1,85 @@
+use super::auth::{AuditorAccess, Authorized};
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::helper::validation::CustomerErrorReps;
+use crate::bank::models::audit::{self, AuditEvent, AuditFilter, ChainVerification};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EventsQuery {
+    pub entity_type: Option<String>,
+    pub entity_id: Option<Uuid>,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct VerifyQuery {
+    pub after_sequence: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct EventsResponseBody {
+    pub data: Vec<AuditEvent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct VerificationResponseBody {
+    pub data: ChainVerification,
+}
+
+pub async fn events<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _auditor: Authorized<AuditorAccess>,
+    Path(bank_id): Path<Uuid>,
+    Query(query): Query<EventsQuery>,
+) -> (StatusCode, Json<Result<EventsResponseBody, String>>) {
+    let filter = AuditFilter {
+        bank_id,
+        entity_type: query.entity_type,
+        entity_id: query.entity_id,
+        from: query.from,
+        to: query.to,
+        limit: query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+    };
+
+    match audit::query_events(&bank_web.pool, &filter).await {
+        Ok(events) => (StatusCode::OK, Json(Ok(EventsResponseBody { data: events }))),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+pub async fn verify<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _auditor: Authorized<AuditorAccess>,
+    Path(bank_id): Path<Uuid>,
+    Query(query): Query<VerifyQuery>,
+) -> (StatusCode, Json<Result<VerificationResponseBody, String>>) {
+    match audit::verify_chain(
+        &bank_web.pool,
+        bank_id,
+        query.after_sequence,
+        query.limit.unwrap_or(MAX_LIMIT).clamp(1, MAX_LIMIT),
+    )
+    .await
+    {
+        Ok(verification) => (
+            StatusCode::OK,
+            Json(Ok(VerificationResponseBody { data: verification })),
+        ),
+        Err(sqlx::Error::RowNotFound) => error_response(CustomerErrorReps::NotFound),
+        Err(err) => error_response(err.into()),
+    }
+}
@@ -0,0 +1,514 @@
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::helper::validation::CustomerErrorReps;
+use crate::bank::models::api_keys::{self, ApiKeyPrincipal};
+use crate::bank::models::audit::AuditContext;
+use crate::bank::models::auth::{self, Principal, StaffMember};
+use crate::bank::models::types::{AccountPermission, Role};
+use async_trait::async_trait;
+use axum::{
+    extract::{FromRequestParts, Path, State},
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        request::Parts,
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use uuid::Uuid;
+
+// RFC 7807 body returned when a request is rejected before reaching its handler
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+}
+
+impl Problem {
+    pub fn new(status: StatusCode, detail: impl Into<String>) -> Self {
+        Self {
+            problem_type: "about:blank".to_string(),
+            title: status.canonical_reason().unwrap_or_default().to_string(),
+            status: status.as_u16(),
+            detail: detail.into(),
+        }
+    }
+
+    pub fn unauthorized() -> Self {
+        Self::new(
+            StatusCode::UNAUTHORIZED,
+            "A valid bearer token is required.",
+        )
+    }
+
+    pub fn forbidden(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, detail)
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut response = (status, Json(self)).into_response();
+        response.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/problem+json"),
+        );
+        response
+    }
+}
+
+// Correlation id supplied by the caller or the edge proxy, recorded on audit events
+pub fn request_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("x-request-id")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.chars().take(255).collect())
+}
+
+async fn bearer_principal<T: AccountService>(
+    parts: &Parts,
+    bank_web: &BankWeb<T>,
+) -> Result<Principal, Problem> {
+    let token = parts
+        .headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(Problem::unauthorized)?;
+
+    let claims = bank_web
+        .jwt
+        .decode_token(token.trim())
+        .map_err(|_| Problem::unauthorized())?;
+
+    Ok(claims.into())
+}
+
+// The customer identified by the request's bearer token. Staff tokens are rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthenticatedCustomer {
+    pub customer_id: Uuid,
+    pub bank_id: Uuid,
+    pub request_id: Option<String>,
+}
+
+#[async_trait]
+impl<T: AccountService> FromRequestParts<BankWeb<T>> for AuthenticatedCustomer {
+    type Rejection = Problem;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        bank_web: &BankWeb<T>,
+    ) -> Result<Self, Self::Rejection> {
+        let principal = bearer_principal(parts, bank_web).await?;
+        if principal.role != Role::Customer {
+            return Err(Problem::forbidden(
+                "This operation is only available to customers.",
+            ));
+        }
+
+        Ok(AuthenticatedCustomer {
+            customer_id: principal.subject_id,
+            bank_id: principal.bank_id,
+            request_id: request_id(&parts.headers),
+        })
+    }
+}
+
+// The set of roles allowed through an `Authorized` extractor
+pub trait Policy {
+    const ROLES: &'static [Role];
+}
+
+pub struct TellerAccess;
+
+impl Policy for TellerAccess {
+    const ROLES: &'static [Role] = &[Role::Teller, Role::BranchManager, Role::BankAdmin];
+}
+
+pub struct BranchManagerAccess;
+
+impl Policy for BranchManagerAccess {
+    const ROLES: &'static [Role] = &[Role::BranchManager, Role::BankAdmin];
+}
+
+pub struct OversightAccess;
+
+impl Policy for OversightAccess {
+    const ROLES: &'static [Role] = &[Role::BranchManager, Role::BankAdmin, Role::Auditor];
+}
+
+pub struct AuditorAccess;
+
+impl Policy for AuditorAccess {
+    const ROLES: &'static [Role] = &[Role::BankAdmin, Role::Auditor];
+}
+
+pub struct BankAdminAccess;
+
+impl Policy for BankAdminAccess {
+    const ROLES: &'static [Role] = &[Role::BankAdmin];
+}
+
+// A staff principal holding one of `P::ROLES` and scoped to the route's `:branch_id` or
+// `:bank_id` segment. Routes with neither are rejected, so every staff route names the
+// bank or branch it acts on.
+pub struct Authorized<P> {
+    pub principal: Principal,
+    pub request_id: Option<String>,
+    policy: PhantomData<P>,
+}
+
+impl<P> Authorized<P> {
+    pub fn audit(&self) -> AuditContext {
+        AuditContext::new(
+            self.principal.role.as_str(),
+            Some(self.principal.subject_id),
+            self.request_id.clone(),
+        )
+    }
+}
+
+#[async_trait]
+impl<T: AccountService, P: Policy> FromRequestParts<BankWeb<T>> for Authorized<P> {
+    type Rejection = Problem;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        bank_web: &BankWeb<T>,
+    ) -> Result<Self, Self::Rejection> {
+        let principal = bearer_principal(parts, bank_web).await?;
+        if !P::ROLES.contains(&principal.role) {
+            return Err(Problem::forbidden(format!(
+                "Role {:?} is not permitted to perform this operation.",
+                principal.role
+            )));
+        }
+
+        let params = Path::<HashMap<String, String>>::from_request_parts(parts, bank_web)
+            .await
+            .map(|Path(params)| params)
+            .unwrap_or_default();
+        let scoped_id = |name: &str| -> Result<Option<Uuid>, Problem> {
+            params
+                .get(name)
+                .map(|value| Uuid::parse_str(value))
+                .transpose()
+                .map_err(|_| Problem::new(StatusCode::BAD_REQUEST, format!("Invalid {}.", name)))
+        };
+
+        let in_scope = match (scoped_id("branch_id")?, scoped_id("bank_id")?) {
+            (Some(branch_id), _) => {
+                let bank_id = match auth::get_branch_bank_id(&bank_web.pool, branch_id).await {
+                    Ok(bank_id) => bank_id,
+                    Err(CustomerErrorReps::BranchNotFound) => {
+                        return Err(Problem::new(StatusCode::NOT_FOUND, "Branch not found."))
+                    }
+                    Err(err) => {
+                        tracing::error!("failed to resolve branch scope: {}", err);
+                        return Err(Problem::new(
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            "Failed to resolve branch.",
+                        ));
+                    }
+                };
+                principal.in_scope(bank_id, Some(branch_id))
+            }
+            (None, Some(bank_id)) => principal.in_scope(bank_id, None),
+            (None, None) => false,
+        };
+
+        if !in_scope {
+            return Err(Problem::forbidden(
+                "The resource is outside of the principal's bank or branch.",
+            ));
+        }
+
+        Ok(Authorized {
+            principal,
+            request_id: request_id(&parts.headers),
+            policy: PhantomData,
+        })
+    }
+}
+
+impl AuthenticatedCustomer {
+    pub fn audit(&self) -> AuditContext {
+        AuditContext::new(
+            Role::Customer.as_str(),
+            Some(self.customer_id),
+            self.request_id.clone(),
+        )
+    }
+
+    // Succeeds when the customer holds the account in a role granting `permission`
+    pub async fn authorize_account<T: AccountService>(
+        &self,
+        bank_web: &BankWeb<T>,
+        account_id: Uuid,
+        permission: AccountPermission,
+    ) -> Result<(), CustomerErrorReps> {
+        match auth::customer_account_role(&bank_web.pool, self.customer_id, account_id).await? {
+            Some(role) if role.permits(permission) => Ok(()),
+            _ => Err(CustomerErrorReps::Forbidden),
+        }
+    }
+
+    pub async fn authorize_card<T: AccountService>(
+        &self,
+        bank_web: &BankWeb<T>,
+        card_number: &str,
+        permission: AccountPermission,
+    ) -> Result<(), CustomerErrorReps> {
+        match auth::customer_card_role(&bank_web.pool, self.customer_id, card_number).await? {
+            Some(role) if role.permits(permission) => Ok(()),
+            _ => Err(CustomerErrorReps::Forbidden),
+        }
+    }
+}
+
+// The scope an `ApiKeyClient` extractor requires
+pub trait Scope {
+    const SCOPE: &'static str;
+}
+
+pub struct PaymentsWrite;
+
+impl Scope for PaymentsWrite {
+    const SCOPE: &'static str = api_keys::SCOPE_PAYMENTS_WRITE;
+}
+
+pub struct RefundsWrite;
+
+impl Scope for RefundsWrite {
+    const SCOPE: &'static str = api_keys::SCOPE_REFUNDS_WRITE;
+}
+
+pub struct AtmWrite;
+
+impl Scope for AtmWrite {
+    const SCOPE: &'static str = api_keys::SCOPE_ATM_WRITE;
+}
+
+// A machine client authenticated with `Authorization: ApiKey <key>` whose key
+// carries `S::SCOPE`
+pub struct ApiKeyClient<S> {
+    pub principal: ApiKeyPrincipal,
+    pub request_id: Option<String>,
+    scope: PhantomData<S>,
+}
+
+impl<S> ApiKeyClient<S> {
+    pub fn audit(&self) -> AuditContext {
+        AuditContext::new(
+            "api_client",
+            Some(self.principal.client_id),
+            self.request_id.clone(),
+        )
+    }
+}
+
+#[async_trait]
+impl<T: AccountService, S: Scope> FromRequestParts<BankWeb<T>> for ApiKeyClient<S> {
+    type Rejection = Problem;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        bank_web: &BankWeb<T>,
+    ) -> Result<Self, Self::Rejection> {
+        let unauthorized = || Problem::new(StatusCode::UNAUTHORIZED, "A valid API key is required.");
+
+        let key = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("ApiKey "))
+            .ok_or_else(unauthorized)?;
+
+        let principal = match api_keys::authenticate_key(&bank_web.pool, key.trim()).await {
+            Ok(principal) => principal,
+            Err(CustomerErrorReps::Unauthorized) => return Err(unauthorized()),
+            Err(err) => {
+                tracing::error!("failed to authenticate api key: {}", err);
+                return Err(Problem::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to authenticate API key.",
+                ));
+            }
+        };
+
+        if !principal.has_scope(S::SCOPE) {
+            return Err(Problem::forbidden(format!(
+                "The API key is missing the {} scope.",
+                S::SCOPE
+            )));
+        }
+
+        Ok(ApiKeyClient {
+            principal,
+            request_id: request_id(&parts.headers),
+            scope: PhantomData,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LoginRequestData {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LoginRequestBody {
+    pub credentials: LoginRequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RegisterRequestData {
+    pub cic_number: String,
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RegisterRequestBody {
+    pub credentials: RegisterRequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StaffRequestData {
+    pub staff_name: String,
+    pub email: String,
+    pub password: String,
+    pub role: Role,
+    pub branch_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StaffRequestBody {
+    pub staff_member: StaffRequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StaffResponseBody {
+    pub data: StaffMember,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TokenResponseData {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TokenResponseBody {
+    pub data: TokenResponseData,
+}
+
+pub async fn register<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    headers: HeaderMap,
+    Json(body): Json<RegisterRequestBody>,
+) -> (StatusCode, Json<Result<(), String>>) {
+    let data = body.credentials;
+    let audit = AuditContext::new("anonymous", None, request_id(&headers));
+
+    match auth::register_credentials(
+        &bank_web.pool,
+        &audit,
+        &data.cic_number,
+        &data.email,
+        &data.password,
+    )
+    .await
+    {
+        Ok(()) => (StatusCode::CREATED, Json(Ok(()))),
+        Err(err) => error_response(err),
+    }
+}
+
+fn token_response<T: AccountService>(
+    bank_web: &BankWeb<T>,
+    principal: &Principal,
+) -> (StatusCode, Json<Result<TokenResponseBody, String>>) {
+    match bank_web.jwt.issue_token(principal) {
+        Ok(access_token) => (
+            StatusCode::OK,
+            Json(Ok(TokenResponseBody {
+                data: TokenResponseData {
+                    access_token,
+                    token_type: "Bearer".to_string(),
+                    expires_in: bank_web.jwt.ttl_seconds,
+                },
+            })),
+        ),
+        Err(err) => {
+            tracing::error!("failed to issue token: {}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(Err("Failed to issue token".to_string())),
+            )
+        }
+    }
+}
+
+pub async fn login<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    Json(body): Json<LoginRequestBody>,
+) -> (StatusCode, Json<Result<TokenResponseBody, String>>) {
+    let data = body.credentials;
+
+    match auth::authenticate(&bank_web.pool, &data.email, &data.password).await {
+        Ok(principal) => token_response(&bank_web, &principal),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn staff_login<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    Json(body): Json<LoginRequestBody>,
+) -> (StatusCode, Json<Result<TokenResponseBody, String>>) {
+    let data = body.credentials;
+
+    match auth::authenticate_staff(&bank_web.pool, &data.email, &data.password).await {
+        Ok(principal) => token_response(&bank_web, &principal),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn create_staff<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    admin: Authorized<BankAdminAccess>,
+    Path(bank_id): Path<Uuid>,
+    Json(body): Json<StaffRequestBody>,
+) -> (StatusCode, Json<Result<StaffResponseBody, String>>) {
+    let data = body.staff_member;
+
+    match auth::create_staff_member(
+        &bank_web.pool,
+        &admin.audit(),
+        data.staff_name,
+        data.email,
+        &data.password,
+        data.role,
+        bank_id,
+        data.branch_id,
+    )
+    .await
+    {
+        Ok(staff_member) => (
+            StatusCode::CREATED,
+            Json(Ok(StaffResponseBody { data: staff_member })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
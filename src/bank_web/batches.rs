@@ -0,0 +1,213 @@
+use super::auth::AuthenticatedCustomer;
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::helper::validation::CustomerErrorReps;
+use crate::bank::models::{
+    payment_batches::{
+        self, BatchSummary, BulkTransfer, NewPaymentBatch, NewPaymentItem, PaymentBatch,
+        PaymentBatchItem,
+    },
+    types::{AccountPermission, BatchExecutionMode},
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BatchRequestBody {
+    pub batch: BatchRequest,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BatchRequest {
+    #[serde(flatten)]
+    pub params: BatchParams,
+    pub transfers: Vec<BulkTransfer>,
+}
+
+// Batch level fields; taken from the query string when the transfers are sent as CSV
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BatchParams {
+    pub funding_account_number: String,
+    pub mode: Option<BatchExecutionMode>,
+    pub reference: Option<String>,
+    pub requested_execution_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BatchDetails {
+    pub batch: PaymentBatch,
+    pub summary: BatchSummary,
+    pub items: Vec<PaymentBatchItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BatchResponseBody {
+    pub data: BatchDetails,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SummaryResponseBody {
+    pub data: BatchSummary,
+}
+
+fn new_batch(params: BatchParams, items: Vec<NewPaymentItem>, today: NaiveDate) -> NewPaymentBatch {
+    let reference = params
+        .reference
+        .filter(|reference| !reference.trim().is_empty())
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    NewPaymentBatch {
+        debtor_account_number: params.funding_account_number,
+        execution_mode: params.mode.unwrap_or(BatchExecutionMode::BestEffort),
+        message_id: reference.clone(),
+        payment_information_id: reference,
+        requested_execution_date: params.requested_execution_date.unwrap_or(today),
+        items,
+    }
+}
+
+async fn batch_details(
+    pool: &sqlx::PgPool,
+    batch: PaymentBatch,
+) -> Result<BatchDetails, CustomerErrorReps> {
+    let items = payment_batches::get_items(pool, batch.id).await?;
+    Ok(BatchDetails {
+        summary: payment_batches::summarize(&batch, &items),
+        batch,
+        items,
+    })
+}
+
+async fn authorized_batch<T: AccountService>(
+    bank_web: &BankWeb<T>,
+    principal: &AuthenticatedCustomer,
+    batch_id: Uuid,
+) -> Result<PaymentBatch, CustomerErrorReps> {
+    let batch = payment_batches::get_batch(&bank_web.pool, batch_id)
+        .await?
+        .ok_or(CustomerErrorReps::NotFound)?;
+    principal
+        .authorize_account(bank_web, batch.debtor_account_id, AccountPermission::View)
+        .await?;
+    Ok(batch)
+}
+
+// Accepts a list of transfers from one funding account, either as a JSON body or as a
+// text/csv body with the batch fields in the query string. Every line is validated up
+// front and reported on individually.
+pub async fn post<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    headers: HeaderMap,
+    params: Option<Query<BatchParams>>,
+    body: String,
+) -> (StatusCode, Json<Result<BatchResponseBody, String>>) {
+    let today = Utc::now().naive_utc().date();
+    let is_csv = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |value| value.starts_with("text/csv"));
+
+    let (source_format, batch) = if is_csv {
+        let params = match params {
+            Some(Query(params)) => params,
+            None => {
+                return error_response(CustomerErrorReps::InvalidInput(
+                    "funding_account_number is required.".to_string(),
+                ))
+            }
+        };
+        match payment_batches::parse_csv(body.as_bytes()) {
+            Ok(items) => ("csv", new_batch(params, items, today)),
+            Err(err) => return error_response(err),
+        }
+    } else {
+        match serde_json::from_str::<BatchRequestBody>(&body) {
+            Ok(BatchRequestBody { batch }) => {
+                let items = batch
+                    .transfers
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, transfer)| transfer.into_item(index + 1))
+                    .collect();
+                ("json", new_batch(batch.params, items, today))
+            }
+            Err(err) => {
+                return error_response(CustomerErrorReps::InvalidInput(format!(
+                    "Invalid batch: {}",
+                    err
+                )))
+            }
+        }
+    };
+
+    let batch = match payment_batches::submit_batches(
+        &bank_web.pool,
+        &principal.audit(),
+        principal.customer_id,
+        source_format,
+        vec![batch],
+        today,
+    )
+    .await
+    {
+        Ok(mut batches) => batches.remove(0),
+        Err(err) => return error_response(err),
+    };
+
+    match batch_details(&bank_web.pool, batch).await {
+        Ok(details) => (
+            StatusCode::CREATED,
+            Json(Ok(BatchResponseBody { data: details })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+// The batch with the status of every line
+pub async fn get<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(batch_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<BatchResponseBody, String>>) {
+    let batch = match authorized_batch(&bank_web, &principal, batch_id).await {
+        Ok(batch) => batch,
+        Err(err) => return error_response(err),
+    };
+
+    match batch_details(&bank_web.pool, batch).await {
+        Ok(details) => (
+            StatusCode::OK,
+            Json(Ok(BatchResponseBody { data: details })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+// Line counts and amounts by outcome
+pub async fn summary<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(batch_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<SummaryResponseBody, String>>) {
+    let batch = match authorized_batch(&bank_web, &principal, batch_id).await {
+        Ok(batch) => batch,
+        Err(err) => return error_response(err),
+    };
+
+    match payment_batches::get_items(&bank_web.pool, batch.id).await {
+        Ok(items) => (
+            StatusCode::OK,
+            Json(Ok(SummaryResponseBody {
+                data: payment_batches::summarize(&batch, &items),
+            })),
+        ),
+        Err(err) => error_response(err.into()),
+    }
+}
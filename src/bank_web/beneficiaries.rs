@@ -0,0 +1,73 @@
+use super::auth::AuthenticatedCustomer;
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::helper::validation::CustomerErrorReps;
+use crate::bank::models::beneficiary;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RequestData {
+    pub beneficiary_name: String,
+    pub beneficiary_account_number: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RequestBody {
+    pub beneficiary: RequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ResponseData {
+    pub beneficiary_id: Uuid,
+    pub customer_id: Uuid,
+    pub beneficiary_name: String,
+    pub beneficiary_account_number: String,
+    pub inserted_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ResponseBody {
+    pub data: ResponseData,
+}
+
+pub async fn post<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(customer_id): Path<Uuid>,
+    Json(body): Json<RequestBody>,
+) -> (StatusCode, Json<Result<ResponseBody, String>>) {
+    if principal.customer_id != customer_id {
+        return error_response(CustomerErrorReps::Forbidden);
+    }
+
+    match beneficiary::add_beneficiary(
+        &bank_web.pool,
+        &principal.audit(),
+        customer_id,
+        body.beneficiary.beneficiary_name,
+        body.beneficiary.beneficiary_account_number,
+    )
+    .await
+    {
+        Ok(beneficiary) => (
+            StatusCode::CREATED,
+            Json(Ok(ResponseBody {
+                data: ResponseData {
+                    beneficiary_id: beneficiary.id,
+                    customer_id: beneficiary.customer.id,
+                    beneficiary_name: beneficiary.beneficiary_name,
+                    beneficiary_account_number: beneficiary.beneficiary_account_number,
+                    inserted_at: beneficiary.inserted_at,
+                },
+            })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
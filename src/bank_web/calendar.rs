@@ -0,0 +1,59 @@
+use super::auth::{Authorized, BankAdminAccess, OversightAccess};
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::helper::validation::CustomerErrorReps;
+use crate::bank::models::calendar::{self, CalendarOverview, CalendarSettings};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CalendarRequestBody {
+    pub calendar: CalendarSettings,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CalendarResponseBody {
+    pub data: CalendarOverview,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SettingsResponseBody {
+    pub data: CalendarSettings,
+}
+
+pub async fn get<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _staff: Authorized<OversightAccess>,
+    Path(bank_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<CalendarResponseBody, String>>) {
+    match calendar::get_overview(&bank_web.pool, bank_id, Utc::now().naive_utc()).await {
+        Ok(Some(overview)) => (
+            StatusCode::OK,
+            Json(Ok(CalendarResponseBody { data: overview })),
+        ),
+        Ok(None) => error_response(CustomerErrorReps::NotFound),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+// Weekend days and the same-day cut-off; holidays come from the holidays file
+pub async fn update<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    admin: Authorized<BankAdminAccess>,
+    Path(bank_id): Path<Uuid>,
+    Json(body): Json<CalendarRequestBody>,
+) -> (StatusCode, Json<Result<SettingsResponseBody, String>>) {
+    match calendar::update_settings(&bank_web.pool, &admin.audit(), bank_id, body.calendar).await {
+        Ok(settings) => (
+            StatusCode::OK,
+            Json(Ok(SettingsResponseBody { data: settings })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
@@ -1,6 +1,10 @@
-use super::BankWeb;
+use super::auth::{AuthenticatedCustomer, Authorized, BranchManagerAccess, TellerAccess};
+use super::{error_response, BankWeb};
 use crate::bank::accounts::AccountService;
-use crate::bank::cards::{self, Card, CardStatus, CardType};
+use crate::bank::cards::{self, Card, CardStatus, CardType, StatusChangeBy};
+use crate::bank::helper::validation::CustomerErrorReps;
+use crate::bank::models::accounts as account_models;
+use crate::bank::models::types::AccountPermission;
 use axum::{
     extract::{Path, State},
     http::StatusCode,
@@ -39,8 +43,17 @@ pub struct ResponseBody {
 
 pub async fn post<T: AccountService>(
     State(bank_web): State<BankWeb<T>>,
+    _staff: Authorized<TellerAccess>,
+    Path(branch_id): Path<Uuid>,
     Json(body): Json<RequestBody>,
 ) -> (StatusCode, Json<Result<ResponseBody, String>>) {
+    // Cards are only issued on accounts held at the teller's branch
+    match account_models::get_by_id(&bank_web.pool, body.card.account_id).await {
+        Ok(Some(account)) if account.branch_id == branch_id => {}
+        Ok(_) => return error_response(CustomerErrorReps::NotFound),
+        Err(err) => return error_response(err.into()),
+    }
+
     let card_id = match cards::insert(
         &bank_web.pool,
         body.card.card_number.clone(),
@@ -81,8 +94,16 @@ pub async fn post<T: AccountService>(
 
 pub async fn get<T: AccountService>(
     State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
     Path(card_number): Path<String>,
 ) -> (StatusCode, Json<Result<ResponseBody, String>>) {
+    if let Err(err) = principal
+        .authorize_card(&bank_web, &card_number, AccountPermission::View)
+        .await
+    {
+        return error_response(err);
+    }
+
     let card = match cards::get_by_card_number(&bank_web.pool, &card_number).await {
         Ok(card) => card,
         Err(_) => {
@@ -111,3 +132,73 @@ pub async fn get<T: AccountService>(
         })),
     )
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StatusRequestBody {
+    pub card_status: CardStatus,
+}
+
+fn status_response(
+    result: Result<Card, CustomerErrorReps>,
+) -> (StatusCode, Json<Result<ResponseBody, String>>) {
+    match result {
+        Ok(card) => (
+            StatusCode::OK,
+            Json(Ok(ResponseBody {
+                data: ResponseData {
+                    card_id: card.id,
+                    card_number: card.card_number,
+                    account_id: card.account_id,
+                    card_type: card.card_type,
+                    expiration_date: card.expiration_date.to_string(),
+                    card_status: card.card_status,
+                },
+            })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+// Cardholders can freeze a card and unfreeze it again; other changes go through staff
+pub async fn update_status<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(card_number): Path<String>,
+    Json(body): Json<StatusRequestBody>,
+) -> (StatusCode, Json<Result<ResponseBody, String>>) {
+    if let Err(err) = principal
+        .authorize_card(&bank_web, &card_number, AccountPermission::Transact)
+        .await
+    {
+        return error_response(err);
+    }
+
+    status_response(
+        cards::update_card_status(
+            &bank_web.pool,
+            &principal.audit(),
+            &card_number,
+            body.card_status,
+            StatusChangeBy::Customer,
+        )
+        .await,
+    )
+}
+
+pub async fn update_status_by_staff<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    staff: Authorized<BranchManagerAccess>,
+    Path((branch_id, card_number)): Path<(Uuid, String)>,
+    Json(body): Json<StatusRequestBody>,
+) -> (StatusCode, Json<Result<ResponseBody, String>>) {
+    status_response(
+        cards::update_card_status(
+            &bank_web.pool,
+            &staff.audit(),
+            &card_number,
+            body.card_status,
+            StatusChangeBy::Staff { branch_id },
+        )
+        .await,
+    )
+}
@@ -0,0 +1,88 @@
+use super::auth::{Authorized, BankAdminAccess, OversightAccess};
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::models::clearing::{self, ClearingItem, ClearingPosition};
+use crate::bank::models::payment_batches::ReasonCode;
+use crate::bank::models::types::ClearingItemStatus;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ItemsQuery {
+    pub status: Option<ClearingItemStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ReturnRequestData {
+    pub reason_code: ReasonCode,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ReturnRequestBody {
+    #[serde(rename = "return")]
+    pub return_item: ReturnRequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ItemResponseBody {
+    pub data: ClearingItem,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ItemsResponseBody {
+    pub data: Vec<ClearingItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PositionsResponseBody {
+    pub data: Vec<ClearingPosition>,
+}
+
+// Items the bank sent or is due to receive
+pub async fn items<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _staff: Authorized<OversightAccess>,
+    Path(bank_id): Path<Uuid>,
+    Query(query): Query<ItemsQuery>,
+) -> (StatusCode, Json<Result<ItemsResponseBody, String>>) {
+    match clearing::get_items(&bank_web.pool, bank_id, query.status).await {
+        Ok(items) => (StatusCode::OK, Json(Ok(ItemsResponseBody { data: items }))),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+pub async fn positions<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _staff: Authorized<OversightAccess>,
+    Path(bank_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<PositionsResponseBody, String>>) {
+    match clearing::get_positions(&bank_web.pool, bank_id).await {
+        Ok(positions) => (
+            StatusCode::OK,
+            Json(Ok(PositionsResponseBody { data: positions })),
+        ),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+// Refuses an incoming item before it settles; the debtor is credited back
+pub async fn return_item<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    admin: Authorized<BankAdminAccess>,
+    Path((bank_id, item_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<ReturnRequestBody>,
+) -> (StatusCode, Json<Result<ItemResponseBody, String>>) {
+    let rejection = (body.return_item.reason_code, body.return_item.reason);
+    match clearing::return_queued_item(&bank_web.pool, &admin.audit(), bank_id, item_id, rejection)
+        .await
+    {
+        Ok(item) => (StatusCode::OK, Json(Ok(ItemResponseBody { data: item }))),
+        Err(err) => error_response(err),
+    }
+}
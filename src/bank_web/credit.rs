@@ -0,0 +1,236 @@
+use super::auth::{
+    ApiKeyClient, AuthenticatedCustomer, Authorized, BranchManagerAccess, PaymentsWrite,
+};
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::helper::validation::CustomerErrorReps;
+use crate::bank::models::credit::{self, CreditAccount, CreditStatement, CreditTerms};
+use crate::bank::models::transactions::Transaction;
+use crate::bank::models::types::AccountPermission;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RequestData {
+    pub account_id: Uuid,
+    pub card_number: String,
+    pub credit_limit: i32,
+    pub statement_day: i32,
+    pub grace_period_days: i32,
+    pub apr_bps: i32,
+    pub min_payment_bps: i32,
+    pub min_payment_floor: i32,
+    pub late_fee: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RequestBody {
+    pub credit_account: RequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ResponseData {
+    pub credit_account_id: Uuid,
+    pub account_id: Uuid,
+    pub card_number: String,
+    pub credit_limit: i32,
+    pub current_balance: i32,
+    pub available_credit: i32,
+    pub statement_day: i32,
+    pub grace_period_days: i32,
+    pub apr_bps: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ResponseBody {
+    pub data: ResponseData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AmountData {
+    pub amount: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ChargeData {
+    pub amount: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ChargeRequestBody {
+    pub charge: ChargeData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PaymentRequestBody {
+    pub payment: AmountData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TransactionResponseBody {
+    pub data: Transaction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StatementsResponseBody {
+    pub data: Vec<CreditStatement>,
+}
+
+impl From<CreditAccount> for ResponseData {
+    fn from(credit_account: CreditAccount) -> Self {
+        Self {
+            credit_account_id: credit_account.id,
+            available_credit: credit_account.available_credit(),
+            account_id: credit_account.account_id,
+            card_number: credit_account.card_number,
+            credit_limit: credit_account.credit_limit,
+            current_balance: credit_account.current_balance,
+            statement_day: credit_account.statement_day,
+            grace_period_days: credit_account.grace_period_days,
+            apr_bps: credit_account.apr_bps,
+        }
+    }
+}
+
+pub async fn post<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    staff: Authorized<BranchManagerAccess>,
+    Path(branch_id): Path<Uuid>,
+    Json(body): Json<RequestBody>,
+) -> (StatusCode, Json<Result<ResponseBody, String>>) {
+    let data = body.credit_account;
+    let terms = CreditTerms {
+        credit_limit: data.credit_limit,
+        statement_day: data.statement_day,
+        grace_period_days: data.grace_period_days,
+        apr_bps: data.apr_bps,
+        min_payment_bps: data.min_payment_bps,
+        min_payment_floor: data.min_payment_floor,
+        late_fee: data.late_fee,
+    };
+
+    match credit::open_credit_account(
+        &bank_web.pool,
+        &staff.audit(),
+        branch_id,
+        data.account_id,
+        data.card_number,
+        terms,
+    )
+    .await
+    {
+        Ok(credit_account) => (
+            StatusCode::CREATED,
+            Json(Ok(ResponseBody {
+                data: credit_account.into(),
+            })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn get<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(account_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<ResponseBody, String>>) {
+    if let Err(err) = principal
+        .authorize_account(&bank_web, account_id, AccountPermission::View)
+        .await
+    {
+        return error_response(err);
+    }
+
+    match credit::get_by_account_id(&bank_web.pool, account_id).await {
+        Ok(Some(credit_account)) => (
+            StatusCode::OK,
+            Json(Ok(ResponseBody {
+                data: credit_account.into(),
+            })),
+        ),
+        Ok(None) => error_response(CustomerErrorReps::NotFound),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+// Charges are submitted by the acquiring merchant, whose registered country and category
+// the charge is screened against
+pub async fn charge<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    client: ApiKeyClient<PaymentsWrite>,
+    Path(card_number): Path<String>,
+    Json(body): Json<ChargeRequestBody>,
+) -> (StatusCode, Json<Result<TransactionResponseBody, String>>) {
+    match credit::charge(
+        &bank_web.pool,
+        &client.audit(),
+        client.principal.bank_id,
+        &card_number,
+        body.charge.amount,
+        client.principal.screening_context(),
+    )
+    .await
+    {
+        Ok(transaction) => (
+            StatusCode::CREATED,
+            Json(Ok(TransactionResponseBody { data: transaction })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn pay<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(account_id): Path<Uuid>,
+    Json(body): Json<PaymentRequestBody>,
+) -> (StatusCode, Json<Result<TransactionResponseBody, String>>) {
+    if let Err(err) = principal
+        .authorize_account(&bank_web, account_id, AccountPermission::Transact)
+        .await
+    {
+        return error_response(err);
+    }
+
+    match credit::make_payment(&bank_web.pool, &principal.audit(), account_id, body.payment.amount)
+        .await
+    {
+        Ok(transaction) => (
+            StatusCode::CREATED,
+            Json(Ok(TransactionResponseBody { data: transaction })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn statements<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(account_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<StatementsResponseBody, String>>) {
+    if let Err(err) = principal
+        .authorize_account(&bank_web, account_id, AccountPermission::View)
+        .await
+    {
+        return error_response(err);
+    }
+
+    let credit_account = match credit::get_by_account_id(&bank_web.pool, account_id).await {
+        Ok(Some(credit_account)) => credit_account,
+        Ok(None) => return error_response(CustomerErrorReps::NotFound),
+        Err(err) => return error_response(err.into()),
+    };
+
+    match credit::get_statements(&bank_web.pool, credit_account.id).await {
+        Ok(statements) => (
+            StatusCode::OK,
+            Json(Ok(StatementsResponseBody { data: statements })),
+        ),
+        Err(err) => error_response(err.into()),
+    }
+}
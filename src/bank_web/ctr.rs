@@ -0,0 +1,52 @@
+use super::auth::{AuditorAccess, Authorized};
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::models::ctr::{self, CashTransactionReport, CtrFiling};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ReportsQuery {
+    pub business_date: NaiveDate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ReportsResponseBody {
+    pub data: Vec<CashTransactionReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FilingResponseBody {
+    pub data: CtrFiling,
+}
+
+pub async fn reports<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _auditor: Authorized<AuditorAccess>,
+    Path(bank_id): Path<Uuid>,
+    Query(query): Query<ReportsQuery>,
+) -> (StatusCode, Json<Result<ReportsResponseBody, String>>) {
+    match ctr::get_reports(&bank_web.pool, bank_id, query.business_date).await {
+        Ok(reports) => (StatusCode::OK, Json(Ok(ReportsResponseBody { data: reports }))),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+// Returns the filing document and marks the day's reports as exported
+pub async fn export<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _auditor: Authorized<AuditorAccess>,
+    Path(bank_id): Path<Uuid>,
+    Query(query): Query<ReportsQuery>,
+) -> (StatusCode, Json<Result<FilingResponseBody, String>>) {
+    match ctr::export_filing(&bank_web.pool, bank_id, query.business_date).await {
+        Ok(filing) => (StatusCode::OK, Json(Ok(FilingResponseBody { data: filing }))),
+        Err(err) => error_response(err.into()),
+    }
+}
@@ -1,13 +1,17 @@
-use super::BankWeb;
+use super::auth::{AuthenticatedCustomer, Authorized, TellerAccess};
+use super::{ error_response, BankWeb };
+use crate::bank::helper::validation::CustomerErrorReps;
 use crate::bank::accounts::AccountService;
 use crate::bank::helper::{ request, response };
-use crate::bank::models::customer;
+use crate::bank::models::{ auth as auth_models, customer };
 use axum::{ extract::{ Path, State }, http::StatusCode, Json };
 use serde::{ Deserialize, Serialize };
 use uuid::Uuid;
 
 pub async fn post<T: AccountService>(
     State(bank_web): State<BankWeb<T>>,
+    staff: Authorized<TellerAccess>,
+    Path(branch_id): Path<Uuid>,
     Json(body): Json<request::CustomerBody>
 ) -> (StatusCode, Json<response::CustomerBody>) {
     let cic_number_validation = validate_input(&body.customer.cic_number, validate_cic_number);
@@ -117,17 +121,43 @@ pub async fn post<T: AccountService>(
         );
     }
 
-    let customer_id = customer
-        ::insert(
-            &bank_web.pool,
-            body.customer.customer_name,
-            body.customer.email,
-            body.customer.phone_number,
-            body.customer.cic_number
-        ).await
-        .unwrap();
+    // Goes through the model so the new customer is screened against the sanctions list
+    let created = match auth_models::get_branch_bank_id(&bank_web.pool, branch_id).await {
+        Ok(bank_id) =>
+            customer::create_customer(
+                &bank_web.pool,
+                &staff.audit(),
+                branch_id,
+                bank_id,
+                body.customer.customer_name.clone(),
+                body.customer.email.clone(),
+                body.customer.phone_number.clone(),
+                body.customer.cic_number.clone()
+            ).await,
+        Err(err) => Err(err),
+    };
 
-    let customer = customer::get(&bank_web.pool, customer_id).await.unwrap();
+    let customer = match created {
+        Ok(customer) => customer,
+        Err(err) => {
+            let (status_code, Json(result)) = error_response::<()>(err);
+            return (
+                status_code,
+                Json(response::CustomerBody {
+                    customer: response::CustomerData {
+                        id: Uuid::nil(),
+                        customer_name: body.customer.customer_name,
+                        email: body.customer.email,
+                        phone_number: body.customer.phone_number,
+                        cic_number: body.customer.cic_number,
+                        inserted_at: None,
+                        updated_at: None,
+                        error_message: result.err(),
+                    },
+                }),
+            );
+        }
+    };
 
     (
         StatusCode::CREATED,
@@ -148,8 +178,27 @@ pub async fn post<T: AccountService>(
 
 pub async fn get<T: AccountService>(
     State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
     Path(customer_id): Path<Uuid>
 ) -> (StatusCode, Json<response::CustomerBody>) {
+    if principal.customer_id != customer_id {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(response::CustomerBody {
+                customer: response::CustomerData {
+                    id: customer_id,
+                    customer_name: String::new(),
+                    email: String::new(),
+                    phone_number: String::new(),
+                    cic_number: String::new(),
+                    inserted_at: None,
+                    updated_at: None,
+                    error_message: Some(CustomerErrorReps::Forbidden.to_string()),
+                },
+            }),
+        );
+    }
+
     let customer = customer::get(&bank_web.pool, customer_id).await.unwrap();
 
     (
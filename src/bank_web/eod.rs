@@ -0,0 +1,38 @@
+use super::auth::{Authorized, OversightAccess};
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::models::eod::{self, BatchRun};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RunsQuery {
+    pub business_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RunsResponseBody {
+    pub data: Vec<BatchRun>,
+}
+
+// End-of-day checkpoints of a business date, yesterday by default
+pub async fn runs<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _staff: Authorized<OversightAccess>,
+    Path(bank_id): Path<Uuid>,
+    Query(query): Query<RunsQuery>,
+) -> (StatusCode, Json<Result<RunsResponseBody, String>>) {
+    let business_date = query
+        .business_date
+        .unwrap_or_else(|| Utc::now().naive_utc().date() - chrono::Duration::days(1));
+    match eod::get_runs(&bank_web.pool, bank_id, business_date).await {
+        Ok(runs) => (StatusCode::OK, Json(Ok(RunsResponseBody { data: runs }))),
+        Err(err) => error_response(err.into()),
+    }
+}
@@ -0,0 +1,221 @@
+use super::auth::{AuthenticatedCustomer, Authorized, BankAdminAccess, BranchManagerAccess};
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::models::fees::{self, FeeCharge, FeeSchedule, FeeScheduleSettings, FeeWaiver};
+use crate::bank::models::types::{AccountPermission, FeeType};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ScheduleRequestBody {
+    pub fee_schedule: FeeScheduleSettings,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WaiverRequestData {
+    pub fee_type: Option<FeeType>,
+    pub reason: String,
+    pub valid_until: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WaiverRequestBody {
+    pub waiver: WaiverRequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ReversalRequestData {
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ReversalRequestBody {
+    pub reversal: ReversalRequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ScheduleResponseBody {
+    pub data: FeeSchedule,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SchedulesResponseBody {
+    pub data: Vec<FeeSchedule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ChargeResponseBody {
+    pub data: FeeCharge,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ChargesResponseBody {
+    pub data: Vec<FeeCharge>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WaiverResponseBody {
+    pub data: FeeWaiver,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WaiversResponseBody {
+    pub data: Vec<FeeWaiver>,
+}
+
+pub async fn schedules<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _admin: Authorized<BankAdminAccess>,
+    Path(bank_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<SchedulesResponseBody, String>>) {
+    match fees::get_schedules(&bank_web.pool, bank_id).await {
+        Ok(schedules) => (
+            StatusCode::OK,
+            Json(Ok(SchedulesResponseBody { data: schedules })),
+        ),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+pub async fn create_schedule<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    admin: Authorized<BankAdminAccess>,
+    Path(bank_id): Path<Uuid>,
+    Json(body): Json<ScheduleRequestBody>,
+) -> (StatusCode, Json<Result<ScheduleResponseBody, String>>) {
+    match fees::create_schedule(&bank_web.pool, &admin.audit(), bank_id, body.fee_schedule).await {
+        Ok(schedule) => (
+            StatusCode::CREATED,
+            Json(Ok(ScheduleResponseBody { data: schedule })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn update_schedule<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    admin: Authorized<BankAdminAccess>,
+    Path((bank_id, schedule_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<ScheduleRequestBody>,
+) -> (StatusCode, Json<Result<ScheduleResponseBody, String>>) {
+    match fees::update_schedule(
+        &bank_web.pool,
+        &admin.audit(),
+        bank_id,
+        schedule_id,
+        body.fee_schedule,
+    )
+    .await
+    {
+        Ok(schedule) => (
+            StatusCode::OK,
+            Json(Ok(ScheduleResponseBody { data: schedule })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+// Fees charged, waived and reversed on the account, newest first
+pub async fn charges<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(account_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<ChargesResponseBody, String>>) {
+    if let Err(err) = principal
+        .authorize_account(&bank_web, account_id, AccountPermission::View)
+        .await
+    {
+        return error_response(err);
+    }
+
+    match fees::get_charges(&bank_web.pool, account_id).await {
+        Ok(charges) => (
+            StatusCode::OK,
+            Json(Ok(ChargesResponseBody { data: charges })),
+        ),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+pub async fn reverse<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    staff: Authorized<BranchManagerAccess>,
+    Path((bank_id, fee_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<ReversalRequestBody>,
+) -> (StatusCode, Json<Result<ChargeResponseBody, String>>) {
+    match fees::reverse_fee(
+        &bank_web.pool,
+        &staff.audit(),
+        bank_id,
+        fee_id,
+        body.reversal.reason,
+    )
+    .await
+    {
+        Ok(charge) => (
+            StatusCode::OK,
+            Json(Ok(ChargeResponseBody { data: charge })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn waivers<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _staff: Authorized<BranchManagerAccess>,
+    Path((bank_id, account_id)): Path<(Uuid, Uuid)>,
+) -> (StatusCode, Json<Result<WaiversResponseBody, String>>) {
+    match fees::get_waivers(&bank_web.pool, bank_id, account_id).await {
+        Ok(waivers) => (
+            StatusCode::OK,
+            Json(Ok(WaiversResponseBody { data: waivers })),
+        ),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+pub async fn grant_waiver<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    staff: Authorized<BranchManagerAccess>,
+    Path((bank_id, account_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<WaiverRequestBody>,
+) -> (StatusCode, Json<Result<WaiverResponseBody, String>>) {
+    let data = body.waiver;
+    match fees::grant_waiver(
+        &bank_web.pool,
+        &staff.audit(),
+        bank_id,
+        account_id,
+        data.fee_type,
+        data.reason,
+        data.valid_until,
+    )
+    .await
+    {
+        Ok(waiver) => (
+            StatusCode::CREATED,
+            Json(Ok(WaiverResponseBody { data: waiver })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn revoke_waiver<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    staff: Authorized<BranchManagerAccess>,
+    Path((bank_id, waiver_id)): Path<(Uuid, Uuid)>,
+) -> (StatusCode, Json<Result<WaiverResponseBody, String>>) {
+    match fees::revoke_waiver(&bank_web.pool, &staff.audit(), bank_id, waiver_id).await {
+        Ok(waiver) => (
+            StatusCode::OK,
+            Json(Ok(WaiverResponseBody { data: waiver })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
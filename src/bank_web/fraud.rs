@@ -0,0 +1,145 @@
+use super::auth::{Authorized, BankAdminAccess};
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::models::fraud::{self, FraudEvaluation, FraudRule, RuleCondition};
+use crate::bank::models::types::FraudDecision;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RuleRequestData {
+    pub rule_name: String,
+    pub condition: RuleCondition,
+    pub action: FraudDecision,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RuleRequestBody {
+    pub fraud_rule: RuleRequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RuleResponseBody {
+    pub data: FraudRule,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RulesResponseBody {
+    pub data: Vec<FraudRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ReviewResponseBody {
+    pub data: FraudEvaluation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ReviewsResponseBody {
+    pub data: Vec<FraudEvaluation>,
+}
+
+pub async fn rules<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _admin: Authorized<BankAdminAccess>,
+    Path(bank_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<RulesResponseBody, String>>) {
+    match fraud::get_rules(&bank_web.pool, bank_id).await {
+        Ok(rules) => (StatusCode::OK, Json(Ok(RulesResponseBody { data: rules }))),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+pub async fn create_rule<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    admin: Authorized<BankAdminAccess>,
+    Path(bank_id): Path<Uuid>,
+    Json(body): Json<RuleRequestBody>,
+) -> (StatusCode, Json<Result<RuleResponseBody, String>>) {
+    let data = body.fraud_rule;
+    match fraud::create_rule(
+        &bank_web.pool,
+        &admin.audit(),
+        bank_id,
+        data.rule_name,
+        data.condition,
+        data.action,
+    )
+    .await
+    {
+        Ok(rule) => (StatusCode::CREATED, Json(Ok(RuleResponseBody { data: rule }))),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn update_rule<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    admin: Authorized<BankAdminAccess>,
+    Path((bank_id, rule_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<RuleRequestBody>,
+) -> (StatusCode, Json<Result<RuleResponseBody, String>>) {
+    let data = body.fraud_rule;
+    match fraud::update_rule(
+        &bank_web.pool,
+        &admin.audit(),
+        bank_id,
+        rule_id,
+        data.rule_name,
+        data.condition,
+        data.action,
+        data.enabled.unwrap_or(true),
+    )
+    .await
+    {
+        Ok(rule) => (StatusCode::OK, Json(Ok(RuleResponseBody { data: rule }))),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn reviews<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _admin: Authorized<BankAdminAccess>,
+    Path(bank_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<ReviewsResponseBody, String>>) {
+    match fraud::get_pending_reviews(&bank_web.pool, bank_id).await {
+        Ok(reviews) => (
+            StatusCode::OK,
+            Json(Ok(ReviewsResponseBody { data: reviews })),
+        ),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+pub async fn approve<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    admin: Authorized<BankAdminAccess>,
+    Path((bank_id, review_id)): Path<(Uuid, Uuid)>,
+) -> (StatusCode, Json<Result<ReviewResponseBody, String>>) {
+    resolve(&bank_web, &admin, bank_id, review_id, true).await
+}
+
+pub async fn reject<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    admin: Authorized<BankAdminAccess>,
+    Path((bank_id, review_id)): Path<(Uuid, Uuid)>,
+) -> (StatusCode, Json<Result<ReviewResponseBody, String>>) {
+    resolve(&bank_web, &admin, bank_id, review_id, false).await
+}
+
+async fn resolve<T: AccountService>(
+    bank_web: &BankWeb<T>,
+    admin: &Authorized<BankAdminAccess>,
+    bank_id: Uuid,
+    review_id: Uuid,
+    approve: bool,
+) -> (StatusCode, Json<Result<ReviewResponseBody, String>>) {
+    match fraud::resolve_review(&bank_web.pool, &admin.audit(), bank_id, review_id, approve).await {
+        Ok(review) => (StatusCode::OK, Json(Ok(ReviewResponseBody { data: review }))),
+        Err(err) => error_response(err),
+    }
+}
@@ -0,0 +1,115 @@
+use super::auth::AuthenticatedCustomer;
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::helper::validation::CustomerErrorReps;
+use crate::bank::models::{
+    iso20022,
+    payment_batches::{self, PaymentBatch},
+    types::AccountPermission,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BatchesResponseBody {
+    pub data: Vec<PaymentBatch>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StatementQuery {
+    pub date: NaiveDate,
+}
+
+fn xml_response(result: Result<String, CustomerErrorReps>) -> Response {
+    match result {
+        Ok(xml) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/xml")],
+            xml,
+        )
+            .into_response(),
+        Err(err) => error_response::<()>(err).into_response(),
+    }
+}
+
+// Accepts a pain.001 message. Batches due today are carried out before responding; the
+// rest wait for their requested execution date.
+pub async fn initiate<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    body: String,
+) -> (StatusCode, Json<Result<BatchesResponseBody, String>>) {
+    let batches = match iso20022::parse_pain001(&body) {
+        Ok(batches) => batches,
+        Err(err) => return error_response(err),
+    };
+
+    match payment_batches::submit_batches(
+        &bank_web.pool,
+        &principal.audit(),
+        principal.customer_id,
+        "pain.001",
+        batches,
+        Utc::now().naive_utc().date(),
+    )
+    .await
+    {
+        Ok(batches) => (
+            StatusCode::CREATED,
+            Json(Ok(BatchesResponseBody { data: batches })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+// pain.002 status report for one batch
+pub async fn status_report<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(batch_id): Path<Uuid>,
+) -> Response {
+    let batch = match payment_batches::get_batch(&bank_web.pool, batch_id).await {
+        Ok(Some(batch)) => batch,
+        Ok(None) => return error_response::<()>(CustomerErrorReps::NotFound).into_response(),
+        Err(err) => return error_response::<()>(err.into()).into_response(),
+    };
+    if let Err(err) = principal
+        .authorize_account(&bank_web, batch.debtor_account_id, AccountPermission::View)
+        .await
+    {
+        return error_response::<()>(err).into_response();
+    }
+
+    match payment_batches::get_items(&bank_web.pool, batch.id).await {
+        Ok(items) => xml_response(iso20022::pain002(&batch, &items, Utc::now().naive_utc())),
+        Err(err) => error_response::<()>(err.into()).into_response(),
+    }
+}
+
+// camt.053 end-of-day statement for one account and date
+pub async fn statement<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(account_id): Path<Uuid>,
+    Query(query): Query<StatementQuery>,
+) -> Response {
+    if let Err(err) = principal
+        .authorize_account(&bank_web, account_id, AccountPermission::View)
+        .await
+    {
+        return error_response::<()>(err).into_response();
+    }
+
+    match iso20022::get_account_statement(&bank_web.pool, account_id, query.date).await {
+        Ok(Some(statement)) => xml_response(iso20022::camt053(&statement, Utc::now().naive_utc())),
+        Ok(None) => error_response::<()>(CustomerErrorReps::NotFound).into_response(),
+        Err(err) => error_response::<()>(err.into()).into_response(),
+    }
+}
@@ -0,0 +1,229 @@
+use super::auth::{ApiKeyClient, AuthenticatedCustomer, PaymentsWrite};
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::helper::validation::CustomerErrorReps;
+use crate::bank::models::mandates::{self, Collection, Mandate};
+use crate::bank::models::refunds::Refund;
+use crate::bank::models::types::{AccountPermission, Status};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RequestData {
+    pub debtor_account_id: Uuid,
+    pub creditor_account_number: String,
+    pub creditor_name: String,
+    pub reference: String,
+    pub max_amount: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RequestBody {
+    pub mandate: RequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ResponseBody {
+    pub data: Mandate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CollectionRequestData {
+    pub amount: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CollectionRequestBody {
+    pub collection: CollectionRequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CollectionResponseBody {
+    pub data: Collection,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CollectionsResponseBody {
+    pub data: Vec<Collection>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RefundResponseBody {
+    pub data: Refund,
+}
+
+async fn authorize_mandate<T: AccountService>(
+    bank_web: &BankWeb<T>,
+    principal: &AuthenticatedCustomer,
+    mandate_id: Uuid,
+    permission: AccountPermission,
+) -> Result<Mandate, CustomerErrorReps> {
+    let mandate = mandates::get_mandate(&bank_web.pool, mandate_id)
+        .await?
+        .ok_or(CustomerErrorReps::NotFound)?;
+    principal
+        .authorize_account(bank_web, mandate.debtor_account_id, permission)
+        .await?;
+
+    Ok(mandate)
+}
+
+pub async fn post<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Json(body): Json<RequestBody>,
+) -> (StatusCode, Json<Result<ResponseBody, String>>) {
+    let data = body.mandate;
+    if let Err(err) = principal
+        .authorize_account(&bank_web, data.debtor_account_id, AccountPermission::Manage)
+        .await
+    {
+        return error_response(err);
+    }
+
+    match mandates::create_mandate(
+        &bank_web.pool,
+        &principal.audit(),
+        data.debtor_account_id,
+        data.creditor_account_number,
+        data.creditor_name,
+        data.reference,
+        data.max_amount,
+    )
+    .await
+    {
+        Ok(mandate) => (
+            StatusCode::CREATED,
+            Json(Ok(ResponseBody { data: mandate })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn get<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(mandate_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<ResponseBody, String>>) {
+    match authorize_mandate(&bank_web, &principal, mandate_id, AccountPermission::View).await {
+        Ok(mandate) => (StatusCode::OK, Json(Ok(ResponseBody { data: mandate }))),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn revoke<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(mandate_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<ResponseBody, String>>) {
+    if let Err(err) =
+        authorize_mandate(&bank_web, &principal, mandate_id, AccountPermission::Manage).await
+    {
+        return error_response(err);
+    }
+
+    match mandates::revoke_mandate(&bank_web.pool, &principal.audit(), mandate_id).await {
+        Ok(mandate) => (StatusCode::OK, Json(Ok(ResponseBody { data: mandate }))),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn collect<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    client: ApiKeyClient<PaymentsWrite>,
+    Path(mandate_id): Path<Uuid>,
+    Json(body): Json<CollectionRequestBody>,
+) -> (StatusCode, Json<Result<CollectionResponseBody, String>>) {
+    // Clients only collect into their own account; other mandates are indistinguishable
+    // from ones that do not exist
+    let creditor_account_id = match client.principal.account_id {
+        Some(account_id) => account_id,
+        None => return error_response(CustomerErrorReps::NotFound),
+    };
+    match mandates::get_creditor_mandate(
+        &bank_web.pool,
+        client.principal.bank_id,
+        creditor_account_id,
+        mandate_id,
+    )
+    .await
+    {
+        Ok(Some(_)) => {}
+        Ok(None) => return error_response(CustomerErrorReps::NotFound),
+        Err(err) => return error_response(err.into()),
+    }
+
+    match mandates::collect(
+        &bank_web.pool,
+        &client.audit(),
+        mandate_id,
+        body.collection.amount,
+    )
+    .await
+    {
+        Ok(collection) => {
+            let status_code = if collection.status == Status::Rejected {
+                StatusCode::UNPROCESSABLE_ENTITY
+            } else {
+                StatusCode::CREATED
+            };
+            (
+                status_code,
+                Json(Ok(CollectionResponseBody { data: collection })),
+            )
+        }
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn collections<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(mandate_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<CollectionsResponseBody, String>>) {
+    if let Err(err) =
+        authorize_mandate(&bank_web, &principal, mandate_id, AccountPermission::View).await
+    {
+        return error_response(err);
+    }
+
+    match mandates::get_collections(&bank_web.pool, mandate_id).await {
+        Ok(collections) => (
+            StatusCode::OK,
+            Json(Ok(CollectionsResponseBody { data: collections })),
+        ),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+pub async fn refund<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path((mandate_id, collection_id)): Path<(Uuid, Uuid)>,
+) -> (StatusCode, Json<Result<RefundResponseBody, String>>) {
+    if let Err(err) =
+        authorize_mandate(&bank_web, &principal, mandate_id, AccountPermission::Manage).await
+    {
+        return error_response(err);
+    }
+
+    match mandates::refund_collection(
+        &bank_web.pool,
+        &principal.audit(),
+        mandate_id,
+        collection_id,
+    )
+    .await
+    {
+        Ok(refund) => (
+            StatusCode::CREATED,
+            Json(Ok(RefundResponseBody { data: refund })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
@@ -1,4 +1,7 @@
+use super::auth::{ApiKeyClient, PaymentsWrite};
 use super::BankWeb;
+use crate::bank::models::cards;
+use crate::bank::models::fraud::{self, ScreenedOperation};
 use crate::bank::payments::{Status};
 use crate::bank::{accounts::AccountService, payments};
 use axum::{
@@ -60,6 +63,7 @@ pub fn create_invalid_data_response<T>(
 
 pub async fn post<T: AccountService>(
     State(bank_web): State<BankWeb<T>>,
+    client: ApiKeyClient<PaymentsWrite>,
     Json(body): Json<RequestBody>,
 ) -> (
     StatusCode,
@@ -97,6 +101,42 @@ pub async fn post<T: AccountService>(
 
         return (StatusCode::PAYMENT_REQUIRED, Json(Err(invalid_data_response)));
     }
+
+    // The merchant waits for an answer, so a payment held for fraud review is declined
+    // like a denied one; the review stays queued
+    let screened = match cards::get_by_id(&bank_web.pool, body.payment.card_id).await {
+        Ok(Some(card)) if card.bank_id == client.principal.bank_id => {
+            match fraud::screen(
+                &bank_web.pool,
+                &client.audit(),
+                ScreenedOperation::CardPayment {
+                    card_number: card.card_number,
+                    amount: body.payment.amount,
+                },
+                client.principal.screening_context(),
+            )
+            .await
+            {
+                Ok(screening) => screening.commit().await.map_err(|err| err.to_string()),
+                Err(err) => Err(err.to_string()),
+            }
+        }
+        Ok(_) => Err("card not found".to_string()),
+        Err(err) => Err(err.to_string()),
+    };
+    if let Err(error_message) = screened {
+        let response_data = ResponseData {
+            payment_id: Uuid::nil(),
+            amount: body.payment.amount,
+            card_id: body.payment.card_id,
+            status: Status::Declined,
+        };
+        let invalid_data_response =
+            create_invalid_data_response(response_data, &error_message, 402);
+
+        return (StatusCode::PAYMENT_REQUIRED, Json(Err(invalid_data_response)));
+    }
+
     let payment_id = payments::insert(
         &bank_web.pool,
         body.payment.card_id,
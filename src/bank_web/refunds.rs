@@ -6,6 +6,7 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::auth::{ApiKeyClient, RefundsWrite};
 use super::BankWeb;
 use crate::bank::{accounts::AccountService, refunds};
 
@@ -33,6 +34,7 @@ pub struct ResponseBody {
 
 pub async fn post<T: AccountService>(
     State(bank_web): State<BankWeb<T>>,
+    _client: ApiKeyClient<RefundsWrite>,
     Path(payment_id): Path<Uuid>,
     Json(body): Json<RequestBody>,
 ) -> (StatusCode, Json<ResponseBody>) {
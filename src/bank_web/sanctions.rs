@@ -0,0 +1,68 @@
+use super::auth::{Authorized, BankAdminAccess};
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::models::sanctions::{self, SanctionsHit};
+use crate::bank::models::types::SanctionsHitStatus;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HitsQuery {
+    pub status: Option<SanctionsHitStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct HitResponseBody {
+    pub data: SanctionsHit,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct HitsResponseBody {
+    pub data: Vec<SanctionsHit>,
+}
+
+pub async fn hits<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _admin: Authorized<BankAdminAccess>,
+    Path(bank_id): Path<Uuid>,
+    Query(query): Query<HitsQuery>,
+) -> (StatusCode, Json<Result<HitsResponseBody, String>>) {
+    match sanctions::get_hits(&bank_web.pool, bank_id, query.status).await {
+        Ok(hits) => (StatusCode::OK, Json(Ok(HitsResponseBody { data: hits }))),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+pub async fn confirm<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    admin: Authorized<BankAdminAccess>,
+    Path((bank_id, hit_id)): Path<(Uuid, Uuid)>,
+) -> (StatusCode, Json<Result<HitResponseBody, String>>) {
+    resolve(&bank_web, &admin, bank_id, hit_id, SanctionsHitStatus::Confirmed).await
+}
+
+pub async fn clear<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    admin: Authorized<BankAdminAccess>,
+    Path((bank_id, hit_id)): Path<(Uuid, Uuid)>,
+) -> (StatusCode, Json<Result<HitResponseBody, String>>) {
+    resolve(&bank_web, &admin, bank_id, hit_id, SanctionsHitStatus::Cleared).await
+}
+
+async fn resolve<T: AccountService>(
+    bank_web: &BankWeb<T>,
+    admin: &Authorized<BankAdminAccess>,
+    bank_id: Uuid,
+    hit_id: Uuid,
+    status: SanctionsHitStatus,
+) -> (StatusCode, Json<Result<HitResponseBody, String>>) {
+    match sanctions::resolve_hit(&bank_web.pool, &admin.audit(), bank_id, hit_id, status).await {
+        Ok(hit) => (StatusCode::OK, Json(Ok(HitResponseBody { data: hit }))),
+        Err(err) => error_response(err),
+    }
+}
@@ -0,0 +1,173 @@
+use super::auth::AuthenticatedCustomer;
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::helper::validation::CustomerErrorReps;
+use crate::bank::models::standing_orders::{
+    self, NewStandingOrder, StandingOrder, StandingOrderExecution,
+};
+use crate::bank::models::types::{AccountPermission, StandingOrderFrequency};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RequestData {
+    pub source_account_id: Uuid,
+    pub beneficiary_id: Uuid,
+    pub amount: i32,
+    pub frequency: StandingOrderFrequency,
+    pub cron_expression: Option<String>,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RequestBody {
+    pub standing_order: RequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ResponseBody {
+    pub data: StandingOrder,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ExecutionsResponseBody {
+    pub data: Vec<StandingOrderExecution>,
+}
+
+async fn authorize_standing_order<T: AccountService>(
+    bank_web: &BankWeb<T>,
+    principal: &AuthenticatedCustomer,
+    standing_order_id: Uuid,
+    permission: AccountPermission,
+) -> Result<StandingOrder, CustomerErrorReps> {
+    let standing_order = standing_orders::get_standing_order(&bank_web.pool, standing_order_id)
+        .await?
+        .ok_or(CustomerErrorReps::NotFound)?;
+    principal
+        .authorize_account(bank_web, standing_order.source_account_id, permission)
+        .await?;
+
+    Ok(standing_order)
+}
+
+pub async fn post<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Json(body): Json<RequestBody>,
+) -> (StatusCode, Json<Result<ResponseBody, String>>) {
+    let data = body.standing_order;
+    if let Err(err) = principal
+        .authorize_account(&bank_web, data.source_account_id, AccountPermission::Manage)
+        .await
+    {
+        return error_response(err);
+    }
+
+    let new_order = NewStandingOrder {
+        source_account_id: data.source_account_id,
+        beneficiary_id: data.beneficiary_id,
+        amount: data.amount,
+        frequency: data.frequency,
+        cron_expression: data.cron_expression,
+        start_date: data.start_date,
+        end_date: data.end_date,
+    };
+
+    match standing_orders::create_standing_order(&bank_web.pool, &principal.audit(), new_order).await {
+        Ok(standing_order) => (
+            StatusCode::CREATED,
+            Json(Ok(ResponseBody {
+                data: standing_order,
+            })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn get<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(standing_order_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<ResponseBody, String>>) {
+    match authorize_standing_order(
+        &bank_web,
+        &principal,
+        standing_order_id,
+        AccountPermission::View,
+    )
+    .await
+    {
+        Ok(standing_order) => (
+            StatusCode::OK,
+            Json(Ok(ResponseBody {
+                data: standing_order,
+            })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn cancel<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(standing_order_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<ResponseBody, String>>) {
+    if let Err(err) = authorize_standing_order(
+        &bank_web,
+        &principal,
+        standing_order_id,
+        AccountPermission::Manage,
+    )
+    .await
+    {
+        return error_response(err);
+    }
+
+    match standing_orders::cancel_standing_order(
+        &bank_web.pool,
+        &principal.audit(),
+        standing_order_id,
+    )
+    .await
+    {
+        Ok(standing_order) => (
+            StatusCode::OK,
+            Json(Ok(ResponseBody {
+                data: standing_order,
+            })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn executions<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    principal: AuthenticatedCustomer,
+    Path(standing_order_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<ExecutionsResponseBody, String>>) {
+    if let Err(err) = authorize_standing_order(
+        &bank_web,
+        &principal,
+        standing_order_id,
+        AccountPermission::View,
+    )
+    .await
+    {
+        return error_response(err);
+    }
+
+    match standing_orders::get_executions(&bank_web.pool, standing_order_id).await {
+        Ok(executions) => (
+            StatusCode::OK,
+            Json(Ok(ExecutionsResponseBody { data: executions })),
+        ),
+        Err(err) => error_response(err.into()),
+    }
+}
@@ -0,0 +1,111 @@
+use super::auth::{ApiKeyClient, AtmWrite, Authorized, TellerAccess};
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::models::teller;
+use crate::bank::models::transactions::Transaction;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RequestData {
+    pub account_number: String,
+    pub amount: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RequestBody {
+    pub cash: RequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AtmRequestData {
+    pub card_number: String,
+    pub amount: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AtmRequestBody {
+    pub withdrawal: AtmRequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ResponseBody {
+    pub data: Transaction,
+}
+
+pub async fn deposit<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    staff: Authorized<TellerAccess>,
+    Path(branch_id): Path<Uuid>,
+    Json(body): Json<RequestBody>,
+) -> (StatusCode, Json<Result<ResponseBody, String>>) {
+    match teller::cash_deposit(
+        &bank_web.pool,
+        &staff.audit(),
+        branch_id,
+        &body.cash.account_number,
+        body.cash.amount,
+    )
+    .await
+    {
+        Ok(transaction) => (
+            StatusCode::CREATED,
+            Json(Ok(ResponseBody { data: transaction })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn withdraw<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    staff: Authorized<TellerAccess>,
+    Path(branch_id): Path<Uuid>,
+    Json(body): Json<RequestBody>,
+) -> (StatusCode, Json<Result<ResponseBody, String>>) {
+    match teller::cash_withdrawal(
+        &bank_web.pool,
+        &staff.audit(),
+        branch_id,
+        &body.cash.account_number,
+        body.cash.amount,
+    )
+    .await
+    {
+        Ok(transaction) => (
+            StatusCode::CREATED,
+            Json(Ok(ResponseBody { data: transaction })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+// Submitted by one of the bank's ATMs at the branch once the cardholder has entered
+// their PIN
+pub async fn atm_withdraw<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    atm: ApiKeyClient<AtmWrite>,
+    Path(branch_id): Path<Uuid>,
+    Json(body): Json<AtmRequestBody>,
+) -> (StatusCode, Json<Result<ResponseBody, String>>) {
+    match teller::atm_withdrawal(
+        &bank_web.pool,
+        &atm.audit(),
+        atm.principal.bank_id,
+        branch_id,
+        &body.withdrawal.card_number,
+        body.withdrawal.amount,
+    )
+    .await
+    {
+        Ok(transaction) => (
+            StatusCode::CREATED,
+            Json(Ok(ResponseBody { data: transaction })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
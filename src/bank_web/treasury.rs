@@ -0,0 +1,111 @@
+use super::auth::{AuditorAccess, Authorized, BranchManagerAccess, OversightAccess};
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::models::treasury::{
+    self, BranchCashPosition, BranchCashTransfer, LoanApproval, ReserveCoverage,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TransferRequestData {
+    pub to_branch_id: Uuid,
+    pub amount: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TransferRequestBody {
+    pub transfer: TransferRequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TransferResponseBody {
+    pub data: BranchCashTransfer,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LoanApprovalResponseBody {
+    pub data: LoanApproval,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PositionsResponseBody {
+    pub data: Vec<BranchCashPosition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CoverageResponseBody {
+    pub data: Vec<ReserveCoverage>,
+}
+
+pub async fn transfer<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    staff: Authorized<BranchManagerAccess>,
+    Path(branch_id): Path<Uuid>,
+    Json(body): Json<TransferRequestBody>,
+) -> (StatusCode, Json<Result<TransferResponseBody, String>>) {
+    match treasury::transfer_cash(
+        &bank_web.pool,
+        &staff.audit(),
+        branch_id,
+        body.transfer.to_branch_id,
+        body.transfer.amount,
+    )
+    .await
+    {
+        Ok(cash_transfer) => (
+            StatusCode::CREATED,
+            Json(Ok(TransferResponseBody {
+                data: cash_transfer,
+            })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn approve_loan<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    staff: Authorized<BranchManagerAccess>,
+    Path((branch_id, loan_id)): Path<(Uuid, Uuid)>,
+) -> (StatusCode, Json<Result<LoanApprovalResponseBody, String>>) {
+    match treasury::approve_loan(&bank_web.pool, &staff.audit(), branch_id, loan_id).await {
+        Ok(approval) => (
+            StatusCode::OK,
+            Json(Ok(LoanApprovalResponseBody { data: approval })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn positions<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _staff: Authorized<OversightAccess>,
+    Path(branch_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<PositionsResponseBody, String>>) {
+    match treasury::get_cash_positions(&bank_web.pool, branch_id).await {
+        Ok(positions) => (
+            StatusCode::OK,
+            Json(Ok(PositionsResponseBody { data: positions })),
+        ),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+pub async fn coverage<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _staff: Authorized<AuditorAccess>,
+    Path(bank_id): Path<Uuid>,
+) -> (StatusCode, Json<Result<CoverageResponseBody, String>>) {
+    match treasury::get_reserve_coverage(&bank_web.pool, bank_id).await {
+        Ok(coverage) => (
+            StatusCode::OK,
+            Json(Ok(CoverageResponseBody { data: coverage })),
+        ),
+        Err(err) => error_response(err.into()),
+    }
+}
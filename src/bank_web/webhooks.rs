@@ -0,0 +1,137 @@
+use super::auth::{Authorized, BankAdminAccess};
+use super::{error_response, BankWeb};
+use crate::bank::accounts::AccountService;
+use crate::bank::models::types::WebhookDeliveryStatus;
+use crate::bank::models::webhooks::{
+    self, RegisteredWebhookEndpoint, WebhookDelivery, WebhookEndpoint,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EndpointRequestData {
+    pub url: String,
+    pub event_types: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EndpointRequestBody {
+    pub webhook_endpoint: EndpointRequestData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RegisteredEndpointResponseBody {
+    pub data: RegisteredWebhookEndpoint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EndpointResponseBody {
+    pub data: WebhookEndpoint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EndpointsResponseBody {
+    pub data: Vec<WebhookEndpoint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DeliveriesQuery {
+    pub status: Option<WebhookDeliveryStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DeliveryResponseBody {
+    pub data: WebhookDelivery,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DeliveriesResponseBody {
+    pub data: Vec<WebhookDelivery>,
+}
+
+pub async fn create_endpoint<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    admin: Authorized<BankAdminAccess>,
+    Path((bank_id, client_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<EndpointRequestBody>,
+) -> (StatusCode, Json<Result<RegisteredEndpointResponseBody, String>>) {
+    let data = body.webhook_endpoint;
+    match webhooks::create_endpoint(
+        &bank_web.pool,
+        &admin.audit(),
+        bank_id,
+        client_id,
+        data.url,
+        data.event_types,
+    )
+    .await
+    {
+        Ok(endpoint) => (
+            StatusCode::CREATED,
+            Json(Ok(RegisteredEndpointResponseBody { data: endpoint })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn endpoints<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _admin: Authorized<BankAdminAccess>,
+    Path((bank_id, client_id)): Path<(Uuid, Uuid)>,
+) -> (StatusCode, Json<Result<EndpointsResponseBody, String>>) {
+    match webhooks::get_endpoints(&bank_web.pool, bank_id, client_id).await {
+        Ok(endpoints) => (
+            StatusCode::OK,
+            Json(Ok(EndpointsResponseBody { data: endpoints })),
+        ),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+pub async fn disable_endpoint<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    admin: Authorized<BankAdminAccess>,
+    Path((bank_id, endpoint_id)): Path<(Uuid, Uuid)>,
+) -> (StatusCode, Json<Result<EndpointResponseBody, String>>) {
+    match webhooks::disable_endpoint(&bank_web.pool, &admin.audit(), bank_id, endpoint_id).await {
+        Ok(endpoint) => (
+            StatusCode::OK,
+            Json(Ok(EndpointResponseBody { data: endpoint })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn deliveries<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    _admin: Authorized<BankAdminAccess>,
+    Path((bank_id, endpoint_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<DeliveriesQuery>,
+) -> (StatusCode, Json<Result<DeliveriesResponseBody, String>>) {
+    match webhooks::get_deliveries(&bank_web.pool, bank_id, endpoint_id, query.status).await {
+        Ok(deliveries) => (
+            StatusCode::OK,
+            Json(Ok(DeliveriesResponseBody { data: deliveries })),
+        ),
+        Err(err) => error_response(err.into()),
+    }
+}
+
+pub async fn replay<T: AccountService>(
+    State(bank_web): State<BankWeb<T>>,
+    admin: Authorized<BankAdminAccess>,
+    Path((bank_id, delivery_id)): Path<(Uuid, Uuid)>,
+) -> (StatusCode, Json<Result<DeliveryResponseBody, String>>) {
+    match webhooks::replay_delivery(&bank_web.pool, &admin.audit(), bank_id, delivery_id).await {
+        Ok(delivery) => (
+            StatusCode::ACCEPTED,
+            Json(Ok(DeliveryResponseBody { data: delivery })),
+        ),
+        Err(err) => error_response(err),
+    }
+}
@@ -32,8 +32,33 @@ async fn main() {
         .await
         .expect("failed to run sqlx migrations");
 
+    if let Ok(path) = std::env::var("HOLIDAYS_PATH") {
+        match bank::models::calendar::load_holidays_file(&pool, std::path::Path::new(&path)).await {
+            Ok(loaded) => tracing::info!("loaded {} holidays", loaded),
+            Err(err) => tracing::error!("holidays could not be loaded: {}", err),
+        }
+    }
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("eod") {
+        let succeeded = run_eod_command(&pool, &args[1..]).await;
+        std::process::exit(if succeeded { 0 } else { 1 });
+    }
+
+    tokio::spawn(run_daily_jobs(pool.clone()));
+    tokio::spawn(run_standing_order_scheduler(pool.clone()));
+    tokio::spawn(run_clearing_cycles(pool.clone()));
+    let sinks =
+        bank::models::event_sinks::from_env(&pool).expect("invalid outbox sink configuration");
+    tokio::spawn(run_outbox_relay(pool.clone(), sinks));
+    tokio::spawn(run_webhook_dispatcher(pool.clone()));
+    if let Ok(path) = std::env::var("WATCHLIST_PATH") {
+        tokio::spawn(run_watchlist_refresh(pool.clone(), path.into()));
+    }
+
     let account_service = bank::accounts::BankService::default();
-    let router = BankWeb::new(pool, account_service).into_router();
+    let jwt = bank::models::auth::JwtConfig::from_env();
+    let router = BankWeb::new(pool, account_service, jwt).into_router();
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 4000));
     tracing::info!("listening on http://{}", addr);
@@ -44,6 +69,260 @@ async fn main() {
         .expect("failed to serve");
 }
 
+// Carries out payment batches due today, then runs the end-of-day steps for every business
+// date of each bank's calendar still waiting for them and files any currency transaction
+// reports not yet exported, once a day.
+async fn run_daily_jobs(pool: PgPool) {
+    let mut interval = tokio::time::interval(Duration::from_secs(24 * 60 * 60));
+
+    loop {
+        interval.tick().await;
+
+        let today = chrono::Utc::now().naive_utc().date();
+        match bank::models::payment_batches::process_due_batches(&pool, None, today).await {
+            Ok(0) => {}
+            Ok(processed) => tracing::info!("processed {} payment batches", processed),
+            Err(err) => tracing::error!("payment batch processing failed: {}", err),
+        }
+
+        match bank::models::eod::run_all_due(&pool, today, false).await {
+            Ok(results) => {
+                for (bank_id, business_date, result) in results {
+                    log_eod_results(business_date, &[(bank_id, result)]);
+                }
+            }
+            Err(err) => tracing::error!("end of day run failed: {}", err),
+        }
+        export_currency_transaction_reports(&pool).await;
+    }
+}
+
+// Returns whether every bank finished all of its steps
+fn log_eod_results(
+    business_date: chrono::NaiveDate,
+    results: &[(uuid::Uuid, bank::models::eod::BankRunResult)],
+) -> bool {
+    use bank::models::eod::StepOutcome;
+
+    let mut succeeded = true;
+    for (bank_id, result) in results {
+        match result {
+            Ok(Some(reports)) => {
+                for report in reports {
+                    tracing::info!(
+                        "end of day {} bank {}: {} {:?}{}",
+                        business_date,
+                        bank_id,
+                        report.step,
+                        report.outcome,
+                        report
+                            .processed
+                            .map(|processed| format!(", {} processed", processed))
+                            .or_else(|| report.error.as_ref().map(|error| format!(": {}", error)))
+                            .unwrap_or_default()
+                    );
+                }
+                succeeded &= reports.iter().all(|report| {
+                    !matches!(report.outcome, StepOutcome::Failed | StepOutcome::NotRun)
+                });
+            }
+            Ok(None) => {
+                tracing::warn!(
+                    "end of day {} bank {}: already running elsewhere",
+                    business_date,
+                    bank_id
+                );
+                succeeded = false;
+            }
+            Err(err) => {
+                tracing::error!("end of day {} bank {} failed: {}", business_date, bank_id, err);
+                succeeded = false;
+            }
+        }
+    }
+    succeeded
+}
+
+// `eod [--date YYYY-MM-DD] [--bank BANK_ID] [--dry-run]`. Defaults to every bank and, for
+// each, the business dates still waiting for their end of day. Running it again for the
+// same date resumes after the last completed step.
+async fn run_eod_command(pool: &PgPool, args: &[String]) -> bool {
+    const USAGE: &str = "usage: eod [--date YYYY-MM-DD] [--bank BANK_ID] [--dry-run]";
+
+    let today = chrono::Utc::now().naive_utc().date();
+    let mut business_date = None;
+    let mut bank_id = None;
+    let mut dry_run = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--date" => match args.next().map(|value| value.parse()) {
+                Some(Ok(date)) => business_date = Some(date),
+                _ => {
+                    eprintln!("{}", USAGE);
+                    return false;
+                }
+            },
+            "--bank" => match args.next().map(|value| uuid::Uuid::parse_str(value)) {
+                Some(Ok(id)) => bank_id = Some(id),
+                _ => {
+                    eprintln!("{}", USAGE);
+                    return false;
+                }
+            },
+            "--dry-run" => dry_run = true,
+            _ => {
+                eprintln!("{}", USAGE);
+                return false;
+            }
+        }
+    }
+
+    let results = match (business_date, bank_id) {
+        (Some(business_date), Some(bank_id)) => Ok(vec![(
+            bank_id,
+            business_date,
+            bank::models::eod::run(pool, bank_id, business_date, dry_run).await,
+        )]),
+        (Some(business_date), None) => bank::models::eod::run_all(pool, business_date, dry_run)
+            .await
+            .map(|results| {
+                results
+                    .into_iter()
+                    .map(|(bank_id, result)| (bank_id, business_date, result))
+                    .collect()
+            }),
+        (None, Some(bank_id)) => bank::models::eod::run_due(pool, bank_id, today, dry_run)
+            .await
+            .map(|results| {
+                results
+                    .into_iter()
+                    .map(|(business_date, result)| (bank_id, business_date, result))
+                    .collect()
+            }),
+        (None, None) => bank::models::eod::run_all_due(pool, today, dry_run).await,
+    };
+
+    match results {
+        Ok(results) => {
+            let mut succeeded = true;
+            for (bank_id, business_date, result) in results {
+                succeeded &= log_eod_results(business_date, &[(bank_id, result)]);
+            }
+            succeeded
+        }
+        Err(err) => {
+            tracing::error!("end of day run failed: {}", err);
+            false
+        }
+    }
+}
+
+// Filings are only written when CTR_EXPORT_DIR is set; the reports stay queryable either way.
+async fn export_currency_transaction_reports(pool: &PgPool) {
+    if let Ok(directory) = std::env::var("CTR_EXPORT_DIR") {
+        let directory = std::path::Path::new(&directory);
+        match bank::models::ctr::export_filings(pool, directory).await {
+            Ok(0) => {}
+            Ok(files) => tracing::info!("wrote {} CTR filings", files),
+            Err(err) => tracing::error!("CTR export failed: {}", err),
+        }
+    }
+}
+
+// Executes due standing orders and their retries every minute.
+async fn run_standing_order_scheduler(pool: PgPool) {
+    let mut interval = tokio::time::interval(Duration::from_secs(60));
+
+    loop {
+        interval.tick().await;
+
+        let now = chrono::Utc::now().naive_utc();
+        match bank::models::standing_orders::run_due_standing_orders(&pool, None, now).await {
+            Ok(0) => {}
+            Ok(executed) => tracing::info!("executed {} standing orders", executed),
+            Err(err) => tracing::error!("standing order scheduler failed: {}", err),
+        }
+    }
+}
+
+// Settles queued interbank transfers once each configured cut-off has passed.
+async fn run_clearing_cycles(pool: PgPool) {
+    let mut interval = tokio::time::interval(Duration::from_secs(60));
+
+    loop {
+        interval.tick().await;
+
+        let now = chrono::Utc::now().naive_utc();
+        match bank::models::clearing::run_due_cycle(&pool, now).await {
+            Ok(None) => {}
+            Ok(Some(cycle)) => tracing::info!(
+                "clearing cycle {} settled {} items, returned {}",
+                cycle.cut_off_at,
+                cycle.settled_count,
+                cycle.returned_count
+            ),
+            Err(err) => tracing::error!("clearing cycle failed: {}", err),
+        }
+    }
+}
+
+// Delivers outbox events to the configured sinks, draining full batches back to back.
+async fn run_outbox_relay(
+    pool: PgPool,
+    sinks: Vec<Box<dyn bank::models::event_sinks::EventSink>>,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+
+    loop {
+        interval.tick().await;
+
+        loop {
+            match bank::models::outbox::relay_pending(&pool, &sinks).await {
+                Ok(delivered) if delivered as i64 == bank::models::outbox::RELAY_BATCH_SIZE => {}
+                Ok(_) => break,
+                Err(err) => {
+                    tracing::error!("outbox relay failed: {}", err);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+// Sends queued webhook deliveries and retries failed ones as they come due.
+async fn run_webhook_dispatcher(pool: PgPool) {
+    let client = bank::models::webhooks::http_client();
+    let mut interval = tokio::time::interval(Duration::from_secs(5));
+
+    loop {
+        interval.tick().await;
+
+        match bank::models::webhooks::dispatch_due(&pool, &client).await {
+            Ok(0) => {}
+            Ok(delivered) => tracing::info!("delivered {} webhooks", delivered),
+            Err(err) => tracing::error!("webhook dispatcher failed: {}", err),
+        }
+    }
+}
+
+// Reloads the sanctions watchlist when its file changes and re-screens existing
+// customers and beneficiaries against the new list.
+async fn run_watchlist_refresh(pool: PgPool, path: std::path::PathBuf) {
+    let mut interval = tokio::time::interval(Duration::from_secs(60 * 60));
+
+    loop {
+        interval.tick().await;
+
+        match bank::models::sanctions::refresh_watchlist(&pool, &path).await {
+            Ok(None) => {}
+            Ok(Some(flagged)) => tracing::info!("watchlist reloaded, {} subjects flagged", flagged),
+            Err(err) => tracing::error!("watchlist refresh failed: {}", err),
+        }
+    }
+}
+
 pub fn init_tracing() {
     use opentelemetry_otlp::WithExportConfig;
     use tracing_subscriber::prelude::*;